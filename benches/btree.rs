@@ -0,0 +1,76 @@
+// Compares `BTree` (minimum degree 6 and 32) against `TreeAVL` and `std::collections::BTreeMap`.
+// Run with `cargo bench --bench btree`.
+
+mod common;
+
+use common::{bench, random_values};
+use data_structures::{BTree, TreeAVL};
+use std::collections::BTreeMap;
+use std::hint::black_box;
+
+const SIZE: usize = 1_000_000;
+
+fn main() {
+    let values = random_values(SIZE);
+
+    bench("insert, BTree<u64, (), 6>", || {
+        let mut tree: BTree<u64, (), 6> = BTree::new();
+        for &value in &values {
+            tree.insert(value, ());
+        }
+        black_box(tree);
+    });
+    bench("insert, BTree<u64, (), 32>", || {
+        let mut tree: BTree<u64, (), 32> = BTree::new();
+        for &value in &values {
+            tree.insert(value, ());
+        }
+        black_box(tree);
+    });
+    bench("insert, TreeAVL<u64>", || {
+        let mut tree = TreeAVL::new();
+        for &value in &values {
+            tree.insert(value);
+        }
+        black_box(tree);
+    });
+    bench("insert, std BTreeMap<u64, ()>", || {
+        let mut tree = BTreeMap::new();
+        for &value in &values {
+            tree.insert(value, ());
+        }
+        black_box(tree);
+    });
+
+    let mut btree_6: BTree<u64, (), 6> = BTree::new();
+    let mut btree_32: BTree<u64, (), 32> = BTree::new();
+    let mut avl = TreeAVL::new();
+    let mut std_btree = BTreeMap::new();
+    for &value in &values {
+        btree_6.insert(value, ());
+        btree_32.insert(value, ());
+        avl.insert(value);
+        std_btree.insert(value, ());
+    }
+
+    bench("lookup, BTree<u64, (), 6>", || {
+        for value in &values {
+            black_box(btree_6.contains_key(value));
+        }
+    });
+    bench("lookup, BTree<u64, (), 32>", || {
+        for value in &values {
+            black_box(btree_32.contains_key(value));
+        }
+    });
+    bench("lookup, TreeAVL<u64>", || {
+        for &value in &values {
+            black_box(avl.contains(value));
+        }
+    });
+    bench("lookup, std BTreeMap<u64, ()>", || {
+        for value in &values {
+            black_box(std_btree.contains_key(value));
+        }
+    });
+}
@@ -0,0 +1,31 @@
+// Helpers shared by the benchmarks, each of them includes this module with `mod common;`.
+// Not every benchmark uses every helper.
+#![allow(dead_code)]
+
+use std::time::{Duration, Instant};
+
+const RUNS: u32 = 5;
+
+// xorshift64, so the benchmarks don't need a rand dependency.
+pub fn random_values(len: usize) -> Vec<u64> {
+    let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        })
+        .collect()
+}
+
+// Runs `f` a few times and reports the fastest run.
+pub fn bench<F: FnMut()>(name: &str, mut f: F) {
+    let mut best = Duration::MAX;
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        f();
+        best = best.min(start.elapsed());
+    }
+    println!("{:<50} {:>10.2} ms", name, best.as_secs_f64() * 1000.0);
+}
@@ -0,0 +1,60 @@
+// Compares `Heap` (binary and 4-ary) against `std::collections::BinaryHeap`.
+// Run with `cargo bench --bench heap`.
+
+mod common;
+
+use common::{bench, random_values};
+use data_structures::Heap;
+use std::collections::BinaryHeap;
+use std::hint::black_box;
+
+const SIZE: usize = 1_000_000;
+
+fn max_heap_comparator(a: &u64, b: &u64) -> bool {
+    a > b
+}
+
+fn main() {
+    let values = random_values(SIZE);
+
+    bench("push + pop, Heap<u64, 2>", || {
+        let mut heap: Heap<u64, 2> = Heap::new_d_ary(max_heap_comparator);
+        for &value in &values {
+            heap.add(value);
+        }
+        while let Some(value) = heap.remove() {
+            black_box(value);
+        }
+    });
+    bench("push + pop, Heap<u64, 4>", || {
+        let mut heap: Heap<u64, 4> = Heap::new_d_ary(max_heap_comparator);
+        for &value in &values {
+            heap.add(value);
+        }
+        while let Some(value) = heap.remove() {
+            black_box(value);
+        }
+    });
+    bench("push + pop, std BinaryHeap<u64>", || {
+        let mut heap = BinaryHeap::new();
+        for &value in &values {
+            heap.push(value);
+        }
+        while let Some(value) = heap.pop() {
+            black_box(value);
+        }
+    });
+
+    bench("from_vec + into_sorted_vec, Heap<u64, 2>", || {
+        let heap: Heap<u64, 2> = Heap::from_vec_d_ary(values.clone(), max_heap_comparator);
+        black_box(heap.into_sorted_vec());
+    });
+    bench("from_vec + into_sorted_vec, Heap<u64, 4>", || {
+        let heap: Heap<u64, 4> = Heap::from_vec_d_ary(values.clone(), max_heap_comparator);
+        black_box(heap.into_sorted_vec());
+    });
+    bench("from + into_sorted_vec, std BinaryHeap", || {
+        let heap = BinaryHeap::from(values.clone());
+        black_box(heap.into_sorted_vec());
+    });
+}
@@ -0,0 +1,119 @@
+// Compares the arena `LinkedListSinglyTail` against the `Rc<RefCell<Node>>` layout it replaced
+// (copied below) and `std::collections::VecDeque`, on the queue operations `add_last`/`remove_first`.
+// Run with `cargo bench --bench linked_list`.
+
+mod common;
+
+use common::bench;
+use data_structures::{LinkedListSinglyTail, QueueLinkedList};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::hint::black_box;
+use std::rc::Rc;
+
+const SIZE: usize = 1_000_000;
+
+// The previous LinkedListSinglyTail, reduced to the operations being measured.
+type RcLink<T> = Option<Rc<RefCell<RcNode<T>>>>;
+struct RcNode<T> {
+    data: T,
+    next: RcLink<T>,
+}
+
+struct RcTailList<T> {
+    head: RcLink<T>,
+    tail: RcLink<T>,
+}
+
+impl<T> RcTailList<T> {
+    fn new() -> Self {
+        RcTailList {
+            head: None,
+            tail: None,
+        }
+    }
+
+    fn add_last(&mut self, data: T) {
+        let new_node = Rc::new(RefCell::new(RcNode { data, next: None }));
+        match &self.tail {
+            Some(tail) => tail.borrow_mut().next = Some(Rc::clone(&new_node)),
+            None => self.head = Some(Rc::clone(&new_node)),
+        }
+        self.tail = Some(new_node);
+    }
+
+    fn remove_first(&mut self) -> Option<T> {
+        self.head.take().map(|node| {
+            self.head = node.borrow_mut().next.take();
+            if self.head.is_none() {
+                self.tail = None;
+            }
+            match Rc::try_unwrap(node) {
+                Ok(refcell) => refcell.into_inner().data,
+                Err(_) => panic!("no other ref to node should exist"),
+            }
+        })
+    }
+}
+
+fn main() {
+    bench("fill then drain, LinkedListSinglyTail (arena)", || {
+        let mut list = LinkedListSinglyTail::new();
+        for value in 0..SIZE {
+            list.add_last(value);
+        }
+        while let Some(value) = list.remove_first() {
+            black_box(value);
+        }
+    });
+    bench("fill then drain, Rc<RefCell> tail list", || {
+        let mut list = RcTailList::new();
+        for value in 0..SIZE {
+            list.add_last(value);
+        }
+        while let Some(value) = list.remove_first() {
+            black_box(value);
+        }
+    });
+    bench("fill then drain, std VecDeque", || {
+        let mut deque = VecDeque::new();
+        for value in 0..SIZE {
+            deque.push_back(value);
+        }
+        while let Some(value) = deque.pop_front() {
+            black_box(value);
+        }
+    });
+
+    // A queue that stays around 1000 elements, so the arena keeps reusing freed slots.
+    bench("steady queue, QueueLinkedList (arena)", || {
+        let mut queue = QueueLinkedList::new();
+        for value in 0..SIZE {
+            queue.enqueue(value);
+            if value >= 1000 {
+                black_box(queue.dequeue());
+            }
+        }
+        black_box(queue.peek().copied());
+    });
+    bench("steady queue, Rc<RefCell> tail list", || {
+        let mut list = RcTailList::new();
+        for value in 0..SIZE {
+            list.add_last(value);
+            if value >= 1000 {
+                black_box(list.remove_first());
+            }
+        }
+        black_box(list.head.is_some());
+    });
+    bench("steady queue, std VecDeque", || {
+        let mut deque = VecDeque::new();
+        for value in 0..SIZE {
+            deque.push_back(value);
+            if value >= 1000 {
+                black_box(deque.pop_front());
+            }
+        }
+        black_box(deque.front().copied());
+    });
+}
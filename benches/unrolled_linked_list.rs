@@ -0,0 +1,98 @@
+// Compares `UnrolledLinkedList` (16 and 64 elements per node) against `DoublyLinkedList`
+// and `std::collections::VecDeque`.
+// Run with `cargo bench --bench unrolled_linked_list`.
+
+mod common;
+
+use common::{bench, random_values};
+use data_structures::{DoublyLinkedList, UnrolledLinkedList};
+use std::collections::VecDeque;
+use std::hint::black_box;
+
+const SIZE: usize = 1_000_000;
+// Positional inserts walk the list, so they run on a smaller one.
+const INSERTS: usize = 20_000;
+
+fn main() {
+    bench("push_back, UnrolledLinkedList<u64, 16>", || {
+        let mut list: UnrolledLinkedList<u64, 16> = UnrolledLinkedList::new();
+        for value in 0..SIZE as u64 {
+            list.push_back(value);
+        }
+        black_box(list);
+    });
+    bench("push_back, UnrolledLinkedList<u64, 64>", || {
+        let mut list: UnrolledLinkedList<u64, 64> = UnrolledLinkedList::new();
+        for value in 0..SIZE as u64 {
+            list.push_back(value);
+        }
+        black_box(list);
+    });
+    bench("push_back, DoublyLinkedList<u64>", || {
+        let mut list = DoublyLinkedList::new();
+        for value in 0..SIZE as u64 {
+            list.add_last(value);
+        }
+        black_box(list);
+    });
+    bench("push_back, std VecDeque<u64>", || {
+        let mut deque = VecDeque::new();
+        for value in 0..SIZE as u64 {
+            deque.push_back(value);
+        }
+        black_box(deque);
+    });
+
+    let unrolled_16: UnrolledLinkedList<u64, 16> = (0..SIZE as u64).collect();
+    let unrolled_64: UnrolledLinkedList<u64, 64> = (0..SIZE as u64).collect();
+    let mut doubly = DoublyLinkedList::new();
+    for value in 0..SIZE as u64 {
+        doubly.add_last(value);
+    }
+    let deque: VecDeque<u64> = (0..SIZE as u64).collect();
+
+    bench("iterate, UnrolledLinkedList<u64, 16>", || {
+        black_box(unrolled_16.iter().sum::<u64>());
+    });
+    bench("iterate, UnrolledLinkedList<u64, 64>", || {
+        black_box(unrolled_64.iter().sum::<u64>());
+    });
+    bench("iterate, DoublyLinkedList<u64>", || {
+        let mut sum = 0;
+        doubly.for_each(|value| sum += value);
+        black_box(sum);
+    });
+    bench("iterate, std VecDeque<u64>", || {
+        black_box(deque.iter().sum::<u64>());
+    });
+
+    let positions = random_values(INSERTS);
+    bench("random inserts, UnrolledLinkedList<u64, 16>", || {
+        let mut list: UnrolledLinkedList<u64, 16> = UnrolledLinkedList::new();
+        for (len, &position) in positions.iter().enumerate() {
+            list.insert(position as usize % (len + 1), position);
+        }
+        black_box(list);
+    });
+    bench("random inserts, UnrolledLinkedList<u64, 64>", || {
+        let mut list: UnrolledLinkedList<u64, 64> = UnrolledLinkedList::new();
+        for (len, &position) in positions.iter().enumerate() {
+            list.insert(position as usize % (len + 1), position);
+        }
+        black_box(list);
+    });
+    bench("random inserts, DoublyLinkedList<u64>", || {
+        let mut list = DoublyLinkedList::new();
+        for (len, &position) in positions.iter().enumerate() {
+            list.insert_at(position as usize % (len + 1), position);
+        }
+        black_box(list);
+    });
+    bench("random inserts, std VecDeque<u64>", || {
+        let mut deque = VecDeque::new();
+        for (len, &position) in positions.iter().enumerate() {
+            deque.insert(position as usize % (len + 1), position);
+        }
+        black_box(deque);
+    });
+}
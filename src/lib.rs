@@ -3,12 +3,45 @@ mod safe_rust;
 mod geeks_for_geeks;
 
 pub use safe_rust::binary_heap::Heap;
+pub use safe_rust::binomial_heap::BinomialHeap;
+pub use safe_rust::binomial_heap::Handle as BinomialHeapHandle;
+pub use safe_rust::btree::BTree;
+pub use safe_rust::disjoint_set::DisjointSet;
+pub use safe_rust::disjoint_set::DisjointSetMap;
+pub use safe_rust::fenwick_tree::FenwickTree;
 pub use safe_rust::hash_map::HashMap;
+pub use safe_rust::heap_utils::top_k;
+pub use safe_rust::heap_utils::KMerge;
+pub use safe_rust::interval_tree::IntervalTree;
+pub use safe_rust::linked_list_doubly::CursorMut;
 pub use safe_rust::linked_list_doubly::DoublyLinkedList;
 pub use safe_rust::linked_list_singly::LinkedList;
 pub use safe_rust::linked_list_singly_tail::LinkedListSinglyTail;
+pub use safe_rust::min_max_heap::MinMaxHeap;
+pub use safe_rust::ordered_set::OrderedSet;
+pub use safe_rust::pairing_heap::Handle as PairingHeapHandle;
+pub use safe_rust::pairing_heap::PairingHeap;
+pub use safe_rust::persistent_list::PersistentList;
+pub use safe_rust::priority_queue::PriorityQueue;
 pub use safe_rust::queue_with_linked_list::QueueLinkedList;
 pub use safe_rust::queue_with_vec::QueueVec;
+pub use safe_rust::radix_tree::RadixTree;
+pub use safe_rust::segment_tree::Assign;
+pub use safe_rust::segment_tree::AssignOrAdd;
+pub use safe_rust::segment_tree::Gcd;
+pub use safe_rust::segment_tree::Max;
+pub use safe_rust::segment_tree::Min;
+pub use safe_rust::segment_tree::Monoid;
+pub use safe_rust::segment_tree::RangeAdd;
+pub use safe_rust::segment_tree::SegmentTree;
+pub use safe_rust::segment_tree::Sum;
+pub use safe_rust::segment_tree::Tag;
+pub use safe_rust::skip_list::SkipList;
 pub use safe_rust::stack_with_linked_list::StackLinkedList;
 pub use safe_rust::stack_with_vec::StackVec;
+pub use safe_rust::treap::ImplicitTreap;
+pub use safe_rust::treap::Treap;
 pub use safe_rust::tree_avl::TreeAVL;
+pub use safe_rust::tree_red_black::TreeRedBlack;
+pub use safe_rust::trie::Trie;
+pub use safe_rust::unrolled_linked_list::UnrolledLinkedList;
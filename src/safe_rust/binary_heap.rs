@@ -1,12 +1,16 @@
-/// `Heap<T>`: A binary heap data structure implementation.
+use crate::safe_rust::priority_queue::PriorityQueue;
+
+/// `Heap<T, D>`: A d-ary heap data structure implementation, binary (`D = 2`) by default.
 ///
 /// This heap's order is determined by the provided comparator function.
 /// A binary heap is usually implemented using an array or a vector where
 /// each element has a defined position based on its insertion order. In the case of this
 /// `Heap<T>`, a `Vec<T>` is used.
 ///
-
-pub struct Heap<T> {
+/// Each node has `D` children, stored at `D * i + 1 ..= D * i + D`. A wider heap is shallower,
+/// so `add` does fewer comparisons, while `remove` compares more children per level but
+/// touches fewer cache lines. A 4-ary heap is usually a good trade-off for large heaps.
+pub struct Heap<T, const D: usize = 2> {
     items: Vec<T>,
     /// A comparator function to determine the order of elements in the heap.
     /// It returns `true` if the first argument should come before the second argument in the heap order.
@@ -15,102 +19,376 @@ pub struct Heap<T> {
 
 impl<T> Heap<T> {
     pub fn new(comparator: fn(&T, &T) -> bool) -> Self {
+        Self::new_d_ary(comparator)
+    }
+
+    pub fn from_vec(vec: Vec<T>, comparator: fn(&T, &T) -> bool) -> Self {
+        Self::from_vec_d_ary(vec, comparator)
+    }
+}
+
+impl<T, const D: usize> Heap<T, D> {
+    const VALID_ARITY: () = assert!(D >= 2, "a heap node needs at least two children");
+
+    /// Creates an empty heap with `D` children per node, e.g. `Heap::<u64, 4>::new_d_ary(cmp)`.
+    pub fn new_d_ary(comparator: fn(&T, &T) -> bool) -> Self {
+        let () = Self::VALID_ARITY;
         Heap {
             items: Vec::new(),
             comparator,
         }
     }
 
-    pub fn add(&mut self, value: T) {
-        self.items.push(value);
-        let added_value_index = self.items.len() - 1;
-        self.bubble_up(added_value_index);
+    pub fn from_vec_d_ary(vec: Vec<T>, comparator: fn(&T, &T) -> bool) -> Self {
+        let () = Self::VALID_ARITY;
+        let mut heap = Heap {
+            items: vec,
+            comparator,
+        };
+        heap.rebuild();
+        heap
     }
 
-    fn bubble_up(&mut self, child_index: usize) {
-        if let Some(parent_index) = Self::parent_index(child_index) {
-            self.bubble_up_from(child_index, parent_index);
+    pub fn add(&mut self, value: T) {
+        // Find the slot `value` belongs in by comparing it against its would-be ancestors,
+        // remembering the path. Nothing is moved yet.
+        let mut path = [0; usize::BITS as usize];
+        let mut depth = 0;
+        let mut index = self.items.len();
+        while let Some(parent_index) = Self::parent_index(index) {
+            if !(self.comparator)(&value, &self.items[parent_index]) {
+                break;
+            }
+            path[depth] = parent_index;
+            depth += 1;
+            index = parent_index;
         }
-    }
-
-    fn bubble_up_from(&mut self, child_index: usize, parent_index: usize) {
-        if (self.comparator)(&self.items[child_index], &self.items[parent_index]) {
-            self.items.swap(parent_index, child_index);
-            self.bubble_up(parent_index);
+        // Write `value` into its slot and move every ancestor it passed down one level,
+        // each with a single move. The last of them lands in the new slot at the end.
+        let mut carried = value;
+        for &ancestor_index in path[..depth].iter().rev() {
+            carried = std::mem::replace(&mut self.items[ancestor_index], carried);
         }
+        self.items.push(carried);
     }
 
     pub fn remove(&mut self) -> Option<T> {
+        let last = self.items.pop()?;
         if self.items.is_empty() {
-            return None;
+            return Some(last);
+        }
+        // `last` came from the bottom of the heap and almost always belongs near it again, so
+        // the hole left by the root is followed down the preferred children to a leaf first,
+        // and the slot for `last` is then searched back up from there. That takes one
+        // comparison per level less than comparing `last` against the preferred child on the way down.
+        let len = self.items.len();
+        let mut index = 0;
+        while let Some(child_index) = self.preferred_child_index(index, len) {
+            index = child_index;
         }
-        let root = self.items.swap_remove(0);
-        self.bubble_down(0);
+        while index > 0 && !(self.comparator)(&self.items[index], &last) {
+            index = Self::parent_index(index).unwrap();
+        }
+        Some(self.fill_hole(0, index, last))
+    }
 
-        Some(root)
+    // Writes `value` at `index` and moves every item on the path from there up to `hole_index`
+    // one level up, each with a single move. Returns the item that was at `hole_index`.
+    // `value` has to belong at `index`, i.e. the items moved up are preferable to it.
+    fn fill_hole(&mut self, hole_index: usize, mut index: usize, value: T) -> T {
+        let mut carried = value;
+        loop {
+            carried = std::mem::replace(&mut self.items[index], carried);
+            if index == hole_index {
+                return carried;
+            }
+            index = Self::parent_index(index).unwrap();
+        }
     }
 
+    // `add`, `remove` and `pop_push` hold the moving item outside of the vector, so they sift
+    // with a hole. The functions below move an item that is already stored in the vector,
+    // and as safe Rust can't leave a hole behind it, they swap it one level at a time.
     fn bubble_down(&mut self, parent_index: usize) {
-        let len = self.items.len();
-        let left_child_index = Self::left_child_index(parent_index);
-        let right_child_index = Self::right_child_index(parent_index);
-
-        // Determine which child should be compared with the parent.
-        // If the right child exists and is more preferable than the left child,
-        // the target becomes the right child, else it's the left child.
-        let target_index = if right_child_index < len
-            && (self.comparator)(
-                &self.items[right_child_index],
-                &self.items[left_child_index],
-            ) {
-            right_child_index
-        } else {
-            left_child_index
-        };
-        // If the target child index is within bounds and is more preferable than the parent,
-        // we swap the parent and target child. After swapping, we recursively
-        // bubble down.
-        if target_index < len
-            && (self.comparator)(&self.items[target_index], &self.items[parent_index])
-        {
+        self.bubble_down_within(parent_index, self.items.len());
+    }
+
+    // Same as `bubble_down`, but only the first `len` items are treated as part of the heap.
+    // Used by `into_sorted_vec`, where the tail of the vector already holds sorted items.
+    fn bubble_down_within(&mut self, mut parent_index: usize, len: usize) {
+        // If the most preferable child is also more preferable than the parent,
+        // swap them and keep going from the child's position.
+        while let Some(target_index) = self.preferred_child_index(parent_index, len) {
+            if !(self.comparator)(&self.items[target_index], &self.items[parent_index]) {
+                break;
+            }
             self.items.swap(parent_index, target_index);
-            self.bubble_down(target_index);
+            parent_index = target_index;
         }
     }
 
+    // Returns the index of the most preferable child of `parent_index` among the first `len` items.
+    fn preferred_child_index(&self, parent_index: usize, len: usize) -> Option<usize> {
+        let first_child_index = Self::first_child_index(parent_index);
+        if first_child_index >= len {
+            return None;
+        }
+        let last_child_index = std::cmp::min(first_child_index + D, len);
+        let mut target_index = first_child_index;
+        for child_index in (first_child_index + 1)..last_child_index {
+            if (self.comparator)(&self.items[child_index], &self.items[target_index]) {
+                target_index = child_index;
+            }
+        }
+        Some(target_index)
+    }
+
     pub fn peek(&self) -> Option<&T> {
         self.items.first()
     }
 
-    pub fn from_vec(vec: Vec<T>, comparator: fn(&T, &T) -> bool) -> Self {
-        let mut heap = Heap {
-            items: vec,
-            comparator,
-        };
-        for i in (0..heap.items.len()).rev() {
-            heap.bubble_down(i);
+    /// Returns a guard to the root item. If the item is mutated through the guard,
+    /// it is bubbled down to its new position when the guard is dropped.
+    pub fn peek_mut(&mut self) -> Option<PeekMut<'_, T, D>> {
+        if self.items.is_empty() {
+            return None;
         }
-        heap
+        Some(PeekMut {
+            heap: self,
+            mutated: false,
+        })
+    }
+
+    /// Removes the root and adds `value` with a single sift down, instead of a
+    /// `remove` followed by an `add`. On an empty heap `value` is simply added.
+    pub fn pop_push(&mut self, value: T) -> Option<T> {
+        if self.items.is_empty() {
+            self.add(value);
+            return None;
+        }
+        // Follow the preferred children down while they are preferable to `value`,
+        // then move them up into the root's hole.
+        let len = self.items.len();
+        let mut index = 0;
+        while let Some(child_index) = self.preferred_child_index(index, len) {
+            if !(self.comparator)(&self.items[child_index], &value) {
+                break;
+            }
+            index = child_index;
+        }
+        Some(self.fill_hole(0, index, value))
+    }
+
+    /// Moves every item of `other` into `self` and restores the heap property in O(n + m).
+    /// The comparator of `self` is kept.
+    pub fn meld(&mut self, mut other: Heap<T, D>) {
+        self.items.append(&mut other.items);
+        self.rebuild();
+    }
+
+    /// Alias of `pop_push`.
+    pub fn replace_top(&mut self, value: T) -> Option<T> {
+        self.pop_push(value)
     }
 
-    fn parent_index(child_index: usize) -> Option<usize> {
+    // Restores the heap property for the whole vector, bottom-up in O(n).
+    fn rebuild(&mut self) {
+        let len = self.items.len();
+        if len < 2 {
+            return;
+        }
+        // Leaves already satisfy the heap property, so start from the last parent.
+        let last_parent_index = Self::parent_index(len - 1).unwrap();
+        for i in (0..=last_parent_index).rev() {
+            self.bubble_down(i);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+    }
+
+    /// Returns an iterator visiting all items in the underlying vector, in arbitrary order.
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.items.iter()
+    }
+
+    /// Consumes the heap and returns the underlying vector, in arbitrary order.
+    pub fn into_vec(self) -> Vec<T> {
+        self.items
+    }
+
+    /// Consumes the heap and returns a vector sorted with an in-place heapsort.
+    ///
+    /// The root is repeatedly swapped to the end of the vector, so the item `remove` would
+    /// return first ends up last. For a max-heap this is ascending order, for a min-heap descending.
+    pub fn into_sorted_vec(mut self) -> Vec<T> {
+        let mut end = self.items.len();
+        while end > 1 {
+            end -= 1;
+            self.items.swap(0, end);
+            self.bubble_down_within(0, end);
+        }
+        self.items
+    }
+
+    /// Removes all items from the heap, yielding them in arbitrary order.
+    pub fn drain(&mut self) -> std::vec::Drain<'_, T> {
+        self.items.drain(..)
+    }
+
+    /// Removes all items from the heap, yielding them in heap order (the same order `remove` would).
+    /// Items that are not consumed are dropped together with the iterator.
+    pub fn drain_sorted(&mut self) -> DrainSorted<'_, T, D> {
+        DrainSorted { heap: self }
+    }
+
+    /// Keeps only the items for which `f` returns `true`, then restores the heap property.
+    pub fn retain<F>(&mut self, f: F)
+    where
+        F: FnMut(&T) -> bool,
+    {
+        let len = self.items.len();
+        self.items.retain(f);
+        if self.items.len() != len {
+            self.rebuild();
+        }
+    }
+
+    pub(crate) fn parent_index(child_index: usize) -> Option<usize> {
         if child_index == 0 {
             return None;
         }
-        Some((child_index - 1) / 2)
+        Some((child_index - 1) / D)
     }
 
-    fn left_child_index(parent_index: usize) -> usize {
-        (2 * parent_index) + 1
+    pub(crate) fn first_child_index(parent_index: usize) -> usize {
+        (D * parent_index) + 1
     }
+}
+
+fn max_heap_comparator<T: Ord>(a: &T, b: &T) -> bool {
+    a > b
+}
 
-    fn right_child_index(parent_index: usize) -> usize {
-        (2 * parent_index) + 2
+pub struct PeekMut<'a, T, const D: usize = 2> {
+    heap: &'a mut Heap<T, D>,
+    // Only bubble down on drop if the root could have changed.
+    mutated: bool,
+}
+
+impl<T, const D: usize> PeekMut<'_, T, D> {
+    /// Removes the peeked item from the heap and returns it.
+    pub fn pop(mut this: Self) -> T {
+        // The root is removed right away, so there is nothing left to bubble down on drop.
+        this.mutated = false;
+        this.heap
+            .remove()
+            .expect("PeekMut is only created for non-empty heaps")
     }
 }
+
+impl<T, const D: usize> std::ops::Deref for PeekMut<'_, T, D> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.heap.items[0]
+    }
+}
+
+impl<T, const D: usize> std::ops::DerefMut for PeekMut<'_, T, D> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.mutated = true;
+        &mut self.heap.items[0]
+    }
+}
+
+impl<T, const D: usize> Drop for PeekMut<'_, T, D> {
+    fn drop(&mut self) {
+        if self.mutated {
+            self.heap.bubble_down(0);
+        }
+    }
+}
+
+pub struct DrainSorted<'a, T, const D: usize = 2> {
+    heap: &'a mut Heap<T, D>,
+}
+
+impl<T, const D: usize> Iterator for DrainSorted<'_, T, D> {
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.heap.remove()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.heap.len();
+        (len, Some(len))
+    }
+}
+
+impl<T, const D: usize> Drop for DrainSorted<'_, T, D> {
+    fn drop(&mut self) {
+        self.heap.clear();
+    }
+}
+
+impl<T, const D: usize> Extend<T> for Heap<T, D> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.items.reserve(iter.size_hint().0);
+        for value in iter {
+            self.add(value);
+        }
+    }
+}
+
+/// Collecting into a `Heap` builds a max-heap, the same ordering as `std::collections::BinaryHeap`.
+/// Use `Heap::from_vec` to pick a different comparator.
+impl<T: Ord, const D: usize> FromIterator<T> for Heap<T, D> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Heap::from_vec_d_ary(iter.into_iter().collect(), max_heap_comparator::<T>)
+    }
+}
+
+impl<T, const D: usize> PriorityQueue<T> for Heap<T, D> {
+    type Peek<'a>
+        = &'a T
+    where
+        Self: 'a;
+
+    fn push(&mut self, value: T) {
+        self.add(value);
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        self.remove()
+    }
+
+    fn peek(&self) -> Option<Self::Peek<'_>> {
+        Heap::peek(self)
+    }
+
+    fn len(&self) -> usize {
+        Heap::len(self)
+    }
+
+    fn meld(&mut self, other: Self) {
+        Heap::meld(self, other);
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::Heap;
+    use super::{Heap, PeekMut};
+    use crate::safe_rust::test_utils::scrambled_with_duplicates;
 
     fn max_heap_comparator(a: &i32, b: &i32) -> bool {
         a > b
@@ -257,4 +535,232 @@ mod tests {
         heap.add(1);
         assert_eq!(heap.peek(), Some(&1));
     }
+
+    #[test]
+    fn len_and_is_empty() {
+        let mut heap = Heap::new(max_heap_comparator);
+        assert!(heap.is_empty());
+        heap.add(1);
+        heap.add(2);
+        assert_eq!(heap.len(), 2);
+        heap.clear();
+        assert!(heap.is_empty());
+        assert_eq!(heap.remove(), None);
+    }
+
+    #[test]
+    fn iter_visits_every_item() {
+        let heap = Heap::from_vec(vec![4, 1, 3, 2], max_heap_comparator);
+        let mut items: Vec<i32> = heap.iter().copied().collect();
+        items.sort();
+        assert_eq!(items, vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn into_vec_keeps_every_item() {
+        let heap = Heap::from_vec(vec![4, 1, 3, 2], max_heap_comparator);
+        let mut items = heap.into_vec();
+        items.sort();
+        assert_eq!(items, vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn into_sorted_vec_max_heap() {
+        let heap = Heap::from_vec(vec![5, 1, 8, 3, 2, 8, 7], max_heap_comparator);
+        assert_eq!(heap.into_sorted_vec(), vec![1, 2, 3, 5, 7, 8, 8]);
+    }
+
+    #[test]
+    fn into_sorted_vec_min_heap() {
+        let heap = Heap::from_vec(vec![5, 1, 8, 3, 2], min_heap_comparator);
+        assert_eq!(heap.into_sorted_vec(), vec![8, 5, 3, 2, 1]);
+    }
+
+    #[test]
+    fn into_sorted_vec_empty() {
+        let heap = Heap::new(max_heap_comparator);
+        assert!(heap.into_sorted_vec().is_empty());
+    }
+
+    #[test]
+    fn drain_empties_heap() {
+        let mut heap = Heap::from_vec(vec![3, 1, 2], max_heap_comparator);
+        let mut drained: Vec<i32> = heap.drain().collect();
+        drained.sort();
+        assert_eq!(drained, vec![1, 2, 3]);
+        assert!(heap.is_empty());
+    }
+
+    #[test]
+    fn drain_sorted_in_heap_order() {
+        let mut heap = Heap::from_vec(vec![3, 1, 4, 1, 5], min_heap_comparator);
+        let drained: Vec<i32> = heap.drain_sorted().collect();
+        assert_eq!(drained, vec![1, 1, 3, 4, 5]);
+        assert!(heap.is_empty());
+    }
+
+    #[test]
+    fn drain_sorted_partially_consumed() {
+        let mut heap = Heap::from_vec(vec![3, 1, 4, 1, 5], max_heap_comparator);
+        let mut drain = heap.drain_sorted();
+        assert_eq!(drain.next(), Some(5));
+        assert_eq!(drain.size_hint(), (4, Some(4)));
+        drop(drain);
+        assert!(heap.is_empty());
+    }
+
+    #[test]
+    fn retain_restores_order() {
+        let mut heap = Heap::from_vec((1..=10).collect(), max_heap_comparator);
+        heap.retain(|x| x % 2 == 1);
+        assert_eq!(heap.len(), 5);
+        assert_eq!(heap.remove(), Some(9));
+        assert_eq!(heap.remove(), Some(7));
+        assert_eq!(heap.remove(), Some(5));
+    }
+
+    #[test]
+    fn extend() {
+        let mut heap = Heap::new(min_heap_comparator);
+        heap.add(5);
+        heap.extend(vec![3, 8, 1]);
+        assert_eq!(heap.len(), 4);
+        assert_eq!(heap.remove(), Some(1));
+        assert_eq!(heap.remove(), Some(3));
+    }
+
+    #[test]
+    fn collect_builds_max_heap() {
+        let heap: Heap<i32> = vec![2, 9, 4].into_iter().collect();
+        assert_eq!(heap.peek(), Some(&9));
+    }
+
+    #[test]
+    fn peek_mut_empty_heap() {
+        let mut heap: Heap<i32> = Heap::new(max_heap_comparator);
+        assert!(heap.peek_mut().is_none());
+    }
+
+    #[test]
+    fn peek_mut_decrease_root() {
+        let mut heap = Heap::from_vec(vec![10, 7, 5, 3], max_heap_comparator);
+        *heap.peek_mut().unwrap() = 1;
+        assert_eq!(heap.remove(), Some(7));
+        assert_eq!(heap.remove(), Some(5));
+        assert_eq!(heap.remove(), Some(3));
+        assert_eq!(heap.remove(), Some(1));
+    }
+
+    #[test]
+    fn peek_mut_increase_root_stays() {
+        let mut heap = Heap::from_vec(vec![10, 7, 5], max_heap_comparator);
+        *heap.peek_mut().unwrap() += 5;
+        assert_eq!(heap.peek(), Some(&15));
+    }
+
+    #[test]
+    fn peek_mut_read_only() {
+        let mut heap = Heap::from_vec(vec![10, 7, 5], min_heap_comparator);
+        assert_eq!(*heap.peek_mut().unwrap(), 5);
+        assert_eq!(heap.len(), 3);
+    }
+
+    #[test]
+    fn peek_mut_pop() {
+        let mut heap = Heap::from_vec(vec![10, 7, 5], max_heap_comparator);
+        let top = heap.peek_mut().unwrap();
+        assert_eq!(PeekMut::pop(top), 10);
+        assert_eq!(heap.peek(), Some(&7));
+        assert_eq!(heap.len(), 2);
+    }
+
+    #[test]
+    fn pop_push() {
+        let mut heap = Heap::from_vec(vec![3, 8, 5], min_heap_comparator);
+        assert_eq!(heap.pop_push(9), Some(3));
+        assert_eq!(heap.pop_push(1), Some(5));
+        assert_eq!(heap.remove(), Some(1));
+        assert_eq!(heap.remove(), Some(8));
+        assert_eq!(heap.remove(), Some(9));
+    }
+
+    #[test]
+    fn pop_push_empty_heap() {
+        let mut heap = Heap::new(max_heap_comparator);
+        assert_eq!(heap.pop_push(4), None);
+        assert_eq!(heap.peek(), Some(&4));
+    }
+
+    #[test]
+    fn replace_top() {
+        let mut heap = Heap::from_vec(vec![3, 8, 5], max_heap_comparator);
+        assert_eq!(heap.replace_top(1), Some(8));
+        assert_eq!(heap.peek(), Some(&5));
+    }
+
+    #[test]
+    fn remove_order_matches_sort() {
+        let mut heap = Heap::new(min_heap_comparator);
+        heap.extend(scrambled_with_duplicates(500));
+        let mut expected = scrambled_with_duplicates(500);
+        expected.sort();
+        let removed: Vec<i32> = heap.drain_sorted().collect();
+        assert_eq!(removed, expected);
+    }
+
+    #[test]
+    fn four_ary_heap() {
+        let mut heap: Heap<i32, 4> = Heap::new_d_ary(max_heap_comparator);
+        for value in scrambled_with_duplicates(500) {
+            heap.add(value);
+        }
+        let mut expected = scrambled_with_duplicates(500);
+        expected.sort_by(|a, b| b.cmp(a));
+        let removed: Vec<i32> = heap.drain_sorted().collect();
+        assert_eq!(removed, expected);
+    }
+
+    #[test]
+    fn three_ary_heap_from_vec() {
+        let heap: Heap<i32, 3> =
+            Heap::from_vec_d_ary(scrambled_with_duplicates(500), min_heap_comparator);
+        let mut expected = scrambled_with_duplicates(500);
+        expected.sort_by(|a, b| b.cmp(a));
+        assert_eq!(heap.into_sorted_vec(), expected);
+    }
+
+    #[test]
+    fn pop_push_matches_remove_then_add() {
+        let mut heap: Heap<i32, 3> = Heap::new_d_ary(min_heap_comparator);
+        let mut expected: Heap<i32, 3> = Heap::new_d_ary(min_heap_comparator);
+        heap.extend(scrambled_with_duplicates(200));
+        expected.extend(scrambled_with_duplicates(200));
+        for value in scrambled_with_duplicates(300) {
+            let removed = expected.remove();
+            expected.add(value);
+            assert_eq!(heap.pop_push(value), removed);
+        }
+        let drained: Vec<i32> = heap.drain_sorted().collect();
+        let expected: Vec<i32> = expected.drain_sorted().collect();
+        assert_eq!(drained, expected);
+    }
+
+    #[test]
+    fn meld() {
+        let mut first = Heap::from_vec(vec![5, 1, 9], min_heap_comparator);
+        let second = Heap::from_vec(vec![4, 0, 7], min_heap_comparator);
+        first.meld(second);
+        assert_eq!(first.len(), 6);
+        assert_eq!(first.into_sorted_vec(), vec![9, 7, 5, 4, 1, 0]);
+    }
+
+    #[test]
+    fn four_ary_heap_peek_mut_and_retain() {
+        let mut heap: Heap<i32, 4> = (0..100).collect();
+        *heap.peek_mut().unwrap() = -1;
+        heap.retain(|x| x % 3 != 0);
+        assert_eq!(heap.remove(), Some(98));
+        assert_eq!(heap.remove(), Some(97));
+        assert_eq!(heap.remove(), Some(95));
+    }
 }
@@ -0,0 +1,384 @@
+/*
+ * Binomial Heap
+ * A forest of heap ordered binomial trees, at most one per degree. A tree of degree k has
+ * 2^k nodes, so the forest mirrors the binary representation of the number of items:
+ * - meld adds two forests like binary numbers, linking equal degree trees as the "carry", O(log n).
+ * - push melds a single node tree, amortized O(1).
+ * - pop removes the root with the most preferable item and melds its children back, O(log n).
+ * - decrease_key moves the item up its tree, O(log n).
+ *
+ * Items live in their own `Entry` instead of the tree node: decrease_key swaps entries between
+ * a node and its parent, and a `Handle` (a `Weak` to the entry) keeps following its item.
+ */
+use std::cell::{Ref, RefCell};
+use std::rc::{Rc, Weak};
+
+use crate::safe_rust::priority_queue::PriorityQueue;
+
+type EntryLink<T> = Rc<RefCell<Entry<T>>>;
+struct Entry<T> {
+    value: T,
+    node: Weak<RefCell<Node<T>>>,
+}
+
+type Link<T> = Rc<RefCell<Node<T>>>;
+struct Node<T> {
+    entry: EntryLink<T>,
+    parent: Weak<RefCell<Node<T>>>,
+    // The child at index i is the root of a tree of degree i.
+    children: Vec<Link<T>>,
+}
+
+pub struct Handle<T>(Weak<RefCell<Entry<T>>>);
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        Handle(Weak::clone(&self.0))
+    }
+}
+
+pub struct BinomialHeap<T> {
+    // `roots[k]` is the tree of degree k, if there is one.
+    roots: Vec<Option<Link<T>>>,
+    // The entry with the most preferable item, kept so `peek` is O(1).
+    top: Option<EntryLink<T>>,
+    size: usize,
+    /// Returns `true` if the first argument should come before the second argument in the heap order.
+    comparator: fn(&T, &T) -> bool,
+}
+
+impl<T> BinomialHeap<T> {
+    pub fn new(comparator: fn(&T, &T) -> bool) -> Self {
+        BinomialHeap {
+            roots: Vec::new(),
+            top: None,
+            size: 0,
+            comparator,
+        }
+    }
+
+    /// Adds `value` and returns a handle that can later be passed to `decrease_key`.
+    pub fn push(&mut self, value: T) -> Handle<T> {
+        let entry = Rc::new(RefCell::new(Entry {
+            value,
+            node: Weak::new(),
+        }));
+        let node = Rc::new(RefCell::new(Node {
+            entry: Rc::clone(&entry),
+            parent: Weak::new(),
+            children: Vec::new(),
+        }));
+        entry.borrow_mut().node = Rc::downgrade(&node);
+        let handle = Handle(Rc::downgrade(&entry));
+
+        self.add_tree(node, 0);
+        self.size += 1;
+        if self
+            .top
+            .as_ref()
+            .is_none_or(|top| self.precedes(&entry, top))
+        {
+            self.top = Some(entry);
+        }
+        handle
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        let top = self.top.take()?;
+        let top_node = top
+            .borrow()
+            .node
+            .upgrade()
+            .expect("Pop: the top entry must belong to a node");
+        let degree = top_node.borrow().children.len();
+        let root = self.roots[degree]
+            .take()
+            .expect("Pop: the top entry must be in a root");
+        drop(top_node);
+        self.trim_roots();
+
+        let node = match Rc::try_unwrap(root) {
+            Ok(refcell) => refcell.into_inner(),
+            Err(_) => panic!("Pop: at this point no other ref to the root should exist."),
+        };
+        for (degree, child) in node.children.into_iter().enumerate() {
+            child.borrow_mut().parent = Weak::new();
+            self.add_tree(child, degree);
+        }
+        drop(top);
+        self.size -= 1;
+        self.update_top();
+
+        let value = match Rc::try_unwrap(node.entry) {
+            Ok(refcell) => refcell.into_inner().value,
+            Err(_) => panic!("Pop: at this point no other ref to the entry should exist."),
+        };
+        Some(value)
+    }
+
+    pub fn peek(&self) -> Option<Ref<'_, T>> {
+        self.top
+            .as_ref()
+            .map(|entry| Ref::map(entry.borrow(), |entry| &entry.value))
+    }
+
+    /// Moves every item of `other` into `self` in O(log n). Handles from `other` stay valid for `self`.
+    pub fn meld(&mut self, mut other: BinomialHeap<T>) {
+        for (degree, root) in other.roots.drain(..).enumerate() {
+            if let Some(root) = root {
+                self.add_tree(root, degree);
+            }
+        }
+        self.size += other.size;
+        self.update_top();
+    }
+
+    /// Replaces the item behind `handle` with a value that is at least as preferable,
+    /// moving it towards the root of its tree.
+    ///
+    /// Returns `Err(value)` if the item was already popped, or if `value` is less preferable
+    /// than the current item. The handle must come from this heap or from one melded into it.
+    pub fn decrease_key(&mut self, handle: &Handle<T>, value: T) -> Result<(), T> {
+        let entry = match handle.0.upgrade() {
+            Some(entry) => entry,
+            None => return Err(value),
+        };
+        if (self.comparator)(&entry.borrow().value, &value) {
+            return Err(value);
+        }
+        entry.borrow_mut().value = value;
+
+        let mut node = entry
+            .borrow()
+            .node
+            .upgrade()
+            .expect("decrease_key: every entry belongs to a node");
+        loop {
+            let parent = match node.borrow().parent.upgrade() {
+                Some(parent) => parent,
+                None => break,
+            };
+            if !self.precedes(&node.borrow().entry, &parent.borrow().entry) {
+                break;
+            }
+            // Swap the entries rather than the nodes, so the tree shape stays untouched.
+            std::mem::swap(&mut node.borrow_mut().entry, &mut parent.borrow_mut().entry);
+            node.borrow().entry.borrow_mut().node = Rc::downgrade(&node);
+            parent.borrow().entry.borrow_mut().node = Rc::downgrade(&parent);
+            node = parent;
+        }
+
+        let top = self.top.as_ref().expect("decrease_key: heap is empty");
+        if self.precedes(&entry, top) {
+            self.top = Some(entry);
+        }
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.roots.clear();
+        self.top = None;
+        self.size = 0;
+    }
+
+    fn precedes(&self, first: &EntryLink<T>, second: &EntryLink<T>) -> bool {
+        (self.comparator)(&first.borrow().value, &second.borrow().value)
+    }
+
+    // Adds a tree of the given degree to the forest. While there already is a tree with
+    // the same degree, the two are linked into a tree one degree higher, like a binary carry.
+    fn add_tree(&mut self, tree: Link<T>, degree: usize) {
+        let mut carry = tree;
+        let mut degree = degree;
+        loop {
+            if degree == self.roots.len() {
+                self.roots.push(None);
+            }
+            match self.roots[degree].take() {
+                Some(existing) => {
+                    carry = self.link(existing, carry);
+                    degree += 1;
+                }
+                None => {
+                    self.roots[degree] = Some(carry);
+                    return;
+                }
+            }
+        }
+    }
+
+    // Links two trees of the same degree: the root with the less preferable item
+    // becomes the last child of the other.
+    fn link(&self, first: Link<T>, second: Link<T>) -> Link<T> {
+        let (parent, child) = if self.precedes(&second.borrow().entry, &first.borrow().entry) {
+            (second, first)
+        } else {
+            (first, second)
+        };
+        child.borrow_mut().parent = Rc::downgrade(&parent);
+        parent.borrow_mut().children.push(child);
+        parent
+    }
+
+    fn trim_roots(&mut self) {
+        while let Some(None) = self.roots.last() {
+            self.roots.pop();
+        }
+    }
+
+    fn update_top(&mut self) {
+        let mut top: Option<EntryLink<T>> = None;
+        for root in self.roots.iter().flatten() {
+            let entry = Rc::clone(&root.borrow().entry);
+            if top.as_ref().is_none_or(|top| self.precedes(&entry, top)) {
+                top = Some(entry);
+            }
+        }
+        self.top = top;
+    }
+}
+
+impl<T> PriorityQueue<T> for BinomialHeap<T> {
+    type Peek<'a>
+        = Ref<'a, T>
+    where
+        Self: 'a;
+
+    fn push(&mut self, value: T) {
+        BinomialHeap::push(self, value);
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        BinomialHeap::pop(self)
+    }
+
+    fn peek(&self) -> Option<Self::Peek<'_>> {
+        BinomialHeap::peek(self)
+    }
+
+    fn len(&self) -> usize {
+        BinomialHeap::len(self)
+    }
+
+    fn meld(&mut self, other: Self) {
+        BinomialHeap::meld(self, other);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn max_heap_comparator(a: &i32, b: &i32) -> bool {
+        a > b
+    }
+
+    fn min_heap_comparator(a: &i32, b: &i32) -> bool {
+        a < b
+    }
+
+    #[test]
+    fn push_and_pop_in_order() {
+        let mut heap = BinomialHeap::new(max_heap_comparator);
+        for value in [5, 1, 8, 3, 7, 2] {
+            heap.push(value);
+        }
+        assert_eq!(heap.len(), 6);
+        assert_eq!(*heap.peek().unwrap(), 8);
+        assert_eq!(heap.pop(), Some(8));
+        assert_eq!(heap.pop(), Some(7));
+        assert_eq!(heap.pop(), Some(5));
+        assert_eq!(heap.pop(), Some(3));
+        assert_eq!(heap.pop(), Some(2));
+        assert_eq!(heap.pop(), Some(1));
+        assert_eq!(heap.pop(), None);
+        assert!(heap.is_empty());
+    }
+
+    #[test]
+    fn forest_matches_binary_representation() {
+        let mut heap = BinomialHeap::new(min_heap_comparator);
+        for value in 0..11 {
+            heap.push(value);
+        }
+        // 11 = 0b1011
+        let present: Vec<bool> = heap.roots.iter().map(|root| root.is_some()).collect();
+        assert_eq!(present, vec![true, true, false, true]);
+    }
+
+    #[test]
+    fn meld() {
+        let mut first = BinomialHeap::new(min_heap_comparator);
+        let mut second = BinomialHeap::new(min_heap_comparator);
+        first.push(4);
+        first.push(9);
+        first.push(2);
+        let handle = second.push(6);
+        second.push(1);
+
+        first.meld(second);
+        assert_eq!(first.len(), 5);
+        assert_eq!(*first.peek().unwrap(), 1);
+        assert_eq!(first.decrease_key(&handle, 0), Ok(()));
+        assert_eq!(first.pop(), Some(0));
+        assert_eq!(first.pop(), Some(1));
+        assert_eq!(first.pop(), Some(2));
+        assert_eq!(first.pop(), Some(4));
+        assert_eq!(first.pop(), Some(9));
+        assert_eq!(first.pop(), None);
+    }
+
+    #[test]
+    fn decrease_key_moves_item_to_top() {
+        let mut heap = BinomialHeap::new(min_heap_comparator);
+        let handles: Vec<Handle<i32>> = (10..26).map(|value| heap.push(value)).collect();
+
+        assert_eq!(heap.decrease_key(&handles[15], 1), Ok(()));
+        assert_eq!(*heap.peek().unwrap(), 1);
+        assert_eq!(heap.decrease_key(&handles[9], 2), Ok(()));
+        assert_eq!(heap.pop(), Some(1));
+        assert_eq!(heap.pop(), Some(2));
+        assert_eq!(heap.pop(), Some(10));
+        assert_eq!(heap.len(), 13);
+    }
+
+    #[test]
+    fn decrease_key_rejects_worse_value() {
+        let mut heap = BinomialHeap::new(min_heap_comparator);
+        let handle = heap.push(5);
+        assert_eq!(heap.decrease_key(&handle, 6), Err(6));
+        assert_eq!(heap.pop(), Some(5));
+    }
+
+    #[test]
+    fn decrease_key_after_pop() {
+        let mut heap = BinomialHeap::new(min_heap_comparator);
+        let handle = heap.push(5);
+        heap.pop();
+        assert_eq!(heap.decrease_key(&handle, 1), Err(1));
+    }
+
+    #[test]
+    fn many_items_in_order() {
+        let mut heap = BinomialHeap::new(min_heap_comparator);
+        let handles: Vec<Handle<i32>> = (0..1000)
+            .map(|i| heap.push((i * 7919) % 1000 + 1000))
+            .collect();
+        for (i, handle) in handles.iter().enumerate().step_by(3) {
+            heap.decrease_key(handle, (i as i32 * 31) % 1000).unwrap();
+        }
+        let mut previous = i32::MIN;
+        while let Some(value) = heap.pop() {
+            assert!(previous <= value);
+            previous = value;
+        }
+    }
+}
@@ -0,0 +1,591 @@
+/*
+B-Tree
+ An ordered map that stores many keys per node, so a lookup touches O(log_B n) nodes and
+ walks each of them as a small sorted array instead of chasing one pointer per key like
+ TreeAVL does.
+
+ B is the minimum degree: every node except the root holds between B - 1 and 2B - 1 keys,
+ and an internal node with k keys has k + 1 children. All leaves are at the same depth.
+
+ Both insert and remove go down the tree in a single pass and fix nodes before entering them:
+ -insert splits a full child (2B - 1 keys) before descending, so there's always room to
+  push its middle key up.
+ -remove makes sure the child it descends into has at least B keys, by borrowing a key from a
+  sibling through the parent or, if both siblings are minimal, by merging with one of them.
+*/
+
+use std::cmp::Ordering;
+use std::mem;
+use std::ops::RangeBounds;
+
+use crate::safe_rust::ordered_set::{is_above_lower, is_below_upper};
+
+struct Node<K, V, const B: usize> {
+    keys: Vec<K>,
+    values: Vec<V>,
+    // Empty for leaves.
+    children: Vec<Node<K, V, B>>,
+}
+
+impl<K: Ord, V, const B: usize> Node<K, V, B> {
+    const MAX_KEYS: usize = 2 * B - 1;
+
+    fn new() -> Self {
+        Node {
+            keys: Vec::with_capacity(Self::MAX_KEYS),
+            values: Vec::with_capacity(Self::MAX_KEYS),
+            children: Vec::new(),
+        }
+    }
+
+    fn is_leaf(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    // Splits the full child at `index` in two, moving its middle key up into this node.
+    fn split_child(&mut self, index: usize) {
+        let child = &mut self.children[index];
+        let mut right = Node::new();
+        right.keys.extend(child.keys.drain(B..));
+        right.values.extend(child.values.drain(B..));
+        if !child.is_leaf() {
+            right.children = child.children.split_off(B);
+        }
+        let middle_key = child.keys.pop().unwrap();
+        let middle_value = child.values.pop().unwrap();
+        self.keys.insert(index, middle_key);
+        self.values.insert(index, middle_value);
+        self.children.insert(index + 1, right);
+    }
+
+    // Makes sure the child at `index` has at least B keys so a key can be removed from its subtree.
+    // Returns the index of the child that now covers the same keys, merging may move it left.
+    fn fill_child(&mut self, index: usize) -> usize {
+        if self.children[index].keys.len() >= B {
+            index
+        } else if index > 0 && self.children[index - 1].keys.len() >= B {
+            self.borrow_from_left(index);
+            index
+        } else if index + 1 < self.children.len() && self.children[index + 1].keys.len() >= B {
+            self.borrow_from_right(index);
+            index
+        } else if index + 1 < self.children.len() {
+            self.merge_children(index);
+            index
+        } else {
+            self.merge_children(index - 1);
+            index - 1
+        }
+    }
+
+    // Rotates the last key of the left sibling through this node into the child at `index`.
+    fn borrow_from_left(&mut self, index: usize) {
+        let (before, after) = self.children.split_at_mut(index);
+        let left = &mut before[index - 1];
+        let child = &mut after[0];
+        let key = mem::replace(&mut self.keys[index - 1], left.keys.pop().unwrap());
+        let value = mem::replace(&mut self.values[index - 1], left.values.pop().unwrap());
+        child.keys.insert(0, key);
+        child.values.insert(0, value);
+        if let Some(grandchild) = left.children.pop() {
+            child.children.insert(0, grandchild);
+        }
+    }
+
+    // Rotates the first key of the right sibling through this node into the child at `index`.
+    fn borrow_from_right(&mut self, index: usize) {
+        let (before, after) = self.children.split_at_mut(index + 1);
+        let child = &mut before[index];
+        let right = &mut after[0];
+        let key = mem::replace(&mut self.keys[index], right.keys.remove(0));
+        let value = mem::replace(&mut self.values[index], right.values.remove(0));
+        child.keys.push(key);
+        child.values.push(value);
+        if !right.is_leaf() {
+            child.children.push(right.children.remove(0));
+        }
+    }
+
+    // Merges the children at `index` and `index + 1` around the key between them.
+    // Both children have B - 1 keys, so the result has exactly 2B - 1.
+    fn merge_children(&mut self, index: usize) {
+        let right = self.children.remove(index + 1);
+        let key = self.keys.remove(index);
+        let value = self.values.remove(index);
+        let child = &mut self.children[index];
+        child.keys.push(key);
+        child.values.push(value);
+        child.keys.extend(right.keys);
+        child.values.extend(right.values);
+        child.children.extend(right.children);
+    }
+
+    fn remove(&mut self, key: &K) -> Option<(K, V)> {
+        match self.keys.binary_search(key) {
+            Ok(index) if self.is_leaf() => {
+                Some((self.keys.remove(index), self.values.remove(index)))
+            }
+            Ok(index) => {
+                // Replace the key with its predecessor or successor, taken from a child that can spare it.
+                if self.children[index].keys.len() >= B {
+                    let (key, value) = self.children[index].pop_last();
+                    Some(self.replace_entry(index, key, value))
+                } else if self.children[index + 1].keys.len() >= B {
+                    let (key, value) = self.children[index + 1].pop_first();
+                    Some(self.replace_entry(index, key, value))
+                } else {
+                    self.merge_children(index);
+                    self.children[index].remove(key)
+                }
+            }
+            Err(_) if self.is_leaf() => None,
+            Err(index) => {
+                let index = self.fill_child(index);
+                self.children[index].remove(key)
+            }
+        }
+    }
+
+    fn replace_entry(&mut self, index: usize, key: K, value: V) -> (K, V) {
+        (
+            mem::replace(&mut self.keys[index], key),
+            mem::replace(&mut self.values[index], value),
+        )
+    }
+
+    fn pop_first(&mut self) -> (K, V) {
+        if self.is_leaf() {
+            return (self.keys.remove(0), self.values.remove(0));
+        }
+        let index = self.fill_child(0);
+        self.children[index].pop_first()
+    }
+
+    fn pop_last(&mut self) -> (K, V) {
+        if self.is_leaf() {
+            return (self.keys.pop().unwrap(), self.values.pop().unwrap());
+        }
+        let index = self.fill_child(self.children.len() - 1);
+        self.children[index].pop_last()
+    }
+}
+
+pub struct BTree<K: Ord, V, const B: usize = 6> {
+    root: Node<K, V, B>,
+    len: usize,
+}
+
+impl<K: Ord, V, const B: usize> BTree<K, V, B> {
+    const VALID_DEGREE: () = assert!(
+        B >= 2,
+        "a B-tree node needs a minimum degree of at least two"
+    );
+
+    /// Creates an empty tree, the minimum degree comes from the type, e.g. `BTree::<u64, (), 32>::new()`.
+    pub fn new() -> Self {
+        let () = Self::VALID_DEGREE;
+        BTree {
+            root: Node::new(),
+            len: 0,
+        }
+    }
+
+    /// Inserts a key-value pair, returning the previous value if the key was already present.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        if self.root.keys.len() == Node::<K, V, B>::MAX_KEYS {
+            // The tree only grows in height here: the old root becomes the child of a new one.
+            let old_root = mem::replace(&mut self.root, Node::new());
+            self.root.children.push(old_root);
+            self.root.split_child(0);
+        }
+
+        let mut node = &mut self.root;
+        loop {
+            let mut index = match node.keys.binary_search(&key) {
+                Ok(index) => return Some(mem::replace(&mut node.values[index], value)),
+                Err(index) => index,
+            };
+            if node.is_leaf() {
+                node.keys.insert(index, key);
+                node.values.insert(index, value);
+                self.len += 1;
+                return None;
+            }
+            if node.children[index].keys.len() == Node::<K, V, B>::MAX_KEYS {
+                node.split_child(index);
+                match key.cmp(&node.keys[index]) {
+                    Ordering::Less => {}
+                    Ordering::Equal => return Some(mem::replace(&mut node.values[index], value)),
+                    Ordering::Greater => index += 1,
+                }
+            }
+            node = &mut node.children[index];
+        }
+    }
+
+    /// Removes a key, returning its value if it was present.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let removed = self.root.remove(key);
+        if self.root.keys.is_empty() && !self.root.is_leaf() {
+            // The root's last key was merged into its only child, the tree shrinks in height.
+            self.root = self.root.children.pop().unwrap();
+        }
+        removed.map(|(_, value)| {
+            self.len -= 1;
+            value
+        })
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let mut node = &self.root;
+        loop {
+            match node.keys.binary_search(key) {
+                Ok(index) => return Some(&node.values[index]),
+                Err(_) if node.is_leaf() => return None,
+                Err(index) => node = &node.children[index],
+            }
+        }
+    }
+
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let mut node = &mut self.root;
+        loop {
+            match node.keys.binary_search(key) {
+                Ok(index) => return Some(&mut node.values[index]),
+                Err(_) if node.is_leaf() => return None,
+                Err(index) => node = &mut node.children[index],
+            }
+        }
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Entry with the smallest key.
+    pub fn first(&self) -> Option<(&K, &V)> {
+        let mut node = &self.root;
+        while let Some(child) = node.children.first() {
+            node = child;
+        }
+        Some((node.keys.first()?, node.values.first()?))
+    }
+
+    /// Entry with the largest key.
+    pub fn last(&self) -> Option<(&K, &V)> {
+        let mut node = &self.root;
+        while let Some(child) = node.children.last() {
+            node = child;
+        }
+        Some((node.keys.last()?, node.values.last()?))
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn height(&self) -> usize {
+        let mut height = 1;
+        let mut node = &self.root;
+        while let Some(child) = node.children.first() {
+            height += 1;
+            node = child;
+        }
+        height
+    }
+
+    pub fn clear(&mut self) {
+        self.root = Node::new();
+        self.len = 0;
+    }
+
+    /// Returns an iterator over the entries in ascending key order.
+    pub fn iter(&self) -> Iter<'_, K, V, B> {
+        let mut iter = Iter {
+            stack: Vec::new(),
+            remaining: self.len,
+        };
+        let mut node = &self.root;
+        loop {
+            iter.stack.push((node, 0));
+            match node.children.first() {
+                Some(child) => node = child,
+                None => break,
+            }
+        }
+        iter
+    }
+
+    /// Returns an iterator over the entries whose key is within `range`, in ascending key order.
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> Range<'_, K, V, B> {
+        // Start from the path to the smallest key above the lower bound, every node on the
+        // stack resumes at its first key above the bound.
+        let mut iter = Iter {
+            stack: Vec::new(),
+            remaining: self.len,
+        };
+        let mut node = &self.root;
+        loop {
+            let index = node
+                .keys
+                .partition_point(|key| !is_above_lower(key, range.start_bound()));
+            iter.stack.push((node, index));
+            match node.children.get(index) {
+                Some(child) => node = child,
+                None => break,
+            }
+        }
+
+        // Find the largest key below the upper bound, the range ends after it.
+        let mut last = None;
+        let mut node = &self.root;
+        loop {
+            let index = node
+                .keys
+                .partition_point(|key| is_below_upper(key, range.end_bound()));
+            if index > 0 {
+                last = Some(&node.keys[index - 1]);
+            }
+            match node.children.get(index) {
+                Some(child) => node = child,
+                None => break,
+            }
+        }
+        Range { iter, last }
+    }
+}
+
+impl<K: Ord, V, const B: usize> Default for BTree<K, V, B> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// In-order iterator: every node on the stack is paired with the index of its next key,
+// the top of the stack is always a leaf or a node whose child before that key is done.
+pub struct Iter<'a, K, V, const B: usize> {
+    stack: Vec<(&'a Node<K, V, B>, usize)>,
+    remaining: usize,
+}
+
+impl<'a, K, V, const B: usize> Iterator for Iter<'a, K, V, B> {
+    type Item = (&'a K, &'a V);
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (node, index) = *self.stack.last()?;
+            if index == node.keys.len() {
+                self.stack.pop();
+                continue;
+            }
+            self.stack.last_mut().unwrap().1 += 1;
+            // Visit the subtree between this key and the next one before the next key.
+            let mut subtree = node.children.get(index + 1);
+            while let Some(child) = subtree {
+                self.stack.push((child, 0));
+                subtree = child.children.first();
+            }
+            self.remaining = self.remaining.saturating_sub(1);
+            return Some((&node.keys[index], &node.values[index]));
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, K: Ord, V, const B: usize> IntoIterator for &'a BTree<K, V, B> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V, B>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+pub struct Range<'a, K, V, const B: usize> {
+    iter: Iter<'a, K, V, B>,
+    // Largest key in the range, `None` once it has been yielded.
+    last: Option<&'a K>,
+}
+
+impl<'a, K: Ord, V, const B: usize> Iterator for Range<'a, K, V, B> {
+    type Item = (&'a K, &'a V);
+    fn next(&mut self) -> Option<Self::Item> {
+        let last = self.last?;
+        let (key, value) = self.iter.next()?;
+        match key.cmp(last) {
+            Ordering::Less => Some((key, value)),
+            Ordering::Equal => {
+                self.last = None;
+                Some((key, value))
+            }
+            // The lower bound is above the upper bound, the range is empty.
+            Ordering::Greater => {
+                self.last = None;
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::safe_rust::test_utils::scrambled;
+
+    // Checks key order, key counts per node and that every leaf is at the same depth.
+    // Returns the depth of the leaves.
+    fn assert_valid<K: Ord, V, const B: usize>(node: &Node<K, V, B>, is_root: bool) -> usize {
+        assert_eq!(node.keys.len(), node.values.len());
+        assert!(node.keys.len() < 2 * B);
+        if !is_root {
+            assert!(node.keys.len() >= B - 1);
+        }
+        assert!(node.keys.windows(2).all(|pair| pair[0] < pair[1]));
+        if node.is_leaf() {
+            return 1;
+        }
+        assert_eq!(node.children.len(), node.keys.len() + 1);
+        let depths: Vec<usize> = node
+            .children
+            .iter()
+            .map(|child| assert_valid(child, false))
+            .collect();
+        assert!(depths.iter().all(|&depth| depth == depths[0]));
+        for (index, key) in node.keys.iter().enumerate() {
+            assert!(node.children[index].keys.last().unwrap() < key);
+            assert!(node.children[index + 1].keys.first().unwrap() > key);
+        }
+        depths[0] + 1
+    }
+
+    #[test]
+    fn insert_and_get() {
+        let mut tree: BTree<i32, String> = BTree::new();
+        for key in scrambled(500) {
+            assert_eq!(tree.insert(key, key.to_string()), None);
+        }
+        assert_valid(&tree.root, true);
+        assert_eq!(tree.len(), 500);
+        assert_eq!(tree.get(&42), Some(&"42".to_string()));
+        assert_eq!(tree.get(&500), None);
+        assert!(tree.contains_key(&0));
+    }
+
+    #[test]
+    fn insert_existing_replaces_value() {
+        let mut tree: BTree<i32, char, 2> = BTree::new();
+        for key in 0..20 {
+            tree.insert(key, 'a');
+        }
+        assert_eq!(tree.insert(7, 'b'), Some('a'));
+        assert_eq!(tree.get(&7), Some(&'b'));
+        assert_eq!(tree.len(), 20);
+    }
+
+    #[test]
+    fn get_mut() {
+        let mut tree: BTree<i32, i32> = BTree::new();
+        tree.insert(1, 10);
+        *tree.get_mut(&1).unwrap() += 5;
+        assert_eq!(tree.get(&1), Some(&15));
+        assert_eq!(tree.get_mut(&2), None);
+    }
+
+    #[test]
+    fn height_is_logarithmic() {
+        let mut tree: BTree<i32, ()> = BTree::new();
+        // Sorted input, every insert goes to the rightmost leaf.
+        for key in 0..10_000 {
+            tree.insert(key, ());
+        }
+        assert_valid(&tree.root, true);
+        // Every node but the root has at least 5 keys, so 6 children.
+        assert!(tree.height() <= 6);
+    }
+
+    #[test]
+    fn remove() {
+        let mut tree: BTree<i32, i32, 2> = BTree::new();
+        for key in scrambled(300) {
+            tree.insert(key, key * 10);
+        }
+        for key in scrambled(300).into_iter().filter(|key| key % 3 != 0) {
+            assert_eq!(tree.remove(&key), Some(key * 10));
+            assert_valid(&tree.root, true);
+        }
+        assert_eq!(tree.remove(&1), None);
+        assert_eq!(tree.len(), 100);
+        let keys: Vec<i32> = tree.iter().map(|(key, _)| *key).collect();
+        assert_eq!(keys, (0..300).step_by(3).collect::<Vec<i32>>());
+    }
+
+    #[test]
+    fn remove_everything() {
+        let mut tree: BTree<i32, (), 3> = BTree::new();
+        for key in scrambled(200) {
+            tree.insert(key, ());
+        }
+        for key in 0..200 {
+            assert_eq!(tree.remove(&key), Some(()));
+        }
+        assert!(tree.is_empty());
+        assert_eq!(tree.height(), 1);
+        assert_eq!(tree.first(), None);
+    }
+
+    #[test]
+    fn first_and_last() {
+        let mut tree: BTree<i32, char> = BTree::new();
+        assert_eq!(tree.first(), None);
+        for (key, value) in [(5, 'e'), (1, 'a'), (9, 'i')] {
+            tree.insert(key, value);
+        }
+        assert_eq!(tree.first(), Some((&1, &'a')));
+        assert_eq!(tree.last(), Some((&9, &'i')));
+    }
+
+    #[test]
+    fn iter_in_order() {
+        let mut tree: BTree<i32, i32, 2> = BTree::new();
+        for key in scrambled(100) {
+            tree.insert(key, -key);
+        }
+        let entries: Vec<(i32, i32)> = tree.iter().map(|(k, v)| (*k, *v)).collect();
+        let expected: Vec<(i32, i32)> = (0..100).map(|k| (k, -k)).collect();
+        assert_eq!(entries, expected);
+        assert_eq!(tree.iter().size_hint(), (100, Some(100)));
+        assert_eq!((&tree).into_iter().count(), 100);
+    }
+
+    #[test]
+    fn range() {
+        let mut tree: BTree<i32, (), 2> = BTree::new();
+        for key in scrambled(50) {
+            tree.insert(key * 2, ());
+        }
+        let keys = |range: Range<'_, i32, (), 2>| range.map(|(k, _)| *k).collect::<Vec<i32>>();
+        assert_eq!(keys(tree.range(10..17)), vec![10, 12, 14, 16]);
+        assert_eq!(keys(tree.range(11..=16)), vec![12, 14, 16]);
+        assert_eq!(keys(tree.range(..4)), vec![0, 2]);
+        assert_eq!(keys(tree.range(95..)), vec![96, 98]);
+        assert_eq!(keys(tree.range(..)).len(), 50);
+        assert_eq!(keys(tree.range(11..12)), Vec::<i32>::new());
+        assert_eq!(keys(tree.range(200..)), Vec::<i32>::new());
+    }
+
+    #[test]
+    fn clear() {
+        let mut tree: BTree<i32, ()> = BTree::new();
+        for key in 0..100 {
+            tree.insert(key, ());
+        }
+        tree.clear();
+        assert!(tree.is_empty());
+        assert_eq!(tree.iter().next(), None);
+    }
+}
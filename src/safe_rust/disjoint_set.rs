@@ -0,0 +1,309 @@
+/*
+Disjoint Set (Union-Find)
+ Partition of the elements 0..n into sets, each set being a tree stored as parent links,
+ with the root as the representative of the set.
+ -union by rank: the root of the shorter tree goes under the root of the taller one, so trees
+  stay O(log n) high.
+ -path compression: after a find, every node on the path links straight to the root.
+ Together they make any sequence of operations take O(α(n)) amortized each, where the inverse
+ Ackermann function α(n) is below 5 for any n that fits in memory.
+
+DisjointSetMap
+ Same thing for arbitrary hashable items: each item is mapped to an index in a `DisjointSet`
+ through `safe_rust::hash_map::HashMap`.
+*/
+
+use std::hash::Hash;
+
+use crate::safe_rust::hash_map::HashMap;
+
+pub struct DisjointSet {
+    parent: Vec<usize>,
+    // Upper bound on the height of the tree, only meaningful for roots.
+    rank: Vec<u8>,
+    // Number of elements in the set, only meaningful for roots.
+    size: Vec<usize>,
+    sets: usize,
+}
+
+impl DisjointSet {
+    /// Creates `len` singleton sets, one per element in `0..len`.
+    pub fn new(len: usize) -> Self {
+        DisjointSet {
+            parent: (0..len).collect(),
+            rank: vec![0; len],
+            size: vec![1; len],
+            sets: len,
+        }
+    }
+
+    /// Adds a new element in its own set and returns it.
+    pub fn make_set(&mut self) -> usize {
+        let element = self.parent.len();
+        self.parent.push(element);
+        self.rank.push(0);
+        self.size.push(1);
+        self.sets += 1;
+        element
+    }
+
+    /// Representative of the set containing `element`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `element >= len`.
+    pub fn find(&mut self, element: usize) -> usize {
+        let mut root = element;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+        // Second pass: point every node on the path to the root.
+        let mut current = element;
+        while current != root {
+            let next = self.parent[current];
+            self.parent[current] = root;
+            current = next;
+        }
+        root
+    }
+
+    /// Merges the sets containing `a` and `b`. Returns `false` if they were already the same set.
+    pub fn union(&mut self, a: usize, b: usize) -> bool {
+        let (mut root_a, mut root_b) = (self.find(a), self.find(b));
+        if root_a == root_b {
+            return false;
+        }
+        if self.rank[root_a] < self.rank[root_b] {
+            std::mem::swap(&mut root_a, &mut root_b);
+        }
+        self.parent[root_b] = root_a;
+        self.size[root_a] += self.size[root_b];
+        if self.rank[root_a] == self.rank[root_b] {
+            self.rank[root_a] += 1;
+        }
+        self.sets -= 1;
+        true
+    }
+
+    pub fn same_set(&mut self, a: usize, b: usize) -> bool {
+        self.find(a) == self.find(b)
+    }
+
+    /// Number of elements in the set containing `element`.
+    pub fn set_size(&mut self, element: usize) -> usize {
+        let root = self.find(element);
+        self.size[root]
+    }
+
+    /// Number of elements.
+    pub fn len(&self) -> usize {
+        self.parent.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parent.is_empty()
+    }
+
+    /// Number of disjoint sets.
+    pub fn set_count(&self) -> usize {
+        self.sets
+    }
+}
+
+pub struct DisjointSetMap<T: Hash + Eq> {
+    indices: HashMap<T, usize>,
+    sets: DisjointSet,
+}
+
+impl<T: Hash + Eq> DisjointSetMap<T> {
+    pub fn new() -> Self {
+        DisjointSetMap {
+            indices: HashMap::new(),
+            sets: DisjointSet::new(0),
+        }
+    }
+
+    /// Adds `item` in its own set. Returns `false` if it was already present.
+    pub fn insert(&mut self, item: T) -> bool {
+        if self.indices.get(&item).is_some() {
+            return false;
+        }
+        let index = self.sets.make_set();
+        self.indices.insert(item, index);
+        true
+    }
+
+    pub fn contains(&self, item: &T) -> bool {
+        self.indices.get(item).is_some()
+    }
+
+    /// Identifier of the set containing `item`, equal for all the items of a set
+    /// until the next union. `None` if `item` isn't present.
+    pub fn find(&mut self, item: &T) -> Option<usize> {
+        let index = *self.indices.get(item)?;
+        Some(self.sets.find(index))
+    }
+
+    /// Merges the sets containing `a` and `b`, adding either of them if it isn't present yet.
+    /// Returns `false` if they were already the same set.
+    pub fn union(&mut self, a: T, b: T) -> bool {
+        let index_a = self.index_or_insert(a);
+        let index_b = self.index_or_insert(b);
+        self.sets.union(index_a, index_b)
+    }
+
+    fn index_or_insert(&mut self, item: T) -> usize {
+        match self.indices.get(&item) {
+            Some(&index) => index,
+            None => {
+                let index = self.sets.make_set();
+                self.indices.insert(item, index);
+                index
+            }
+        }
+    }
+
+    /// `false` if either item isn't present.
+    pub fn same_set(&mut self, a: &T, b: &T) -> bool {
+        match (self.find(a), self.find(b)) {
+            (Some(root_a), Some(root_b)) => root_a == root_b,
+            _ => false,
+        }
+    }
+
+    /// Number of items in the set containing `item`, `None` if `item` isn't present.
+    pub fn set_size(&mut self, item: &T) -> Option<usize> {
+        let index = *self.indices.get(item)?;
+        Some(self.sets.set_size(index))
+    }
+
+    /// Number of items.
+    pub fn len(&self) -> usize {
+        self.sets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sets.is_empty()
+    }
+
+    /// Number of disjoint sets.
+    pub fn set_count(&self) -> usize {
+        self.sets.set_count()
+    }
+}
+
+impl<T: Hash + Eq> Default for DisjointSetMap<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn singletons() {
+        let mut sets = DisjointSet::new(4);
+        assert_eq!(sets.len(), 4);
+        assert_eq!(sets.set_count(), 4);
+        for element in 0..4 {
+            assert_eq!(sets.find(element), element);
+            assert_eq!(sets.set_size(element), 1);
+        }
+        assert!(!sets.same_set(0, 1));
+    }
+
+    #[test]
+    fn union() {
+        let mut sets = DisjointSet::new(6);
+        assert!(sets.union(0, 1));
+        assert!(sets.union(2, 3));
+        assert!(sets.union(1, 3));
+        assert!(!sets.union(0, 2));
+        assert!(sets.same_set(0, 3));
+        assert!(!sets.same_set(0, 4));
+        assert_eq!(sets.set_size(2), 4);
+        assert_eq!(sets.set_size(5), 1);
+        assert_eq!(sets.set_count(), 3);
+    }
+
+    #[test]
+    fn make_set() {
+        let mut sets = DisjointSet::new(0);
+        assert!(sets.is_empty());
+        let a = sets.make_set();
+        let b = sets.make_set();
+        assert_eq!((a, b), (0, 1));
+        sets.union(a, b);
+        assert_eq!(sets.set_count(), 1);
+        assert_eq!(sets.set_size(b), 2);
+    }
+
+    #[test]
+    fn path_compression() {
+        let mut sets = DisjointSet::new(8);
+        // Union by rank keeps the chain short, so build one by hand.
+        for element in 1..8 {
+            sets.parent[element] = element - 1;
+        }
+        assert_eq!(sets.find(7), 0);
+        assert!(sets.parent.iter().all(|&parent| parent == 0));
+    }
+
+    #[test]
+    fn union_by_rank_keeps_trees_flat() {
+        let mut sets = DisjointSet::new(1024);
+        // Merge pairs, then pairs of pairs and so on: the worst case for the rank.
+        let mut step = 1;
+        while step < 1024 {
+            for element in (0..1024).step_by(2 * step) {
+                sets.union(element, element + step);
+            }
+            step *= 2;
+        }
+        assert_eq!(sets.set_count(), 1);
+        assert!(sets.rank.iter().all(|&rank| rank <= 10));
+    }
+
+    #[test]
+    fn map_union_and_find() {
+        let mut sets = DisjointSetMap::new();
+        assert!(sets.union("a", "b"));
+        assert!(sets.union("c", "d"));
+        assert!(sets.insert("e"));
+        assert!(!sets.insert("a"));
+        assert_eq!(sets.len(), 5);
+        assert_eq!(sets.set_count(), 3);
+
+        assert!(sets.same_set(&"a", &"b"));
+        assert!(!sets.same_set(&"a", &"c"));
+        assert!(sets.union("b", "d"));
+        assert_eq!(sets.find(&"a"), sets.find(&"c"));
+        assert_eq!(sets.set_size(&"d"), Some(4));
+        assert_eq!(sets.set_size(&"e"), Some(1));
+    }
+
+    #[test]
+    fn map_missing_items() {
+        let mut sets: DisjointSetMap<String> = DisjointSetMap::new();
+        assert!(sets.is_empty());
+        assert_eq!(sets.find(&"x".to_string()), None);
+        assert_eq!(sets.set_size(&"x".to_string()), None);
+        assert!(!sets.same_set(&"x".to_string(), &"x".to_string()));
+        assert!(!sets.contains(&"x".to_string()));
+    }
+
+    #[test]
+    fn map_connected_components() {
+        let edges = [(1, 2), (2, 3), (4, 5), (6, 6), (7, 5)];
+        let mut sets = DisjointSetMap::new();
+        for (a, b) in edges {
+            sets.union(a, b);
+        }
+        assert_eq!(sets.set_count(), 3);
+        assert!(sets.same_set(&1, &3));
+        assert!(sets.same_set(&4, &7));
+        assert!(sets.contains(&6));
+    }
+}
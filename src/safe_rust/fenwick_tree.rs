@@ -0,0 +1,267 @@
+/*
+Fenwick Tree (Binary Indexed Tree)
+ Prefix sums with point updates, both O(log n), in a single array the size of the input.
+
+ With 1-based indices, slot i holds the sum of the `i & -i` elements ending at i (the lowest set
+ bit of i gives the length). A prefix sum adds slots while clearing the lowest bit of the index,
+ an update adds to slots while adding the lowest bit to the index.
+
+ Simpler and lighter than a segment tree but limited to sums, or anything with an inverse
+ since range sums are the difference of two prefix sums.
+*/
+
+use std::ops::{Add, Bound, RangeBounds, Sub};
+
+pub struct FenwickTree<T> {
+    // `tree[i - 1]` holds slot i.
+    tree: Vec<T>,
+}
+
+fn lowest_bit(index: usize) -> usize {
+    index & index.wrapping_neg()
+}
+
+impl<T> FenwickTree<T>
+where
+    T: Copy + Default + Add<Output = T> + Sub<Output = T> + PartialOrd,
+{
+    /// Creates a tree over `len` elements, all zero (`T::default()`).
+    pub fn new(len: usize) -> Self {
+        FenwickTree {
+            tree: vec![T::default(); len],
+        }
+    }
+
+    /// Builds the tree in O(n): every slot passes its sum on to the next slot covering it.
+    pub fn from_vec(values: Vec<T>) -> Self {
+        let mut tree = values;
+        for slot in 1..=tree.len() {
+            let parent = slot + lowest_bit(slot);
+            if parent <= tree.len() {
+                tree[parent - 1] = tree[parent - 1] + tree[slot - 1];
+            }
+        }
+        FenwickTree { tree }
+    }
+
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    /// Adds `delta` to the element at `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= len`.
+    pub fn add(&mut self, index: usize, delta: T) {
+        assert!(
+            index < self.len(),
+            "FenwickTree::add: index {} out of bounds for length {}",
+            index,
+            self.len()
+        );
+        self.update_slots(index, |sum| sum + delta);
+    }
+
+    /// Sets the element at `index` to `value`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= len`.
+    pub fn set(&mut self, index: usize, value: T) {
+        let current = self.get(index);
+        // Only the non-negative difference is formed, so unsigned elements can be lowered too.
+        if value >= current {
+            self.update_slots(index, |sum| sum + (value - current));
+        } else {
+            self.update_slots(index, |sum| sum - (current - value));
+        }
+    }
+
+    // Applies `update` to every slot covering `index`.
+    fn update_slots<F: Fn(T) -> T>(&mut self, index: usize, update: F) {
+        let mut slot = index + 1;
+        while slot <= self.tree.len() {
+            self.tree[slot - 1] = update(self.tree[slot - 1]);
+            slot += lowest_bit(slot);
+        }
+    }
+
+    pub fn get(&self, index: usize) -> T {
+        self.range_sum(index..=index)
+    }
+
+    /// Sum of the first `end` elements.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `end > len`.
+    pub fn prefix_sum(&self, end: usize) -> T {
+        assert!(
+            end <= self.len(),
+            "FenwickTree::prefix_sum: end {} out of bounds for length {}",
+            end,
+            self.len()
+        );
+        let mut sum = T::default();
+        let mut slot = end;
+        while slot > 0 {
+            sum = sum + self.tree[slot - 1];
+            slot -= lowest_bit(slot);
+        }
+        sum
+    }
+
+    /// Sum of the elements in `range`.
+    pub fn range_sum<R: RangeBounds<usize>>(&self, range: R) -> T {
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start + 1,
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&end) => end + 1,
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => self.len(),
+        };
+        assert!(start <= end, "FenwickTree::range_sum: start after end");
+        self.prefix_sum(end) - self.prefix_sum(start)
+    }
+
+    /// Smallest index whose prefix sum (inclusive) reaches `target`, or `len` if none does.
+    /// Only meaningful when no element is negative, so the prefix sums don't decrease.
+    ///
+    /// O(log n): descends the implicit tree from the largest power of two, instead of
+    /// binary searching over prefix sums.
+    pub fn lower_bound(&self, target: T) -> usize {
+        let mut position = 0;
+        let mut remaining = target;
+        let mut step = match self.len() {
+            0 => 0,
+            len => 1 << len.ilog2(),
+        };
+        while step > 0 {
+            let next = position + step;
+            if next <= self.len() && self.tree[next - 1] < remaining {
+                position = next;
+                remaining = remaining - self.tree[next - 1];
+            }
+            step >>= 1;
+        }
+        position
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::safe_rust::test_utils::scrambled;
+
+    #[test]
+    fn prefix_sums() {
+        let tree = FenwickTree::from_vec(vec![3, 1, 4, 1, 5, 9, 2, 6]);
+        assert_eq!(tree.prefix_sum(0), 0);
+        assert_eq!(tree.prefix_sum(1), 3);
+        assert_eq!(tree.prefix_sum(4), 9);
+        assert_eq!(tree.prefix_sum(8), 31);
+        assert_eq!(tree.range_sum(2..5), 10);
+        assert_eq!(tree.range_sum(..), 31);
+        assert_eq!(tree.get(5), 9);
+    }
+
+    #[test]
+    fn from_vec_matches_adds() {
+        let values = scrambled(100);
+        let built = FenwickTree::from_vec(values.clone());
+        let mut added = FenwickTree::new(values.len());
+        for (index, value) in values.iter().enumerate() {
+            added.add(index, *value);
+        }
+        assert_eq!(built.tree, added.tree);
+    }
+
+    #[test]
+    fn updates_match_vec() {
+        let mut values = scrambled(50);
+        let mut tree = FenwickTree::from_vec(values.clone());
+        for step in 0..100_i64 {
+            let index = ((step * 7919) % 50) as usize;
+            if step % 2 == 0 {
+                tree.add(index, step - 40);
+                values[index] += step - 40;
+            } else {
+                tree.set(index, step);
+                values[index] = step;
+            }
+            let end = ((step * 104_729) % 51) as usize;
+            assert_eq!(tree.prefix_sum(end), values[..end].iter().sum());
+        }
+    }
+
+    #[test]
+    fn set_lowers_unsigned_elements() {
+        let mut tree = FenwickTree::from_vec(vec![5_u64, 3, 7]);
+        tree.set(1, 1);
+        assert_eq!(tree.get(1), 1);
+        assert_eq!(tree.prefix_sum(3), 13);
+        tree.set(0, 0);
+        assert_eq!(tree.range_sum(..2), 1);
+    }
+
+    #[test]
+    fn lower_bound() {
+        let tree = FenwickTree::from_vec(vec![2, 0, 3, 1, 0, 4]);
+        // Prefix sums: 2, 2, 5, 6, 6, 10
+        assert_eq!(tree.lower_bound(0), 0);
+        assert_eq!(tree.lower_bound(1), 0);
+        assert_eq!(tree.lower_bound(2), 0);
+        assert_eq!(tree.lower_bound(3), 2);
+        assert_eq!(tree.lower_bound(6), 3);
+        assert_eq!(tree.lower_bound(7), 5);
+        assert_eq!(tree.lower_bound(10), 5);
+        assert_eq!(tree.lower_bound(11), 6);
+    }
+
+    #[test]
+    fn lower_bound_matches_linear_search() {
+        let values: Vec<u64> = (0..37).map(|i| (i * 7919) % 5).collect();
+        let tree = FenwickTree::from_vec(values.clone());
+        let total: u64 = values.iter().sum();
+        for target in 0..=total + 1 {
+            let mut sum = 0;
+            let expected = values
+                .iter()
+                .position(|value| {
+                    sum += value;
+                    sum >= target
+                })
+                .unwrap_or(values.len());
+            assert_eq!(tree.lower_bound(target), expected, "target {}", target);
+        }
+    }
+
+    #[test]
+    fn floats() {
+        let tree = FenwickTree::from_vec(vec![0.5, 1.5, 2.0]);
+        assert_eq!(tree.prefix_sum(2), 2.0);
+    }
+
+    #[test]
+    fn empty_tree() {
+        let tree: FenwickTree<i32> = FenwickTree::new(0);
+        assert!(tree.is_empty());
+        assert_eq!(tree.prefix_sum(0), 0);
+        assert_eq!(tree.lower_bound(1), 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn add_out_of_bounds() {
+        let mut tree: FenwickTree<i32> = FenwickTree::new(3);
+        tree.add(3, 1);
+    }
+}
@@ -0,0 +1,224 @@
+/*
+ * Heap utilities
+ * Streaming helpers built on `binary_heap::Heap`:
+ * - top_k keeps the k largest items of an iterator in a min heap of size k, O(n log k).
+ * - KMerge lazily merges any number of sorted iterators, keeping one head per source
+ *   in a min heap, O(log k) per item.
+ *
+ * `Heap` takes a plain `fn` comparator, so the user's comparator travels inside every heap
+ * entry together with the position used to break ties.
+ */
+use std::cmp::Ordering;
+
+use crate::safe_rust::binary_heap::Heap;
+
+struct RankedEntry<T> {
+    value: T,
+    // Position of the item in the input (top_k) or index of its source (KMerge).
+    rank: usize,
+    compare: fn(&T, &T) -> Ordering,
+}
+
+// Min heap of ranked entries: for top_k the root is the worst item kept so far.
+// A later item is worse than an earlier one with an equal value.
+fn worst_first<T>(a: &RankedEntry<T>, b: &RankedEntry<T>) -> bool {
+    (a.compare)(&a.value, &b.value).then(b.rank.cmp(&a.rank)) == Ordering::Less
+}
+
+// Min heap of ranked entries: for KMerge the root is the next item to yield.
+// Equal values come out by source index.
+fn smallest_first<T>(a: &RankedEntry<T>, b: &RankedEntry<T>) -> bool {
+    (a.compare)(&a.value, &b.value).then(a.rank.cmp(&b.rank)) == Ordering::Less
+}
+
+/// Returns the `k` largest items according to `compare`, largest first.
+/// Equal items keep the order they had in `iter`.
+pub fn top_k<T, I>(iter: I, k: usize, compare: fn(&T, &T) -> Ordering) -> Vec<T>
+where
+    I: IntoIterator<Item = T>,
+{
+    if k == 0 {
+        return Vec::new();
+    }
+    let mut heap = Heap::new(worst_first::<T>);
+    for (rank, value) in iter.into_iter().enumerate() {
+        let entry = RankedEntry {
+            value,
+            rank,
+            compare,
+        };
+        if heap.len() < k {
+            heap.add(entry);
+        } else if worst_first(heap.peek().unwrap(), &entry) {
+            heap.pop_push(entry);
+        }
+    }
+    // The heap pops the worst item first, so its heapsort puts the best item first.
+    heap.into_sorted_vec()
+        .into_iter()
+        .map(|entry| entry.value)
+        .collect()
+}
+
+/// Merges iterators that are each sorted according to `compare` into one sorted iterator.
+/// Equal items are yielded in source order: first by source index, then by position within the source.
+pub struct KMerge<I: Iterator> {
+    sources: Vec<I>,
+    heads: Heap<RankedEntry<I::Item>>,
+}
+
+impl<I: Iterator> KMerge<I> {
+    pub fn new<S>(sources: S, compare: fn(&I::Item, &I::Item) -> Ordering) -> Self
+    where
+        S: IntoIterator<Item = I>,
+    {
+        let mut sources: Vec<I> = sources.into_iter().collect();
+        let heads = sources
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(rank, source)| {
+                source.next().map(|value| RankedEntry {
+                    value,
+                    rank,
+                    compare,
+                })
+            })
+            .collect();
+        KMerge {
+            sources,
+            heads: Heap::from_vec(heads, smallest_first::<I::Item>),
+        }
+    }
+}
+
+impl<I: Iterator> Iterator for KMerge<I> {
+    type Item = I::Item;
+    fn next(&mut self) -> Option<Self::Item> {
+        let head = self.heads.peek()?;
+        let (rank, compare) = (head.rank, head.compare);
+        let next_entry = self.sources[rank].next().map(|value| RankedEntry {
+            value,
+            rank,
+            compare,
+        });
+        // Replace the head with the next item from the same source in a single bubble down.
+        let entry = match next_entry {
+            Some(next_entry) => self.heads.pop_push(next_entry),
+            None => self.heads.remove(),
+        };
+        entry.map(|entry| entry.value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let heads = self.heads.len();
+        self.sources
+            .iter()
+            .fold((heads, Some(heads)), |(low, high), source| {
+                let (source_low, source_high) = source.size_hint();
+                (
+                    low.saturating_add(source_low),
+                    high.zip(source_high).and_then(|(a, b)| a.checked_add(b)),
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn top_k_largest_first() {
+        let scores = vec![5, 1, 9, 3, 7, 2, 8];
+        assert_eq!(top_k(scores, 3, i32::cmp), vec![9, 8, 7]);
+    }
+
+    #[test]
+    fn top_k_with_reversed_compare() {
+        let scores = vec![5, 1, 9, 3, 7, 2, 8];
+        assert_eq!(top_k(scores, 2, |a: &i32, b| b.cmp(a)), vec![1, 2]);
+    }
+
+    #[test]
+    fn top_k_more_than_available() {
+        assert_eq!(top_k(vec![2, 3, 1], 10, i32::cmp), vec![3, 2, 1]);
+    }
+
+    #[test]
+    fn top_k_zero() {
+        assert!(top_k(vec![2, 3, 1], 0, i32::cmp).is_empty());
+    }
+
+    #[test]
+    fn top_k_ties_keep_input_order() {
+        let players = vec![("a", 10), ("b", 20), ("c", 10), ("d", 20), ("e", 10)];
+        let best = top_k(players, 3, |x: &(&str, i32), y| x.1.cmp(&y.1));
+        assert_eq!(best, vec![("b", 20), ("d", 20), ("a", 10)]);
+    }
+
+    #[test]
+    fn top_k_large_input() {
+        let values = (0..10_000).map(|i| (i * 7919) % 10_000);
+        assert_eq!(
+            top_k(values, 5, i32::cmp),
+            vec![9999, 9998, 9997, 9996, 9995]
+        );
+    }
+
+    #[test]
+    fn kmerge_sorted_runs() {
+        let runs = vec![vec![1, 4, 7], vec![2, 5, 8], vec![0, 3, 6, 9]];
+        let merged: Vec<i32> =
+            KMerge::new(runs.into_iter().map(Vec::into_iter), i32::cmp).collect();
+        assert_eq!(merged, (0..10).collect::<Vec<i32>>());
+    }
+
+    #[test]
+    fn kmerge_with_empty_sources() {
+        let runs = vec![vec![], vec![3, 4], vec![], vec![1]];
+        let merged: Vec<i32> =
+            KMerge::new(runs.into_iter().map(Vec::into_iter), i32::cmp).collect();
+        assert_eq!(merged, vec![1, 3, 4]);
+    }
+
+    #[test]
+    fn kmerge_no_sources() {
+        let runs: Vec<std::vec::IntoIter<i32>> = Vec::new();
+        assert_eq!(KMerge::new(runs, i32::cmp).next(), None);
+    }
+
+    #[test]
+    fn kmerge_ties_in_source_order() {
+        let runs = vec![
+            vec![(1, 'a'), (2, 'a'), (2, 'b')],
+            vec![(1, 'c'), (2, 'c')],
+            vec![(2, 'd')],
+        ];
+        let merged: Vec<(i32, char)> = KMerge::new(
+            runs.into_iter().map(Vec::into_iter),
+            |x: &(i32, char), y| x.0.cmp(&y.0),
+        )
+        .collect();
+        assert_eq!(
+            merged,
+            vec![(1, 'a'), (1, 'c'), (2, 'a'), (2, 'b'), (2, 'c'), (2, 'd')]
+        );
+    }
+
+    #[test]
+    fn kmerge_is_lazy() {
+        let endless = (0..).step_by(2);
+        let odd = (1..).step_by(2);
+        let merged: Vec<i32> = KMerge::new(vec![endless, odd], i32::cmp).take(5).collect();
+        assert_eq!(merged, vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn kmerge_size_hint() {
+        let runs = vec![vec![1, 4, 7], vec![2, 5]];
+        let mut merged = KMerge::new(runs.into_iter().map(Vec::into_iter), i32::cmp);
+        assert_eq!(merged.size_hint(), (5, Some(5)));
+        merged.next();
+        assert_eq!(merged.size_hint(), (4, Some(4)));
+    }
+}
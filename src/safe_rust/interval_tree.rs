@@ -0,0 +1,489 @@
+/*
+Interval Tree
+ Map from half-open intervals [start, end) to values, answering "which intervals overlap [a, b)"
+ and "which intervals contain p".
+
+ It's the AVL tree from tree_avl.rs ordered by (start, end), where every node also stores
+ the largest end point in its subtree. A subtree whose max end is <= a can't overlap [a, b),
+ and once a node starts at or after b neither it nor anything after it in order can, so a query
+ only walks the paths leading to matches. Each match costs at most one O(log n) path, and no
+ node is visited twice, so a query with k results is O(log n + min(n, k log n)).
+
+ The max end of a node only depends on its own interval and its children, so it's recomputed
+ wherever the height is, through the `update_summary` hook of the shared AVL balancing: on the
+ way back up from an insert/remove and inside the rotations.
+ Inserting an interval that is already in the tree replaces its value.
+*/
+
+use std::cmp::Ordering;
+use std::ops::{Bound, Range};
+
+use crate::safe_rust::tree_avl::{check_balance, update, AvlNode};
+
+struct Node<K, V> {
+    left: Option<Box<Node<K, V>>>,
+    right: Option<Box<Node<K, V>>>,
+    height: usize,
+    interval: Range<K>,
+    // Largest `interval.end` in this subtree.
+    max_end: K,
+    value: V,
+}
+
+impl<K: Ord + Clone, V> AvlNode for Node<K, V> {
+    fn left_link(&self) -> &Option<Box<Self>> {
+        &self.left
+    }
+    fn right_link(&self) -> &Option<Box<Self>> {
+        &self.right
+    }
+    fn left_link_mut(&mut self) -> &mut Option<Box<Self>> {
+        &mut self.left
+    }
+    fn right_link_mut(&mut self) -> &mut Option<Box<Self>> {
+        &mut self.right
+    }
+    fn height(&self) -> usize {
+        self.height
+    }
+    fn set_height(&mut self, height: usize) {
+        self.height = height;
+    }
+
+    // The max end is the largest of the node's own end and its children's max ends.
+    fn update_summary(&mut self) {
+        let mut max_end = &self.interval.end;
+        for child in [&self.left, &self.right].into_iter().flatten() {
+            if child.max_end > *max_end {
+                max_end = &child.max_end;
+            }
+        }
+        self.max_end = max_end.clone();
+    }
+}
+
+pub struct IntervalTree<K: Ord + Clone, V> {
+    root: Option<Box<Node<K, V>>>,
+    size: usize,
+}
+
+fn compare<K: Ord>(a: &Range<K>, b: &Range<K>) -> Ordering {
+    a.start.cmp(&b.start).then_with(|| a.end.cmp(&b.end))
+}
+
+impl<K: Ord + Clone, V> IntervalTree<K, V> {
+    pub fn new() -> Self {
+        IntervalTree {
+            root: None,
+            size: 0,
+        }
+    }
+
+    /// Inserts the interval `[start, end)`, returning the previous value if it was already present.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the interval is empty (`start >= end`).
+    pub fn insert(&mut self, interval: Range<K>, value: V) -> Option<V> {
+        assert!(
+            interval.start < interval.end,
+            "IntervalTree::insert: empty interval"
+        );
+        let new_node = Node {
+            left: None,
+            right: None,
+            height: 0,
+            max_end: interval.end.clone(),
+            interval,
+            value,
+        };
+        match &mut self.root {
+            Some(node) => {
+                let replaced = Self::insert_recursive(node, new_node);
+                if replaced.is_none() {
+                    self.size += 1;
+                }
+                replaced
+            }
+            None => {
+                self.root = Some(Box::new(new_node));
+                self.size += 1;
+                None
+            }
+        }
+    }
+
+    fn insert_recursive(parent: &mut Box<Node<K, V>>, new_node: Node<K, V>) -> Option<V> {
+        match compare(&new_node.interval, &parent.interval) {
+            Ordering::Less => {
+                // add to left side
+                match &mut parent.left {
+                    Some(node) => {
+                        if let Some(replaced) = Self::insert_recursive(node, new_node) {
+                            return Some(replaced);
+                        }
+                    }
+                    None => parent.left = Some(Box::new(new_node)),
+                }
+            }
+            Ordering::Equal => return Some(std::mem::replace(&mut parent.value, new_node.value)),
+            Ordering::Greater => {
+                // add to right side
+                match &mut parent.right {
+                    Some(node) => {
+                        if let Some(replaced) = Self::insert_recursive(node, new_node) {
+                            return Some(replaced);
+                        }
+                    }
+                    None => parent.right = Some(Box::new(new_node)),
+                }
+            }
+        }
+
+        update(parent);
+        check_balance(parent);
+        None
+    }
+
+    /// Removes the interval, returning its value if it was present.
+    pub fn remove(&mut self, interval: &Range<K>) -> Option<V> {
+        let removed = Self::remove_recursive(&mut self.root, interval);
+        if removed.is_some() {
+            self.size -= 1;
+        }
+        removed
+    }
+
+    fn remove_recursive(
+        node_option: &mut Option<Box<Node<K, V>>>,
+        interval: &Range<K>,
+    ) -> Option<V> {
+        let node = node_option.as_mut()?;
+        let removed = match compare(interval, &node.interval) {
+            Ordering::Less => Self::remove_recursive(&mut node.left, interval),
+            Ordering::Greater => Self::remove_recursive(&mut node.right, interval),
+            Ordering::Equal => match (node.left.is_some(), node.right.is_some()) {
+                (true, true) => {
+                    // Swap with the in-order successor, then remove it from the right subtree.
+                    let in_order_successor = {
+                        let mut current = node.right.as_mut().unwrap();
+                        while current.left.is_some() {
+                            current = current.left.as_mut().unwrap();
+                        }
+                        current
+                    };
+                    std::mem::swap(&mut node.interval, &mut in_order_successor.interval);
+                    std::mem::swap(&mut node.value, &mut in_order_successor.value);
+                    Self::remove_recursive(&mut node.right, interval)
+                }
+                _ => {
+                    let mut removed = node_option.take().unwrap();
+                    *node_option = removed.left.take().or(removed.right.take());
+                    return Some(removed.value);
+                }
+            },
+        };
+
+        if removed.is_some() {
+            let node = node_option.as_mut().unwrap();
+            update(node);
+            check_balance(node);
+        }
+        removed
+    }
+
+    pub fn get(&self, interval: &Range<K>) -> Option<&V> {
+        let mut current = &self.root;
+        while let Some(node) = current {
+            match compare(interval, &node.interval) {
+                Ordering::Less => current = &node.left,
+                Ordering::Equal => return Some(&node.value),
+                Ordering::Greater => current = &node.right,
+            }
+        }
+        None
+    }
+
+    /// Returns an iterator over the intervals overlapping `[query.start, query.end)`,
+    /// ordered by start then end. An empty or reversed query overlaps nothing.
+    pub fn overlapping(&self, query: Range<K>) -> Iter<'_, K, V> {
+        if query.start >= query.end {
+            return Iter::new(&None, None, Bound::Unbounded);
+        }
+        Iter::new(&self.root, Some(query.start), Bound::Excluded(query.end))
+    }
+
+    /// Returns an iterator over the intervals containing `point`, ordered by start then end.
+    pub fn stabbing(&self, point: K) -> Iter<'_, K, V> {
+        Iter::new(&self.root, Some(point.clone()), Bound::Included(point))
+    }
+
+    /// Returns an iterator over all the intervals, ordered by start then end.
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter::new(&self.root, None, Bound::Unbounded)
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn height(&self) -> usize {
+        match &self.root {
+            Some(node) => node.height,
+            None => 0,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    pub fn clear(&mut self) {
+        self.root = None;
+        self.size = 0;
+    }
+}
+
+impl<K: Ord + Clone, V> Default for IntervalTree<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// In-order iterator over the intervals that end after `end_above` and start before `start_below`.
+/// The stack holds the nodes whose left subtree is being visited.
+pub struct Iter<'a, K, V> {
+    stack: Vec<&'a Node<K, V>>,
+    end_above: Option<K>,
+    start_below: Bound<K>,
+}
+
+impl<'a, K: Ord, V> Iter<'a, K, V> {
+    fn new(root: &'a Option<Box<Node<K, V>>>, end_above: Option<K>, start_below: Bound<K>) -> Self {
+        let mut iter = Iter {
+            stack: Vec::new(),
+            end_above,
+            start_below,
+        };
+        iter.push_left_path(root);
+        iter
+    }
+
+    fn ends_after(&self, end: &K) -> bool {
+        self.end_above.as_ref().is_none_or(|point| end > point)
+    }
+
+    fn starts_before(&self, start: &K) -> bool {
+        match &self.start_below {
+            Bound::Included(point) => start <= point,
+            Bound::Excluded(point) => start < point,
+            Bound::Unbounded => true,
+        }
+    }
+
+    // Subtrees where every interval ends too early are skipped whole.
+    fn push_left_path(&mut self, mut current: &'a Option<Box<Node<K, V>>>) {
+        while let Some(node) = current {
+            if !self.ends_after(&node.max_end) {
+                break;
+            }
+            self.stack.push(node);
+            current = &node.left;
+        }
+    }
+}
+
+impl<'a, K: Ord, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a Range<K>, &'a V);
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(node) = self.stack.pop() {
+            // Everything left to visit starts at or after this node.
+            if !self.starts_before(&node.interval.start) {
+                self.stack.clear();
+                return None;
+            }
+            self.push_left_path(&node.right);
+            if self.ends_after(&node.interval.end) {
+                return Some((&node.interval, &node.value));
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::safe_rust::tree_avl;
+
+    // Checks the search tree order, the AVL balance, the heights and the max ends.
+    fn assert_valid(node: &Option<Box<Node<i32, i32>>>) {
+        let node = match node {
+            Some(node) => node,
+            None => return,
+        };
+        assert_valid(&node.left);
+        assert_valid(&node.right);
+        if let Some(left) = &node.left {
+            assert_eq!(compare(&left.interval, &node.interval), Ordering::Less);
+        }
+        if let Some(right) = &node.right {
+            assert_eq!(compare(&right.interval, &node.interval), Ordering::Greater);
+        }
+        assert!(tree_avl::balance_factor(node.as_ref()).abs() <= 1);
+        assert_eq!(node.height, tree_avl::calculate_height(node.as_ref()));
+        let children_max = [&node.left, &node.right]
+            .into_iter()
+            .flatten()
+            .map(|child| child.max_end)
+            .max();
+        assert_eq!(
+            node.max_end,
+            children_max.map_or(node.interval.end, |max| max.max(node.interval.end))
+        );
+    }
+
+    // Deterministic intervals of varying lengths in [0, 1000).
+    fn intervals(count: i32) -> Vec<Range<i32>> {
+        (0..count)
+            .map(|i| {
+                let start = (i * 7919) % 1000;
+                let length = 1 + (i * 104_729) % 50;
+                start..start + length
+            })
+            .collect()
+    }
+
+    fn tree_from(intervals: &[Range<i32>]) -> IntervalTree<i32, i32> {
+        let mut tree = IntervalTree::new();
+        for (index, interval) in intervals.iter().enumerate() {
+            tree.insert(interval.clone(), index as i32);
+        }
+        tree
+    }
+
+    fn sorted_brute_force(
+        intervals: &[Range<i32>],
+        matches: impl Fn(&Range<i32>) -> bool,
+    ) -> Vec<Range<i32>> {
+        let mut expected: Vec<Range<i32>> =
+            intervals.iter().filter(|i| matches(i)).cloned().collect();
+        expected.sort_by(compare);
+        expected.dedup();
+        expected
+    }
+
+    #[test]
+    fn insert_and_get() {
+        let mut tree = IntervalTree::new();
+        assert_eq!(tree.insert(5..10, 'a'), None);
+        assert_eq!(tree.insert(1..3, 'b'), None);
+        assert_eq!(tree.insert(5..10, 'c'), Some('a'));
+        assert_eq!(tree.size(), 2);
+        assert_eq!(tree.get(&(5..10)), Some(&'c'));
+        assert_eq!(tree.get(&(5..11)), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn insert_empty_interval() {
+        let mut tree = IntervalTree::new();
+        tree.insert(3..3, ());
+    }
+
+    #[test]
+    fn stays_balanced() {
+        let mut tree = IntervalTree::new();
+        for start in 0..1000 {
+            tree.insert(start..start + 5, start);
+        }
+        assert_valid(&tree.root);
+        assert!(tree.height() < 15);
+    }
+
+    #[test]
+    fn overlapping_matches_brute_force() {
+        let intervals = intervals(300);
+        let tree = tree_from(&intervals);
+        assert_valid(&tree.root);
+        #[allow(clippy::reversed_empty_ranges)]
+        for query in [0..10, 100..101, 250..400, 990..1100, 500..500, 400..250] {
+            let found: Vec<Range<i32>> = tree
+                .overlapping(query.clone())
+                .map(|(interval, _)| interval.clone())
+                .collect();
+            let expected = sorted_brute_force(&intervals, |interval| {
+                query.start < query.end && interval.start < query.end && query.start < interval.end
+            });
+            assert_eq!(found, expected, "query {:?}", query);
+        }
+    }
+
+    #[test]
+    fn overlapping_is_half_open() {
+        let mut tree = IntervalTree::new();
+        tree.insert(0..5, ());
+        tree.insert(5..10, ());
+        tree.insert(10..15, ());
+        let found: Vec<&Range<i32>> = tree.overlapping(5..10).map(|(i, _)| i).collect();
+        assert_eq!(found, vec![&(5..10)]);
+    }
+
+    #[test]
+    fn stabbing_matches_brute_force() {
+        let intervals = intervals(300);
+        let tree = tree_from(&intervals);
+        for point in [0, 17, 500, 999, 1049, 2000] {
+            let found: Vec<Range<i32>> = tree
+                .stabbing(point)
+                .map(|(interval, _)| interval.clone())
+                .collect();
+            let expected = sorted_brute_force(&intervals, |interval| interval.contains(&point));
+            assert_eq!(found, expected, "point {}", point);
+        }
+    }
+
+    #[test]
+    fn remove() {
+        let intervals = intervals(200);
+        let mut tree = tree_from(&intervals);
+        let (removed, kept) = intervals.split_at(120);
+        for interval in removed {
+            tree.remove(interval);
+            assert_valid(&tree.root);
+        }
+        assert_eq!(tree.remove(&(2000..2001)), None);
+
+        let expected = sorted_brute_force(kept, |interval| interval.contains(&300));
+        let found: Vec<Range<i32>> = tree.stabbing(300).map(|(i, _)| i.clone()).collect();
+        assert_eq!(found, expected);
+        assert_eq!(tree.size(), sorted_brute_force(kept, |_| true).len());
+    }
+
+    #[test]
+    fn remove_returns_value() {
+        let mut tree = IntervalTree::new();
+        tree.insert(1..4, "one");
+        tree.insert(2..6, "two");
+        assert_eq!(tree.remove(&(1..4)), Some("one"));
+        assert_eq!(tree.remove(&(1..4)), None);
+        assert_eq!(tree.size(), 1);
+    }
+
+    #[test]
+    fn iter_in_order() {
+        let intervals = intervals(100);
+        let tree = tree_from(&intervals);
+        let found: Vec<Range<i32>> = tree.iter().map(|(i, _)| i.clone()).collect();
+        assert_eq!(found, sorted_brute_force(&intervals, |_| true));
+    }
+
+    #[test]
+    fn clear() {
+        let mut tree = tree_from(&intervals(10));
+        tree.clear();
+        assert!(tree.is_empty());
+        assert_eq!(tree.size(), 0);
+        assert_eq!(tree.stabbing(5).next(), None);
+    }
+}
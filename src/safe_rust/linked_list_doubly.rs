@@ -1,18 +1,18 @@
 /**
  * Doubly Linked List.
  * Compared to my singly_linked_list implementation this one adds a tail pointer, and a pointer to the previous node.
+ *
+ * Every node lives in its own `Rc<RefCell<Option<Node>>>` cell. The `next` of the last node is an empty cell,
+ * which marks the end of the list, and when the list is empty `head` and `tail` both point to that empty cell.
+ * `previous` is a `Weak` pointer to the cell of the previous node, so there are no reference cycles.
+ * All the relinking goes through `insert_after` and `unlink`, which keep `next`, `previous`, `head` and `tail`
+ * consistent, `validate` checks it.
  */
 // I had std::borrow::BorrowMut which was shadowing the method of the same name in RefCell.
 // I'm learning Rust so I thought the error was on my logic, my brain almost fucking fried.
 // https://github.com/rust-lang/rust/issues/39232 a PR was added a few months before this that makes the type check warn you lol
-
-// TODOs for DoublyLinkedList:
-// 1. Handle `previous` pointers in `reverse` and `remove` methods.
-// 2. Implement the `Drop` trait for memory cleanup.
-// 3. Refactor to eliminate repetitive patterns.
-// 4. Add comments explaining core logic.
-// 5. Implement a reverse iterator.
-use std::cell::RefCell;
+use std::cell::{Ref, RefCell, RefMut};
+use std::cmp::Ordering;
 use std::rc::Rc;
 use std::rc::Weak;
 
@@ -42,116 +42,683 @@ where
             counter: 0,
         }
     }
+
     pub fn add_first(&mut self, data: T) {
+        self.insert_after(None, data);
+    }
+
+    pub fn add_last(&mut self, data: T) {
+        if self.counter == 0 {
+            self.add_first(data);
+            return;
+        }
+        let tail = Rc::clone(&self.tail);
+        self.insert_after(Some(&tail), data);
+    }
+
+    // Inserts a node right after the node in `previous`, or at the front if it's None.
+    // Returns the cell of the new node.
+    fn insert_after(&mut self, previous: Option<&Link<T>>, data: T) -> Link<T> {
+        let next = match previous {
+            Some(cell) => Rc::clone(&cell.borrow().as_ref().unwrap().next),
+            None => Rc::clone(&self.head),
+        };
         let new_node = Rc::new(RefCell::new(Some(Node {
             data,
-            next: Rc::clone(&self.head),
-            previous: Weak::new(),
+            next: Rc::clone(&next),
+            previous: previous.map_or(Weak::new(), Rc::downgrade),
         })));
 
-        match *self.head.borrow_mut() {
-            Some(ref mut node) => node.previous = Rc::downgrade(&new_node),
-            // // If this is the first element added to the list then also set the tail to be equal to this new node
+        // Whatever comes after points back to the new node, if nothing does the new node is the tail.
+        match next.borrow_mut().as_mut() {
+            Some(next_node) => next_node.previous = Rc::downgrade(&new_node),
             None => self.tail = Rc::clone(&new_node),
         }
-
-        self.head = new_node;
+        // Whatever comes before points to the new node, if nothing does the new node is the head.
+        match previous {
+            Some(cell) => cell.borrow_mut().as_mut().unwrap().next = Rc::clone(&new_node),
+            None => self.head = Rc::clone(&new_node),
+        }
         self.counter += 1;
+        new_node
     }
 
-    pub fn add_last(&mut self, data: T) {
+    // Removes the node in `cell` from the list and returns its data.
+    fn unlink(&mut self, cell: &Link<T>) -> T {
+        let node = cell
+            .take()
+            .expect("Unlink: the cell of a node in the list can't be empty");
+        let previous = node.previous.upgrade();
+
+        // The next node points back to the previous one, if there's no next node the previous one is the tail.
+        match node.next.borrow_mut().as_mut() {
+            Some(next_node) => next_node.previous = node.previous.clone(),
+            None => {
+                self.tail = match &previous {
+                    Some(previous) => Rc::clone(previous),
+                    // The list is now empty, head and tail share the end marker.
+                    None => Rc::clone(&node.next),
+                }
+            }
+        }
+        // The previous node skips over the removed one, if there's no previous node the next one is the head.
+        match &previous {
+            Some(previous) => previous.borrow_mut().as_mut().unwrap().next = Rc::clone(&node.next),
+            None => self.head = Rc::clone(&node.next),
+        }
+        self.counter -= 1;
+        node.data
+    }
+
+    pub fn remove_first(&mut self) -> Option<T> {
         if self.counter == 0 {
-            self.add_first(data);
+            return None;
+        }
+        let head = Rc::clone(&self.head);
+        Some(self.unlink(&head))
+    }
+
+    pub fn remove_last(&mut self) -> Option<T> {
+        if self.counter == 0 {
+            return None;
+        }
+        let tail = Rc::clone(&self.tail);
+        Some(self.unlink(&tail))
+    }
+
+    // Swaps `next` and `previous` in every node, then swaps head and tail.
+    pub fn reverse(&mut self) {
+        if self.counter < 2 {
             return;
         }
-        let mut current_tail_node = self.tail.take();
+        // The old head becomes the last node, it takes over the end marker.
+        let mut previous = Rc::clone(&self.tail.borrow().as_ref().unwrap().next);
+        let mut current = Rc::clone(&self.head);
+        loop {
+            let next = {
+                let mut borrowed = current.borrow_mut();
+                let node = match borrowed.as_mut() {
+                    Some(node) => node,
+                    None => break,
+                };
+                let next = std::mem::replace(&mut node.next, previous);
+                node.previous = if next.borrow().is_some() {
+                    Rc::downgrade(&next)
+                } else {
+                    Weak::new()
+                };
+                next
+            };
+            previous = current;
+            current = next;
+        }
+        std::mem::swap(&mut self.head, &mut self.tail);
+    }
 
-        let new_tail_node = Rc::new(RefCell::new(Some(Node {
-            data,
-            next: Rc::new(RefCell::new(None)),
-            previous: Rc::downgrade(&self.tail),
-        })));
+    /// Removes the first element equal to `data`, returns whether there was one.
+    pub fn remove(&mut self, data: T) -> bool {
+        let mut current = Rc::clone(&self.head);
+        loop {
+            let next = match current.borrow().as_ref() {
+                None => return false,
+                Some(node) if node.data == data => None,
+                Some(node) => Some(Rc::clone(&node.next)),
+            };
+            match next {
+                Some(next) => current = next,
+                None => {
+                    self.unlink(&current);
+                    return true;
+                }
+            }
+        }
+    }
 
-        if let Some(ref mut node) = current_tail_node {
-            node.next = Rc::clone(&new_tail_node);
+    // Cell of the node at `index`, walking from whichever end is closer.
+    fn cell_at(&self, index: usize) -> Option<Link<T>> {
+        if index >= self.counter {
+            return None;
+        }
+        if index < self.counter / 2 {
+            let mut current = Rc::clone(&self.head);
+            for _ in 0..index {
+                let next = Rc::clone(&current.borrow().as_ref().unwrap().next);
+                current = next;
+            }
+            Some(current)
+        } else {
+            let mut current = Rc::clone(&self.tail);
+            for _ in index + 1..self.counter {
+                let previous = current.borrow().as_ref().unwrap().previous.upgrade();
+                current = previous.unwrap();
+            }
+            Some(current)
         }
+    }
 
-        *self.tail.borrow_mut() = current_tail_node;
-        self.tail = Rc::clone(&new_tail_node);
-        self.counter += 1;
+    /// Inserts `data` so that it ends up at `index`, shifting the elements after it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index > size`.
+    pub fn insert_at(&mut self, index: usize, data: T) {
+        assert!(
+            index <= self.counter,
+            "DoublyLinkedList::insert_at: index {} out of bounds for size {}",
+            index,
+            self.counter
+        );
+        let previous = index.checked_sub(1).and_then(|index| self.cell_at(index));
+        self.insert_after(previous.as_ref(), data);
     }
 
-    pub fn remove_first(&mut self) -> Option<T> {
-        self.head.take().map(|node| {
-            self.head = node.next;
-            self.counter -= 1;
-            node.data
-        })
+    /// Removes the element at `index`, None if `index >= size`.
+    pub fn remove_at(&mut self, index: usize) -> Option<T> {
+        let cell = self.cell_at(index)?;
+        Some(self.unlink(&cell))
+    }
+
+    /// Clone of the element at `index`. The element lives in its own `RefCell`,
+    /// so a reference to it can't outlive the walk down the list.
+    pub fn get(&self, index: usize) -> Option<T>
+    where
+        T: Clone,
+    {
+        let cell = self.cell_at(index)?;
+        let data = cell.borrow().as_ref().unwrap().data.clone();
+        Some(data)
     }
 
-    pub fn remove_last(&mut self) {
-        // Handles empty list and list with a single element
+    /// Keeps only the elements for which `f` returns true, in order.
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut f: F) {
+        let mut current = Rc::clone(&self.head);
+        loop {
+            let (keep, next) = match current.borrow().as_ref() {
+                None => break,
+                Some(node) => (f(&node.data), Rc::clone(&node.next)),
+            };
+            if !keep {
+                self.unlink(&current);
+            }
+            current = next;
+        }
+    }
+
+    /// Removes every element equal to `data`, returning how many were removed.
+    pub fn remove_all(&mut self, data: &T) -> usize {
+        let before = self.counter;
+        self.retain(|element| element != data);
+        before - self.counter
+    }
+
+    /// Sorts the list in place, keeping the order of equal elements.
+    pub fn sort(&mut self)
+    where
+        T: Ord,
+    {
+        self.sort_by(T::cmp);
+    }
+
+    pub fn sort_by_key<K: Ord, F: FnMut(&T) -> K>(&mut self, mut key: F) {
+        self.sort_by(|a, b| key(a).cmp(&key(b)));
+    }
+
+    /// Bottom-up merge sort: merges runs of 1, 2, 4... nodes by relinking their `next` cells,
+    /// then fixes every `previous` in one last pass. O(n log n) time and O(1) extra space. Stable.
+    pub fn sort_by<F: FnMut(&T, &T) -> Ordering>(&mut self, mut compare: F) {
         if self.counter < 2 {
-            self.remove_first();
             return;
         }
+        let end = Rc::clone(&self.tail.borrow().as_ref().unwrap().next);
+        let mut head = Rc::clone(&self.head);
+        let mut width = 1;
+        while width < self.counter {
+            let mut rest = Some(head);
+            let mut merged_head = None;
+            let mut merged_last: Option<Link<T>> = None;
+            while let Some(left) = rest {
+                let right = split_cells(&left, width, &end);
+                rest = right
+                    .as_ref()
+                    .and_then(|right| split_cells(right, width, &end));
+                let (first, last) = merge_cells(Some(left), right, &mut compare, &end);
+                match &merged_last {
+                    Some(merged_last) => set_next(merged_last, first),
+                    None => merged_head = Some(first),
+                }
+                merged_last = Some(last);
+            }
+            head = merged_head.unwrap();
+            width *= 2;
+        }
+        self.relink(head);
+    }
 
-        self.tail = self
-            .tail
-            .take()
-            .and_then(|tail_node| tail_node.previous.upgrade())
-            .expect("Error: previous node of tail node is None");
+    /// Merges `other` into the list, both already sorted. Equal elements of the list come first.
+    pub fn merge_sorted(&mut self, mut other: DoublyLinkedList<T>)
+    where
+        T: Ord,
+    {
+        if other.counter == 0 {
+            return;
+        }
+        if self.counter == 0 {
+            std::mem::swap(self, &mut other);
+            return;
+        }
+        let end = Rc::clone(&self.tail.borrow().as_ref().unwrap().next);
+        // Leave `other` empty so its Drop has nothing left to free.
+        let other_head = std::mem::take(&mut other.head);
+        other.tail = Rc::clone(&other.head);
+        self.counter += std::mem::take(&mut other.counter);
 
-        self.counter -= 1;
+        let left = Rc::clone(&self.head);
+        let (head, _) = merge_cells(Some(left), Some(other_head), &mut T::cmp, &end);
+        self.relink(head);
     }
 
-    // To do: this doesn't handle previous pointers
-    pub fn reverse(&mut self) {
-        let mut prev_link = None;
-        let mut current_link = self.head.take();
-
-        while let Some(mut current_node) = current_link {
-            let next_link = current_node.next.take();
-            current_node.next = Rc::new(RefCell::new(prev_link));
-            prev_link = Some(current_node);
-            current_link = next_link;
-        }
-        self.head = Rc::new(RefCell::new(prev_link));
-    }
-
-    // TO DO: this is not handling previous pointers
-    pub fn remove(&mut self, data: T) {
-        let mut current = self.head.clone();
-        let mut prev = Rc::new(RefCell::new(None::<Node<T>>));
-
-        while current.borrow().is_some() {
-            let next;
-            {
-                let borrowed = current.borrow();
-                let node = borrowed.as_ref().unwrap();
-                if node.data == data {
-                    if Rc::ptr_eq(&self.head, &current) {
-                        self.head = Rc::clone(&node.next);
-
-                        if let Some(new_head) = self.head.borrow_mut().as_mut() {
-                            new_head.previous = Weak::new();
-                        }
-                    } else {
-                        prev.borrow_mut().as_mut().unwrap().next = Rc::clone(&node.next);
-
-                        if let Some(next_node) = node.next.borrow_mut().as_mut() {
-                            next_node.previous = Rc::downgrade(&prev);
-                        }
-                    }
-                    self.counter -= 1;
+    // Makes `head` the first node, then walks the `next` cells to set every `previous` and the tail.
+    fn relink(&mut self, head: Link<T>) {
+        let mut previous: Option<Link<T>> = None;
+        let mut current = Rc::clone(&head);
+        loop {
+            let next = match current.borrow_mut().as_mut() {
+                None => break,
+                Some(node) => {
+                    node.previous = previous.as_ref().map_or(Weak::new(), Rc::downgrade);
+                    Rc::clone(&node.next)
+                }
+            };
+            previous = Some(current);
+            current = next;
+        }
+        self.head = head;
+        self.tail = previous.unwrap_or_else(|| Rc::clone(&self.head));
+    }
+
+    /// Removes consecutive repeated elements, so a sorted list ends up without duplicates.
+    pub fn dedup(&mut self) {
+        self.dedup_by(|a, b| a == b);
+    }
 
-                    return;
+    pub fn dedup_by_key<K: PartialEq, F: FnMut(&T) -> K>(&mut self, mut key: F) {
+        self.dedup_by(|a, b| key(a) == key(b));
+    }
+
+    /// Removes every element for which `same(element, previous kept element)` returns true.
+    pub fn dedup_by<F: FnMut(&T, &T) -> bool>(&mut self, mut same: F) {
+        if self.counter == 0 {
+            return;
+        }
+        let mut kept = Rc::clone(&self.head);
+        loop {
+            let next = Rc::clone(&kept.borrow().as_ref().unwrap().next);
+            let duplicate = match next.borrow().as_ref() {
+                None => break,
+                Some(next_node) => same(&next_node.data, &kept.borrow().as_ref().unwrap().data),
+            };
+            if duplicate {
+                self.unlink(&next);
+            } else {
+                kept = next;
+            }
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.counter
+    }
+
+    /// Calls `f` on every element from front to back.
+    ///
+    /// Every node sits in its own `RefCell`, so a borrowing iterator would have to hand out a `Ref`
+    /// that outlives the borrow of the cell it came from. Taking a closure keeps each borrow local.
+    pub fn for_each<F: FnMut(&T)>(&self, mut f: F) {
+        let mut current = Rc::clone(&self.head);
+        loop {
+            let next = match current.borrow().as_ref() {
+                None => break,
+                Some(node) => {
+                    f(&node.data);
+                    Rc::clone(&node.next)
                 }
-                next = current.borrow().as_ref().unwrap().next.clone();
+            };
+            current = next;
+        }
+    }
+
+    /// Calls `f` on a mutable reference to every element from front to back.
+    pub fn for_each_mut<F: FnMut(&mut T)>(&mut self, mut f: F) {
+        let mut current = Rc::clone(&self.head);
+        loop {
+            let next = match current.borrow_mut().as_mut() {
+                None => break,
+                Some(node) => {
+                    f(&mut node.data);
+                    Rc::clone(&node.next)
+                }
+            };
+            current = next;
+        }
+    }
+
+    /// Cursor on the first element, or on the ghost position if the list is empty.
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_, T> {
+        let current = (self.counter > 0).then(|| Rc::clone(&self.head));
+        CursorMut {
+            list: self,
+            current,
+            index: 0,
+        }
+    }
+
+    /// Cursor on the last element, or on the ghost position if the list is empty.
+    pub fn cursor_back_mut(&mut self) -> CursorMut<'_, T> {
+        let current = (self.counter > 0).then(|| Rc::clone(&self.tail));
+        let index = self.counter.saturating_sub(1);
+        CursorMut {
+            list: self,
+            current,
+            index,
+        }
+    }
+
+    /// Walks the list forwards and backwards, checking that `next` and `previous` agree,
+    /// that `head` and `tail` are the ends of the list and that `counter` matches both walks.
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if self.counter == 0 {
+            if self.head.borrow().is_some() || !Rc::ptr_eq(&self.head, &self.tail) {
+                return Err("an empty list must have head and tail on the end marker");
             }
-            prev = current.clone();
+            return Ok(());
+        }
+
+        let mut count = 0;
+        let mut previous: Option<Link<T>> = None;
+        let mut current = Rc::clone(&self.head);
+        loop {
+            let next = match current.borrow().as_ref() {
+                None => break,
+                Some(node) => {
+                    let points_back = match (&previous, node.previous.upgrade()) {
+                        (None, None) => true,
+                        (Some(expected), Some(actual)) => Rc::ptr_eq(expected, &actual),
+                        _ => false,
+                    };
+                    if !points_back {
+                        return Err("a previous pointer doesn't match the next pointers");
+                    }
+                    Rc::clone(&node.next)
+                }
+            };
+            count += 1;
+            previous = Some(current);
             current = next;
         }
+        if count != self.counter {
+            return Err("counter doesn't match the number of nodes going forwards");
+        }
+        if !previous.is_some_and(|last| Rc::ptr_eq(&last, &self.tail)) {
+            return Err("tail isn't the last node");
+        }
+
+        let mut count = 0;
+        let mut current = Some(Rc::clone(&self.tail));
+        let mut first = Rc::clone(&self.tail);
+        while let Some(cell) = current {
+            count += 1;
+            current = cell
+                .borrow()
+                .as_ref()
+                .and_then(|node| node.previous.upgrade());
+            first = cell;
+        }
+        if count != self.counter {
+            return Err("counter doesn't match the number of nodes going backwards");
+        }
+        if !Rc::ptr_eq(&first, &self.head) {
+            return Err("head isn't the first node");
+        }
+        Ok(())
+    }
+}
+
+fn set_next<T>(cell: &Link<T>, next: Link<T>) {
+    cell.borrow_mut().as_mut().unwrap().next = next;
+}
+
+// Cuts the chain starting at `first` after `len` nodes, ending them with `end`. Returns the rest.
+fn split_cells<T>(first: &Link<T>, len: usize, end: &Link<T>) -> Option<Link<T>> {
+    let mut current = Rc::clone(first);
+    for _ in 1..len {
+        let next = Rc::clone(&current.borrow().as_ref().unwrap().next);
+        if next.borrow().is_none() {
+            return None;
+        }
+        current = next;
+    }
+    let mut borrowed = current.borrow_mut();
+    let node = borrowed.as_mut().unwrap();
+    if node.next.borrow().is_none() {
+        return None;
+    }
+    Some(std::mem::replace(&mut node.next, Rc::clone(end)))
+}
+
+// Merges two sorted chains ending on empty cells, taking from `left` on ties.
+// Returns the first and last node of the merged chain, which ends with `end`.
+// Only `next` is relinked, `previous` is fixed afterwards by `relink`.
+fn merge_cells<T, F: FnMut(&T, &T) -> Ordering>(
+    mut left: Option<Link<T>>,
+    mut right: Option<Link<T>>,
+    compare: &mut F,
+    end: &Link<T>,
+) -> (Link<T>, Link<T>) {
+    let mut first: Option<Link<T>> = None;
+    let mut last: Option<Link<T>> = None;
+    loop {
+        let take_right = match (&left, &right) {
+            (Some(l), Some(r)) => {
+                let (l, r) = (l.borrow(), r.borrow());
+                compare(&r.as_ref().unwrap().data, &l.as_ref().unwrap().data) == Ordering::Less
+            }
+            (Some(_), None) => false,
+            (None, Some(_)) => true,
+            (None, None) => break,
+        };
+        let source = if take_right { &mut right } else { &mut left };
+        let cell = source.take().unwrap();
+        let next = Rc::clone(&cell.borrow().as_ref().unwrap().next);
+        if next.borrow().is_some() {
+            *source = Some(next);
+        }
+        match &last {
+            Some(last) => set_next(last, Rc::clone(&cell)),
+            None => first = Some(Rc::clone(&cell)),
+        }
+        last = Some(cell);
+    }
+    let last = last.expect("merge_cells: both chains are empty");
+    set_next(&last, Rc::clone(end));
+    (first.unwrap(), last)
+}
+
+/// A cursor over a `DoublyLinkedList` that can edit the list around its position in O(1).
+///
+/// Besides the elements there's a "ghost" position, after the last element and before the first one,
+/// so moving past either end wraps through it.
+pub struct CursorMut<'a, T: PartialEq> {
+    list: &'a mut DoublyLinkedList<T>,
+    // Cell of the current node, None on the ghost position.
+    current: Option<Link<T>>,
+    // Index of the current node, equal to the length of the list on the ghost position.
+    index: usize,
+}
+
+impl<'a, T: PartialEq> CursorMut<'a, T> {
+    /// Index of the current element, None on the ghost position.
+    pub fn index(&self) -> Option<usize> {
+        self.current.as_ref().map(|_| self.index)
+    }
+
+    pub fn current(&self) -> Option<Ref<'_, T>> {
+        let cell = self.current.as_ref()?;
+        Some(Ref::map(cell.borrow(), |node| &node.as_ref().unwrap().data))
+    }
+
+    pub fn current_mut(&mut self) -> Option<RefMut<'_, T>> {
+        let cell = self.current.as_ref()?;
+        Some(RefMut::map(cell.borrow_mut(), |node| {
+            &mut node.as_mut().unwrap().data
+        }))
+    }
+
+    /// Moves to the next element, from the last one to the ghost position and from there to the first one.
+    pub fn move_next(&mut self) {
+        match self.current.take() {
+            None => {
+                if self.list.counter > 0 {
+                    self.current = Some(Rc::clone(&self.list.head));
+                }
+                self.index = 0;
+            }
+            Some(cell) => {
+                let next = Rc::clone(&cell.borrow().as_ref().unwrap().next);
+                if next.borrow().is_some() {
+                    self.current = Some(next);
+                }
+                self.index += 1;
+            }
+        }
+    }
+
+    /// Moves to the previous element, from the first one to the ghost position and from there to the last one.
+    pub fn move_prev(&mut self) {
+        match self.current.take() {
+            None => {
+                if self.list.counter > 0 {
+                    self.current = Some(Rc::clone(&self.list.tail));
+                }
+                self.index = self.list.counter.saturating_sub(1);
+            }
+            Some(cell) => {
+                self.current = cell.borrow().as_ref().unwrap().previous.upgrade();
+                self.index = match self.current {
+                    Some(_) => self.index - 1,
+                    None => self.list.counter,
+                };
+            }
+        }
+    }
+
+    /// Inserts `data` before the current element, or at the back on the ghost position.
+    pub fn insert_before(&mut self, data: T) {
+        let previous = match &self.current {
+            Some(cell) => cell.borrow().as_ref().unwrap().previous.upgrade(),
+            None if self.list.counter > 0 => Some(Rc::clone(&self.list.tail)),
+            None => None,
+        };
+        self.list.insert_after(previous.as_ref(), data);
+        self.index += 1;
+    }
+
+    /// Inserts `data` after the current element, or at the front on the ghost position.
+    pub fn insert_after(&mut self, data: T) {
+        self.list.insert_after(self.current.as_ref(), data);
+        if self.current.is_none() {
+            self.index += 1;
+        }
+    }
+
+    /// Removes the current element and moves to the next one (or the ghost position).
+    pub fn remove_current(&mut self) -> Option<T> {
+        let cell = self.current.take()?;
+        let next = Rc::clone(&cell.borrow().as_ref().unwrap().next);
+        let data = self.list.unlink(&cell);
+        if next.borrow().is_some() {
+            self.current = Some(next);
+        }
+        Some(data)
+    }
+
+    /// Splits the list after the current element, returning everything after it.
+    /// On the ghost position the whole list is returned.
+    pub fn split_after(&mut self) -> DoublyLinkedList<T> {
+        let cell = match &self.current {
+            Some(cell) => Rc::clone(cell),
+            None => {
+                self.index = 0;
+                return std::mem::take(self.list);
+            }
+        };
+        let first = {
+            let mut borrowed = cell.borrow_mut();
+            let node = borrowed.as_mut().unwrap();
+            if node.next.borrow().is_none() {
+                return DoublyLinkedList::new();
+            }
+            // The current node gets a new end marker, the old one stays with the returned tail.
+            std::mem::replace(&mut node.next, Rc::new(RefCell::new(None)))
+        };
+        first.borrow_mut().as_mut().unwrap().previous = Weak::new();
+
+        let counter = self.list.counter - self.index - 1;
+        let tail = std::mem::replace(&mut self.list.tail, cell);
+        self.list.counter = self.index + 1;
+        DoublyLinkedList {
+            head: first,
+            tail,
+            counter,
+        }
+    }
+
+    /// Moves all the elements of `other` after the current element, or at the front on the ghost position.
+    pub fn splice_after(&mut self, mut other: DoublyLinkedList<T>) {
+        if other.counter == 0 {
+            return;
+        }
+        // Leave `other` empty so its Drop has nothing left to free.
+        let first = std::mem::take(&mut other.head);
+        let last = std::mem::replace(&mut other.tail, Rc::clone(&other.head));
+        let count = std::mem::take(&mut other.counter);
+
+        let next = match &self.current {
+            Some(cell) => Rc::clone(&cell.borrow().as_ref().unwrap().next),
+            None => Rc::clone(&self.list.head),
+        };
+        match next.borrow_mut().as_mut() {
+            Some(next_node) => next_node.previous = Rc::downgrade(&last),
+            None => self.list.tail = Rc::clone(&last),
+        }
+        last.borrow_mut().as_mut().unwrap().next = next;
+        match &self.current {
+            Some(cell) => {
+                first.borrow_mut().as_mut().unwrap().previous = Rc::downgrade(cell);
+                cell.borrow_mut().as_mut().unwrap().next = first;
+            }
+            None => {
+                self.list.head = first;
+                self.index += count;
+            }
+        }
+        self.list.counter += count;
+    }
+}
+
+// The default drop would drop the first node, which drops the second one and so on,
+// recursing once per node. Taking every node out of its cell first keeps it iterative.
+impl<T: PartialEq> Drop for DoublyLinkedList<T> {
+    fn drop(&mut self) {
+        let mut current = self.head.take();
+        while let Some(node) = current {
+            current = node.next.take();
+        }
+    }
+}
+
+impl<T: PartialEq> Default for DoublyLinkedList<T> {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -169,11 +736,24 @@ impl<T: PartialEq> Iterator for IntoIter<T> {
     fn next(&mut self) -> Option<Self::Item> {
         self.0.remove_first()
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.counter, Some(self.0.counter))
+    }
 }
 
+impl<T: PartialEq> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.remove_last()
+    }
+}
+
+impl<T: PartialEq> ExactSizeIterator for IntoIter<T> {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::safe_rust::test_utils::scrambled;
 
     #[test]
     fn add_first() {
@@ -428,4 +1008,420 @@ mod tests {
         assert_eq!(list.counter, 0);
         assert!(list.remove_first().is_none());
     }
+
+    fn collect_backwards(list: &DoublyLinkedList<i32>) -> Vec<i32> {
+        let mut values = Vec::new();
+        let mut current = Some(Rc::clone(&list.tail));
+        while let Some(cell) = current {
+            current = cell.borrow().as_ref().and_then(|node| {
+                values.push(node.data);
+                node.previous.upgrade()
+            });
+        }
+        values
+    }
+
+    #[test]
+    fn validate_after_every_operation() {
+        let mut list = DoublyLinkedList::new();
+        assert_eq!(list.validate(), Ok(()));
+        for i in 0..10 {
+            if i % 2 == 0 {
+                list.add_last(i);
+            } else {
+                list.add_first(i);
+            }
+            assert_eq!(list.validate(), Ok(()));
+        }
+        list.remove(0);
+        assert_eq!(list.validate(), Ok(()));
+        list.remove_first();
+        assert_eq!(list.validate(), Ok(()));
+        list.remove_last();
+        assert_eq!(list.validate(), Ok(()));
+        list.reverse();
+        assert_eq!(list.validate(), Ok(()));
+        while list.remove_first().is_some() {
+            assert_eq!(list.validate(), Ok(()));
+        }
+        assert_eq!(list.counter, 0);
+    }
+
+    #[test]
+    fn reverse_keeps_previous_pointers() {
+        let mut list = DoublyLinkedList::new();
+        for i in 1..=5 {
+            list.add_last(i);
+        }
+        list.reverse();
+        assert_eq!(list.validate(), Ok(()));
+        assert_eq!(collect_backwards(&list), vec![1, 2, 3, 4, 5]);
+        list.add_last(0);
+        list.add_first(6);
+        assert_eq!(list.validate(), Ok(()));
+        assert_eq!(
+            list.into_iter().collect::<Vec<_>>(),
+            vec![6, 5, 4, 3, 2, 1, 0]
+        );
+    }
+
+    #[test]
+    fn remove_tail_updates_tail() {
+        let mut list = DoublyLinkedList::new();
+        list.add_last(1);
+        list.add_last(2);
+        list.add_last(3);
+        list.remove(3);
+        assert_eq!(list.validate(), Ok(()));
+        assert_eq!(list.tail.borrow().as_ref().unwrap().data, 2);
+        list.add_last(4);
+        assert_eq!(collect_backwards(&list), vec![4, 2, 1]);
+    }
+
+    #[test]
+    fn add_after_emptying() {
+        let mut list = DoublyLinkedList::new();
+        list.add_last(1);
+        list.remove_last();
+        list.add_last(2);
+        list.add_first(1);
+        assert_eq!(list.validate(), Ok(()));
+        list.remove(1);
+        list.remove(2);
+        list.add_first(3);
+        assert_eq!(list.validate(), Ok(()));
+        assert_eq!(list.into_iter().collect::<Vec<_>>(), vec![3]);
+    }
+
+    #[test]
+    fn drop_long_list() {
+        let mut list = DoublyLinkedList::new();
+        for i in 0..200_000 {
+            list.add_last(i);
+        }
+        drop(list);
+    }
+
+    #[test]
+    fn into_iter_both_ends() {
+        let mut list = DoublyLinkedList::new();
+        for i in 1..=5 {
+            list.add_last(i);
+        }
+        let mut iter = list.into_iter();
+        assert_eq!(iter.len(), 5);
+        assert_eq!(iter.next_back(), Some(5));
+        assert_eq!(iter.next(), Some(1));
+        assert_eq!(iter.len(), 3);
+        assert_eq!(iter.rev().collect::<Vec<_>>(), vec![4, 3, 2]);
+    }
+
+    #[test]
+    fn into_iter_rev() {
+        let mut list = DoublyLinkedList::new();
+        for i in 1..=4 {
+            list.add_first(i);
+        }
+        assert_eq!(list.into_iter().rev().collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn for_each() {
+        let mut list = DoublyLinkedList::new();
+        for i in 1..=4 {
+            list.add_last(i);
+        }
+        let mut values = Vec::new();
+        list.for_each(|value| values.push(*value));
+        assert_eq!(values, vec![1, 2, 3, 4]);
+        assert_eq!(list.size(), 4);
+
+        list.for_each_mut(|value| *value *= 10);
+        assert_eq!(list.validate(), Ok(()));
+        assert_eq!(list.into_iter().collect::<Vec<_>>(), vec![10, 20, 30, 40]);
+    }
+
+    #[test]
+    fn for_each_empty_list() {
+        let mut list: DoublyLinkedList<i32> = DoublyLinkedList::new();
+        let mut calls = 0;
+        list.for_each(|_| calls += 1);
+        list.for_each_mut(|_| calls += 1);
+        assert_eq!(calls, 0);
+    }
+
+    fn list_of(values: &[i32]) -> DoublyLinkedList<i32> {
+        let mut list = DoublyLinkedList::new();
+        for value in values {
+            list.add_last(*value);
+        }
+        list
+    }
+
+    fn to_vec(list: DoublyLinkedList<i32>) -> Vec<i32> {
+        list.into_iter().collect()
+    }
+
+    #[test]
+    fn cursor_moves_through_ghost() {
+        let mut list = list_of(&[1, 2, 3]);
+        let mut cursor = list.cursor_front_mut();
+        assert_eq!(*cursor.current().unwrap(), 1);
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!((cursor.index(), *cursor.current().unwrap()), (Some(2), 3));
+        cursor.move_next();
+        assert_eq!(cursor.index(), None);
+        assert!(cursor.current().is_none());
+        cursor.move_next();
+        assert_eq!((cursor.index(), *cursor.current().unwrap()), (Some(0), 1));
+        cursor.move_prev();
+        assert_eq!(cursor.index(), None);
+        cursor.move_prev();
+        assert_eq!((cursor.index(), *cursor.current().unwrap()), (Some(2), 3));
+        *cursor.current_mut().unwrap() = 30;
+        assert_eq!(to_vec(list), vec![1, 2, 30]);
+    }
+
+    #[test]
+    fn cursor_on_empty_list() {
+        let mut list = DoublyLinkedList::new();
+        let mut cursor = list.cursor_back_mut();
+        assert_eq!(cursor.index(), None);
+        cursor.move_next();
+        cursor.move_prev();
+        assert_eq!(cursor.remove_current(), None);
+        cursor.insert_after(2);
+        cursor.insert_before(3);
+        cursor.insert_after(1);
+        assert_eq!(cursor.index(), None);
+        cursor.move_next();
+        assert_eq!(cursor.index(), Some(0));
+        assert_eq!(list.validate(), Ok(()));
+        assert_eq!(to_vec(list), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn cursor_insert() {
+        let mut list = list_of(&[1, 3]);
+        let mut cursor = list.cursor_front_mut();
+        cursor.insert_after(2);
+        cursor.insert_before(0);
+        assert_eq!((cursor.index(), *cursor.current().unwrap()), (Some(1), 1));
+        cursor.move_prev();
+        cursor.move_prev();
+        cursor.insert_before(4);
+        assert_eq!(cursor.index(), None);
+        assert_eq!(list.validate(), Ok(()));
+        assert_eq!(to_vec(list), vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn cursor_remove_current() {
+        let mut list = list_of(&[1, 2, 3, 4]);
+        let mut cursor = list.cursor_front_mut();
+        cursor.move_next();
+        assert_eq!(cursor.remove_current(), Some(2));
+        assert_eq!((cursor.index(), *cursor.current().unwrap()), (Some(1), 3));
+        cursor.move_next();
+        assert_eq!(cursor.remove_current(), Some(4));
+        assert_eq!(cursor.index(), None);
+        cursor.move_next();
+        assert_eq!(cursor.remove_current(), Some(1));
+        assert_eq!(list.validate(), Ok(()));
+        assert_eq!(to_vec(list), vec![3]);
+    }
+
+    #[test]
+    fn cursor_remove_everything() {
+        let mut list = list_of(&[1, 2, 3]);
+        let mut cursor = list.cursor_back_mut();
+        while cursor.remove_current().is_some() {
+            cursor.move_prev();
+        }
+        assert_eq!(list.validate(), Ok(()));
+        assert_eq!(list.size(), 0);
+        list.add_last(5);
+        assert_eq!(to_vec(list), vec![5]);
+    }
+
+    #[test]
+    fn cursor_split_after() {
+        let mut list = list_of(&[1, 2, 3, 4, 5]);
+        let mut cursor = list.cursor_front_mut();
+        cursor.move_next();
+        let mut back = cursor.split_after();
+        assert_eq!(cursor.index(), Some(1));
+        cursor.insert_after(6);
+        assert_eq!(list.validate(), Ok(()));
+        assert_eq!(back.validate(), Ok(()));
+        back.add_last(7);
+        assert_eq!(to_vec(list), vec![1, 2, 6]);
+        assert_eq!(to_vec(back), vec![3, 4, 5, 7]);
+    }
+
+    #[test]
+    fn cursor_split_after_ends() {
+        let mut list = list_of(&[1, 2]);
+        let mut cursor = list.cursor_back_mut();
+        assert_eq!(to_vec(cursor.split_after()), vec![]);
+        cursor.move_next();
+        let all = cursor.split_after();
+        assert_eq!(cursor.index(), None);
+        assert_eq!(list.validate(), Ok(()));
+        assert_eq!(list.size(), 0);
+        assert_eq!(to_vec(all), vec![1, 2]);
+    }
+
+    #[test]
+    fn cursor_splice_after() {
+        let mut list = list_of(&[1, 5]);
+        let mut cursor = list.cursor_front_mut();
+        cursor.splice_after(list_of(&[2, 3, 4]));
+        assert_eq!((cursor.index(), *cursor.current().unwrap()), (Some(0), 1));
+        cursor.move_prev();
+        cursor.splice_after(list_of(&[-1, 0]));
+        assert_eq!(cursor.index(), None);
+        cursor.move_prev();
+        cursor.splice_after(list_of(&[6]));
+        cursor.splice_after(DoublyLinkedList::new());
+        assert_eq!(list.validate(), Ok(()));
+        assert_eq!(list.size(), 8);
+        assert_eq!(to_vec(list), vec![-1, 0, 1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn cursor_splice_into_empty_list() {
+        let mut list = DoublyLinkedList::new();
+        let mut cursor = list.cursor_front_mut();
+        cursor.splice_after(list_of(&[1, 2]));
+        cursor.move_prev();
+        assert_eq!(*cursor.current().unwrap(), 2);
+        assert_eq!(list.validate(), Ok(()));
+        assert_eq!(to_vec(list), vec![1, 2]);
+    }
+
+    #[test]
+    fn remove_returns_whether_found() {
+        let mut list = list_of(&[1, 2, 3]);
+        assert!(list.remove(2));
+        assert!(!list.remove(2));
+        assert_eq!(list.remove_last(), Some(3));
+        assert_eq!(list.remove_last(), Some(1));
+        assert_eq!(list.remove_last(), None);
+        assert_eq!(list.validate(), Ok(()));
+    }
+
+    #[test]
+    fn insert_at() {
+        let mut list = list_of(&[1, 4]);
+        list.insert_at(1, 3);
+        list.insert_at(1, 2);
+        list.insert_at(0, 0);
+        list.insert_at(5, 5);
+        assert_eq!(list.validate(), Ok(()));
+        assert_eq!(to_vec(list), vec![0, 1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn insert_at_out_of_bounds() {
+        let mut list = list_of(&[1]);
+        list.insert_at(2, 2);
+    }
+
+    #[test]
+    fn remove_at_and_get() {
+        let mut list = list_of(&[0, 1, 2, 3, 4, 5, 6]);
+        for index in 0..7 {
+            assert_eq!(list.get(index), Some(index as i32));
+        }
+        assert_eq!(list.get(7), None);
+        assert_eq!(list.remove_at(7), None);
+        assert_eq!(list.remove_at(5), Some(5));
+        assert_eq!(list.remove_at(1), Some(1));
+        assert_eq!(list.remove_at(4), Some(6));
+        assert_eq!(list.remove_at(0), Some(0));
+        assert_eq!(list.validate(), Ok(()));
+        assert_eq!(to_vec(list), vec![2, 3, 4]);
+    }
+
+    #[test]
+    fn retain_and_remove_all() {
+        let mut list = list_of(&[2, 1, 2, 3, 4, 5, 6, 2]);
+        assert_eq!(list.remove_all(&2), 3);
+        assert_eq!(list.remove_all(&7), 0);
+        assert_eq!(list.validate(), Ok(()));
+        list.retain(|value| value % 2 == 1);
+        assert_eq!(list.validate(), Ok(()));
+        assert_eq!(list.size(), 3);
+        list.retain(|_| false);
+        assert_eq!(list.validate(), Ok(()));
+        list.add_last(7);
+        assert_eq!(to_vec(list), vec![7]);
+    }
+
+    #[test]
+    fn sort() {
+        let mut list = list_of(&scrambled(1000));
+        list.sort();
+        assert_eq!(list.validate(), Ok(()));
+        assert_eq!(list.size(), 1000);
+        assert_eq!(
+            collect_backwards(&list),
+            (0..1000).rev().collect::<Vec<_>>()
+        );
+
+        let mut list = list_of(&[3, 1, 2]);
+        list.sort_by(|a, b| b.cmp(a));
+        assert_eq!(list.validate(), Ok(()));
+        list.add_last(0);
+        assert_eq!(to_vec(list), vec![3, 2, 1, 0]);
+
+        let mut list = list_of(&[1]);
+        list.sort();
+        assert_eq!(list.validate(), Ok(()));
+    }
+
+    #[test]
+    fn sort_is_stable() {
+        let mut list = DoublyLinkedList::new();
+        for (index, value) in scrambled(100).into_iter().enumerate() {
+            list.add_last((value % 7, index));
+        }
+        list.sort_by_key(|&(key, _)| key);
+        let sorted: Vec<_> = list.into_iter().collect();
+        for pair in sorted.windows(2) {
+            assert!(pair[0].0 < pair[1].0 || (pair[0].0 == pair[1].0 && pair[0].1 < pair[1].1));
+        }
+    }
+
+    #[test]
+    fn merge_sorted() {
+        let mut list = list_of(&[1, 3, 5, 7]);
+        list.merge_sorted(list_of(&[0, 3, 4, 8, 9]));
+        assert_eq!(list.validate(), Ok(()));
+        assert_eq!(list.size(), 9);
+        assert_eq!(collect_backwards(&list), vec![9, 8, 7, 5, 4, 3, 3, 1, 0]);
+
+        let mut list = DoublyLinkedList::new();
+        list.merge_sorted(list_of(&[1, 2]));
+        list.merge_sorted(DoublyLinkedList::new());
+        assert_eq!(list.validate(), Ok(()));
+        list.add_last(3);
+        assert_eq!(to_vec(list), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn dedup() {
+        let mut list = list_of(&[1, 1, 2, 3, 3, 3, 1, 4, 4]);
+        list.dedup();
+        assert_eq!(list.validate(), Ok(()));
+        assert_eq!(to_vec(list), vec![1, 2, 3, 1, 4]);
+
+        let mut list = list_of(&[10, 11, 20, 25, 31]);
+        list.dedup_by_key(|value| value / 10);
+        assert_eq!(list.validate(), Ok(()));
+        assert_eq!(to_vec(list), vec![10, 20, 31]);
+    }
 }
@@ -1,9 +1,19 @@
 /**
  * Singly Linked List
+ *
+ * Every node owns the next one through a `Box`, so a node can't be linked back into the list
+ * it belongs to: a cycle can't be built, and the list needs no cycle detection.
  */
+use std::cmp::Ordering;
 
 // Iterator structs
 pub struct IntoIter<T>(LinkedList<T>);
+pub struct Iter<'a, T> {
+    next: Option<&'a Node<T>>,
+}
+pub struct IterMut<'a, T> {
+    next: Option<&'a mut Node<T>>,
+}
 
 type Link<T> = Option<Box<Node<T>>>;
 struct Node<T> {
@@ -51,29 +61,89 @@ impl<T> LinkedList<T> {
         })
     }
 
-    pub fn remove_last(&mut self) {
-        if self.head.is_none() {
-            return;
+    pub fn remove_last(&mut self) -> Option<T> {
+        let mut current_link = &mut self.head;
+        while current_link.as_ref()?.next.is_some() {
+            current_link = &mut current_link.as_mut().unwrap().next;
         }
+        let node = current_link.take()?;
+        self.counter -= 1;
+        Some(node.data)
+    }
 
-        if self.counter == 1 {
-            self.remove_first();
-            return;
+    // Link holding the node at `index`, the trailing None if `index == counter`.
+    fn link_at(&mut self, index: usize) -> &mut Link<T> {
+        let mut current_link = &mut self.head;
+        for _ in 0..index {
+            current_link = &mut current_link
+                .as_mut()
+                .expect("Error link_at: index is past the end of the list")
+                .next;
         }
+        current_link
+    }
 
-        let mut current_link = &mut self.head;
-        for _ in 0..(self.counter - 2) {
-            if let Some(node) = current_link {
-                current_link = &mut node.next;
+    /// Inserts `data` so that it ends up at `index`, shifting the elements after it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index > size`.
+    pub fn insert_at(&mut self, index: usize, data: T) {
+        assert!(
+            index <= self.counter,
+            "LinkedList::insert_at: index {} out of bounds for size {}",
+            index,
+            self.counter
+        );
+        let link = self.link_at(index);
+        let next = link.take();
+        *link = Some(Box::new(Node { data, next }));
+        self.counter += 1;
+    }
+
+    /// Removes the element at `index`, None if `index >= size`.
+    pub fn remove_at(&mut self, index: usize) -> Option<T> {
+        if index >= self.counter {
+            return None;
+        }
+        let link = self.link_at(index);
+        let node = link.take()?;
+        *link = node.next;
+        self.counter -= 1;
+        Some(node.data)
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        let mut current_link = &self.head;
+        for _ in 0..index {
+            current_link = &current_link.as_ref()?.next;
+        }
+        current_link.as_ref().map(|node| &node.data)
+    }
+
+    /// Keeps only the elements for which `f` returns true, in order.
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut f: F) {
+        // Detach the whole chain and relink the nodes that stay.
+        let mut rest = self.head.take();
+        let mut tail_link = &mut self.head;
+        while let Some(mut node) = rest {
+            rest = node.next.take();
+            if f(&node.data) {
+                tail_link = &mut tail_link.insert(node).next;
             } else {
-                panic!("Error remove_last: list count is inconsistent with actual number of nodes. 'self.counter' is bigger than the actual number of nodes");
+                self.counter -= 1;
             }
         }
+    }
 
-        if let Some(node) = current_link {
-            node.next = None;
-        }
-        self.counter -= 1;
+    /// Removes every element equal to `data`, returning how many were removed.
+    pub fn remove_all(&mut self, data: &T) -> usize
+    where
+        T: PartialEq,
+    {
+        let before = self.counter;
+        self.retain(|element| element != data);
+        before - self.counter
     }
 
     pub fn reverse(&mut self) {
@@ -89,6 +159,184 @@ impl<T> LinkedList<T> {
         self.head = prev_link;
     }
 
+    /// Sorts the list in place, keeping the order of equal elements.
+    pub fn sort(&mut self)
+    where
+        T: Ord,
+    {
+        self.sort_by(T::cmp);
+    }
+
+    pub fn sort_by_key<K: Ord, F: FnMut(&T) -> K>(&mut self, mut key: F) {
+        self.sort_by(|a, b| key(a).cmp(&key(b)));
+    }
+
+    /// Bottom-up merge sort: merges runs of 1, 2, 4... nodes by relinking them,
+    /// O(n log n) time and O(1) extra space. Stable.
+    pub fn sort_by<F: FnMut(&T, &T) -> Ordering>(&mut self, mut compare: F) {
+        let mut width = 1;
+        while width < self.counter {
+            let mut rest = self.head.take();
+            let mut tail_link = &mut self.head;
+            while rest.is_some() {
+                let mut left = rest;
+                let mut right = split_link(&mut left, width);
+                rest = split_link(&mut right, width);
+                *tail_link = merge_links(left, right, &mut compare);
+                while let Some(node) = tail_link {
+                    tail_link = &mut node.next;
+                }
+            }
+            width *= 2;
+        }
+    }
+
+    /// Merges `other` into the list, both already sorted. Equal elements of the list come first.
+    pub fn merge_sorted(&mut self, mut other: LinkedList<T>)
+    where
+        T: Ord,
+    {
+        self.head = merge_links(self.head.take(), other.head.take(), &mut T::cmp);
+        self.counter += other.counter;
+    }
+
+    /// Removes consecutive repeated elements, so a sorted list ends up without duplicates.
+    pub fn dedup(&mut self)
+    where
+        T: PartialEq,
+    {
+        self.dedup_by(|a, b| a == b);
+    }
+
+    pub fn dedup_by_key<K: PartialEq, F: FnMut(&T) -> K>(&mut self, mut key: F) {
+        self.dedup_by(|a, b| key(a) == key(b));
+    }
+
+    /// Removes every element for which `same(element, previous kept element)` returns true.
+    pub fn dedup_by<F: FnMut(&T, &T) -> bool>(&mut self, mut same: F) {
+        let mut current = self.head.as_mut();
+        while let Some(node) = current {
+            while node
+                .next
+                .as_ref()
+                .is_some_and(|next| same(&next.data, &node.data))
+            {
+                let removed = node.next.take().unwrap();
+                node.next = removed.next;
+                self.counter -= 1;
+            }
+            current = node.next.as_mut();
+        }
+    }
+
+    /// Splits the list in two at `at`: the list keeps the first `at` elements and the rest is returned.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `at > size`.
+    pub fn split_off(&mut self, at: usize) -> LinkedList<T> {
+        assert!(
+            at <= self.counter,
+            "LinkedList::split_off: index {} out of bounds for size {}",
+            at,
+            self.counter
+        );
+        let head = self.link_at(at).take();
+        let counter = self.counter - at;
+        self.counter = at;
+        LinkedList { head, counter }
+    }
+
+    /// Moves all the elements of `other` to the end of the list, leaving `other` empty.
+    pub fn append(&mut self, other: &mut LinkedList<T>) {
+        let end = self.link_at(self.counter);
+        *end = other.head.take();
+        self.counter += std::mem::take(&mut other.counter);
+    }
+
+    /// Rotates the list so that the first `k` elements (modulo the size) move to the end.
+    pub fn rotate_left(&mut self, k: usize) {
+        let k = match self.counter {
+            0 => return,
+            counter => k % counter,
+        };
+        if k == 0 {
+            return;
+        }
+        let mut front = self.head.take();
+        self.head = split_link(&mut front, k);
+        let end = self.link_at(self.counter - k);
+        *end = front;
+    }
+
+    /// Rotates the list so that the last `k` elements (modulo the size) move to the front.
+    pub fn rotate_right(&mut self, k: usize) {
+        if self.counter > 0 {
+            self.rotate_left(self.counter - k % self.counter);
+        }
+    }
+
+    /// Middle element, the second of the two middle ones when the size is even.
+    pub fn middle(&self) -> Option<&T> {
+        self.get(self.counter / 2)
+    }
+
+    /// Reverses every run of `k` consecutive elements, including a shorter last one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k == 0`.
+    pub fn reverse_in_groups(&mut self, k: usize) {
+        assert!(k > 0, "LinkedList::reverse_in_groups: k must be positive");
+        let mut rest = self.head.take();
+        let mut tail_link = &mut self.head;
+        while rest.is_some() {
+            // Pushing the nodes to the front of `group` one by one reverses them.
+            let mut group = None;
+            for _ in 0..k {
+                let Some(mut node) = rest else { break };
+                rest = node.next.take();
+                node.next = group;
+                group = Some(node);
+            }
+            *tail_link = group;
+            while let Some(node) = tail_link {
+                tail_link = &mut node.next;
+            }
+        }
+    }
+
+    /// Whether the list reads the same forwards and backwards.
+    ///
+    /// Keeps references to the first half, O(n) extra space. `is_palindrome_in_place` answers
+    /// in O(1) extra space, but needs `&mut self`.
+    pub fn is_palindrome(&self) -> bool
+    where
+        T: PartialEq,
+    {
+        let half = self.counter / 2;
+        let first_half: Vec<&T> = self.iter().take(half).collect();
+        self.iter()
+            .skip(self.counter - half)
+            .zip(first_half.into_iter().rev())
+            .all(|(a, b)| a == b)
+    }
+
+    /// Same as `is_palindrome` in O(1) extra space: the second half is reversed to be compared
+    /// with the first one, then reversed back, so the list is unchanged afterwards.
+    pub fn is_palindrome_in_place(&mut self) -> bool
+    where
+        T: PartialEq,
+    {
+        let half = self.counter / 2;
+        let mut second_half = self.split_off(self.counter - half);
+        second_half.reverse();
+        let is_palindrome = self.iter().zip(second_half.iter()).all(|(a, b)| a == b);
+        second_half.reverse();
+        self.append(&mut second_half);
+        is_palindrome
+    }
+
     pub fn peek(&self) -> Option<&T> {
         self.head.as_ref().map(|node| &node.data)
     }
@@ -97,6 +345,38 @@ impl<T> LinkedList<T> {
         self.head.as_mut().map(|node| &mut node.data)
     }
 
+    pub fn peek_last(&self) -> Option<&T> {
+        self.iter().last()
+    }
+
+    pub fn peek_last_mut(&mut self) -> Option<&mut T> {
+        self.iter_mut().last()
+    }
+
+    pub fn contains(&self, data: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        self.iter().any(|element| element == data)
+    }
+
+    /// First element for which `predicate` returns true.
+    pub fn find<P: FnMut(&T) -> bool>(&self, mut predicate: P) -> Option<&T> {
+        self.iter().find(|element| predicate(element))
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            next: self.head.as_deref(),
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            next: self.head.as_deref_mut(),
+        }
+    }
+
     pub fn size(&self) -> usize {
         self.counter
     }
@@ -111,6 +391,46 @@ impl<T> LinkedList<T> {
     }
 }
 
+// Cuts `link` after `len` nodes and returns the rest.
+fn split_link<T>(link: &mut Link<T>, len: usize) -> Link<T> {
+    let mut current_link = link;
+    for _ in 0..len {
+        match current_link {
+            Some(node) => current_link = &mut node.next,
+            None => return None,
+        }
+    }
+    current_link.take()
+}
+
+// Merges two sorted chains, taking from `left` on ties.
+fn merge_links<T, F: FnMut(&T, &T) -> Ordering>(
+    mut left: Link<T>,
+    mut right: Link<T>,
+    compare: &mut F,
+) -> Link<T> {
+    let mut merged = None;
+    let mut tail_link = &mut merged;
+    loop {
+        let source = match (&left, &right) {
+            (Some(l), Some(r)) if compare(&r.data, &l.data) == Ordering::Less => &mut right,
+            (Some(_), Some(_)) => &mut left,
+            // The rest of the other chain is already in order.
+            (Some(_), None) => {
+                *tail_link = left;
+                return merged;
+            }
+            (None, _) => {
+                *tail_link = right;
+                return merged;
+            }
+        };
+        let mut node = source.take().unwrap();
+        *source = node.next.take();
+        tail_link = &mut tail_link.insert(node).next;
+    }
+}
+
 impl<T> Iterator for IntoIter<T> {
     type Item = T;
     fn next(&mut self) -> Option<Self::Item> {
@@ -118,9 +438,46 @@ impl<T> Iterator for IntoIter<T> {
     }
 }
 
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next.map(|node| {
+            self.next = node.next.as_deref();
+            &node.data
+        })
+    }
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next.take().map(|node| {
+            self.next = node.next.as_deref_mut();
+            &mut node.data
+        })
+    }
+}
+
+impl<'a, T> IntoIterator for &'a LinkedList<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut LinkedList<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::safe_rust::test_utils::scrambled;
 
     #[test]
     fn add_first() {
@@ -292,4 +649,277 @@ mod tests {
         list.remove_first();
         list.remove_first();
     }
+
+    fn list_of(values: &[i32]) -> LinkedList<i32> {
+        let mut list = LinkedList::new();
+        for value in values {
+            list.add_last(*value);
+        }
+        list
+    }
+
+    #[test]
+    fn remove_last_returns_value() {
+        let mut list = list_of(&[1, 2, 3]);
+        assert_eq!(list.remove_last(), Some(3));
+        assert_eq!(list.remove_last(), Some(2));
+        assert_eq!(list.remove_last(), Some(1));
+        assert_eq!(list.remove_last(), None);
+        assert_eq!(list.size(), 0);
+    }
+
+    #[test]
+    fn insert_at() {
+        let mut list = list_of(&[1, 3]);
+        list.insert_at(1, 2);
+        list.insert_at(0, 0);
+        list.insert_at(4, 4);
+        assert_eq!(list.size(), 5);
+        assert_eq!(list.into_iter().collect::<Vec<_>>(), vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn insert_at_out_of_bounds() {
+        let mut list = list_of(&[1]);
+        list.insert_at(2, 2);
+    }
+
+    #[test]
+    fn remove_at_and_get() {
+        let mut list = list_of(&[1, 2, 3, 4]);
+        assert_eq!(list.get(2), Some(&3));
+        assert_eq!(list.get(4), None);
+        assert_eq!(list.remove_at(4), None);
+        assert_eq!(list.remove_at(1), Some(2));
+        assert_eq!(list.remove_at(2), Some(4));
+        assert_eq!(list.remove_at(0), Some(1));
+        assert_eq!(list.get(0), Some(&3));
+        assert_eq!(list.size(), 1);
+    }
+
+    #[test]
+    fn retain_and_remove_all() {
+        let mut list = list_of(&[1, 2, 2, 3, 4, 2, 5, 6]);
+        assert_eq!(list.remove_all(&2), 3);
+        assert_eq!(list.remove_all(&7), 0);
+        list.retain(|value| value % 2 == 1);
+        assert_eq!(list.size(), 3);
+        list.add_last(7);
+        assert_eq!(list.into_iter().collect::<Vec<_>>(), vec![1, 3, 5, 7]);
+    }
+
+    #[test]
+    fn iter() {
+        let list = list_of(&[1, 2, 3]);
+        assert_eq!(list.iter().collect::<Vec<_>>(), vec![&1, &2, &3]);
+        let mut sum = 0;
+        for value in &list {
+            sum += value;
+        }
+        assert_eq!(sum, 6);
+        // Still usable after borrowing iteration.
+        assert_eq!(list.size(), 3);
+        assert_eq!(LinkedList::<i32>::new().iter().next(), None);
+    }
+
+    #[test]
+    fn iter_mut() {
+        let mut list = list_of(&[1, 2, 3]);
+        for value in list.iter_mut() {
+            *value *= 2;
+        }
+        for value in &mut list {
+            *value += 1;
+        }
+        assert_eq!(list.into_iter().collect::<Vec<_>>(), vec![3, 5, 7]);
+    }
+
+    #[test]
+    fn peek_last() {
+        let mut list = LinkedList::new();
+        assert_eq!(list.peek_last(), None);
+        list.add_first(2);
+        list.add_first(1);
+        assert_eq!(list.peek_last(), Some(&2));
+        *list.peek_last_mut().unwrap() = 5;
+        assert_eq!(list.peek_last(), Some(&5));
+        assert_eq!(list.peek(), Some(&1));
+    }
+
+    #[test]
+    fn contains_and_find() {
+        let list = list_of(&[1, 4, 6, 9]);
+        assert!(list.contains(&6));
+        assert!(!list.contains(&5));
+        assert_eq!(list.find(|value| value % 2 == 0), Some(&4));
+        assert_eq!(list.find(|value| *value > 10), None);
+    }
+
+    #[test]
+    fn sort() {
+        let mut list = list_of(&scrambled(1000));
+        list.sort();
+        assert_eq!(list.size(), 1000);
+        assert!(list.iter().copied().eq(0..1000));
+
+        let mut list = list_of(&[3, 1, 2]);
+        list.sort_by(|a, b| b.cmp(a));
+        assert_eq!(list.into_iter().collect::<Vec<_>>(), vec![3, 2, 1]);
+
+        let mut empty: LinkedList<i32> = LinkedList::new();
+        empty.sort();
+        assert_eq!(empty.size(), 0);
+    }
+
+    #[test]
+    fn sort_is_stable() {
+        let mut list = LinkedList::new();
+        for (index, value) in scrambled(100).into_iter().enumerate() {
+            list.add_last((value % 7, index));
+        }
+        list.sort_by_key(|&(key, _)| key);
+        let sorted: Vec<_> = list.into_iter().collect();
+        for pair in sorted.windows(2) {
+            assert!(pair[0].0 < pair[1].0 || (pair[0].0 == pair[1].0 && pair[0].1 < pair[1].1));
+        }
+    }
+
+    #[test]
+    fn merge_sorted() {
+        let mut list = list_of(&[1, 3, 5, 7]);
+        list.merge_sorted(list_of(&[0, 3, 4, 8, 9]));
+        assert_eq!(list.size(), 9);
+        assert_eq!(
+            list.into_iter().collect::<Vec<_>>(),
+            vec![0, 1, 3, 3, 4, 5, 7, 8, 9]
+        );
+
+        let mut list = LinkedList::new();
+        list.merge_sorted(list_of(&[1, 2]));
+        list.merge_sorted(LinkedList::new());
+        assert_eq!(list.into_iter().collect::<Vec<_>>(), vec![1, 2]);
+    }
+
+    #[test]
+    fn dedup() {
+        let mut list = list_of(&[1, 1, 2, 3, 3, 3, 1, 4, 4]);
+        list.dedup();
+        assert_eq!(list.size(), 5);
+        assert_eq!(list.into_iter().collect::<Vec<_>>(), vec![1, 2, 3, 1, 4]);
+
+        let mut list = list_of(&[10, 11, 20, 25, 31]);
+        list.dedup_by_key(|value| value / 10);
+        assert_eq!(list.into_iter().collect::<Vec<_>>(), vec![10, 20, 31]);
+    }
+
+    fn to_vec(list: LinkedList<i32>) -> Vec<i32> {
+        list.into_iter().collect()
+    }
+
+    #[test]
+    fn split_off_and_append() {
+        let mut list = list_of(&[1, 2, 3, 4, 5]);
+        let mut back = list.split_off(2);
+        assert_eq!((list.size(), back.size()), (2, 3));
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(back.iter().copied().collect::<Vec<_>>(), vec![3, 4, 5]);
+
+        back.append(&mut list);
+        assert_eq!(list.size(), 0);
+        assert!(list.head.is_none());
+        assert_eq!(back.size(), 5);
+        assert_eq!(to_vec(back), vec![3, 4, 5, 1, 2]);
+
+        let mut list = list_of(&[1, 2]);
+        assert_eq!(list.split_off(2).size(), 0);
+        assert_eq!(to_vec(list.split_off(0)), vec![1, 2]);
+        list.append(&mut list_of(&[7]));
+        assert_eq!(to_vec(list), vec![7]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn split_off_out_of_bounds() {
+        list_of(&[1]).split_off(2);
+    }
+
+    #[test]
+    fn rotate() {
+        let mut list = list_of(&[1, 2, 3, 4, 5]);
+        list.rotate_left(2);
+        assert_eq!(
+            list.iter().copied().collect::<Vec<_>>(),
+            vec![3, 4, 5, 1, 2]
+        );
+        list.rotate_right(2);
+        assert_eq!(
+            list.iter().copied().collect::<Vec<_>>(),
+            vec![1, 2, 3, 4, 5]
+        );
+        list.rotate_left(7);
+        assert_eq!(
+            list.iter().copied().collect::<Vec<_>>(),
+            vec![3, 4, 5, 1, 2]
+        );
+        list.rotate_right(5);
+        list.rotate_left(0);
+        assert_eq!(list.size(), 5);
+        assert_eq!(to_vec(list), vec![3, 4, 5, 1, 2]);
+
+        let mut empty: LinkedList<i32> = LinkedList::new();
+        empty.rotate_left(3);
+        empty.rotate_right(3);
+        assert_eq!(empty.size(), 0);
+    }
+
+    #[test]
+    fn middle() {
+        assert_eq!(LinkedList::<i32>::new().middle(), None);
+        assert_eq!(list_of(&[1]).middle(), Some(&1));
+        assert_eq!(list_of(&[1, 2, 3]).middle(), Some(&2));
+        assert_eq!(list_of(&[1, 2, 3, 4]).middle(), Some(&3));
+    }
+
+    #[test]
+    fn reverse_in_groups() {
+        let mut list = list_of(&[1, 2, 3, 4, 5, 6, 7, 8]);
+        list.reverse_in_groups(3);
+        assert_eq!(list.size(), 8);
+        assert_eq!(
+            list.iter().copied().collect::<Vec<_>>(),
+            vec![3, 2, 1, 6, 5, 4, 8, 7]
+        );
+        list.reverse_in_groups(1);
+        list.reverse_in_groups(8);
+        assert_eq!(to_vec(list), vec![7, 8, 4, 5, 6, 1, 2, 3]);
+    }
+
+    #[test]
+    fn is_palindrome() {
+        assert!(LinkedList::<i32>::new().is_palindrome());
+        assert!(list_of(&[1]).is_palindrome());
+        assert!(list_of(&[1, 2, 1]).is_palindrome());
+        assert!(list_of(&[1, 2, 2, 1]).is_palindrome());
+        assert!(!list_of(&[1, 2, 3, 1]).is_palindrome());
+        assert!(!list_of(&[1, 2]).is_palindrome());
+    }
+
+    #[test]
+    fn is_palindrome_in_place() {
+        assert!(LinkedList::<i32>::new().is_palindrome_in_place());
+        for values in [
+            vec![1],
+            vec![1, 2, 1],
+            vec![1, 2, 2, 1],
+            vec![1, 2, 3, 1],
+            vec![1, 2],
+            vec![3, 1, 4, 1, 3],
+        ] {
+            let mut list = list_of(&values);
+            assert_eq!(list.is_palindrome_in_place(), list.is_palindrome());
+            assert_eq!(list.size(), values.len());
+            assert_eq!(to_vec(list), values);
+        }
+    }
 }
@@ -1,130 +1,217 @@
 /**
  * Singly linked List with a tail pointer
  * Makes add_last o(1)
+ *
+ * A tail pointer means two owners for the last node, which with Box nodes would take
+ * Rc<RefCell<Node>> and hand out Ref guards from peek. Instead the nodes live in an arena
+ * (a Vec) and link to each other by index, so the list owns every node once and the tail is
+ * just another index. Removed slots go to a free list and get reused by the next add.
+ *
+ * The arena doesn't shrink by itself: after removing most of a long list, the slots of the
+ * removed nodes stay allocated for the next adds. It starts over whenever the list becomes
+ * empty, and `shrink_to_fit` gives the memory back otherwise.
  */
-use std::cell::{Ref, RefCell, RefMut};
-use std::rc::Rc;
-
-type Link<T> = Option<Rc<RefCell<Node<T>>>>;
+type Link = Option<usize>;
 struct Node<T> {
     data: T,
-    next: Link<T>,
+    next: Link,
 }
 
 pub struct LinkedListSinglyTail<T> {
-    head: Link<T>,
-    tail: Link<T>,
+    // Arena of nodes, `None` for free slots.
+    nodes: Vec<Option<Node<T>>>,
+    free: Vec<usize>,
+    head: Link,
+    tail: Link,
     counter: usize,
 }
 
 impl<T> LinkedListSinglyTail<T> {
     pub fn new() -> LinkedListSinglyTail<T> {
-        let head = None;
-        let tail = None;
         LinkedListSinglyTail {
-            head,
-            tail,
+            nodes: Vec::new(),
+            free: Vec::new(),
+            head: None,
+            tail: None,
             counter: 0,
         }
     }
-    pub fn add_first(&mut self, data: T) {
-        let new_node = Rc::new(RefCell::new(Node {
-            data,
-            next: self.head.as_ref().map(|node| Rc::clone(node)),
-        }));
-
-        if self.head.is_none() {
-            self.tail = Some(Rc::clone(&new_node));
-        }
 
-        self.head = Some(new_node);
-        self.counter += 1;
+    fn node(&self, index: usize) -> &Node<T> {
+        self.nodes[index]
+            .as_ref()
+            .expect("LinkedListSinglyTail: a link points to a free slot")
     }
 
-    pub fn add_last(&mut self, data: T) {
-        let new_node = Rc::new(RefCell::new(Node { data, next: None }));
+    fn node_mut(&mut self, index: usize) -> &mut Node<T> {
+        self.nodes[index]
+            .as_mut()
+            .expect("LinkedListSinglyTail: a link points to a free slot")
+    }
 
-        match &self.tail {
-            Some(tail) => {
-                tail.borrow_mut().next = Some(Rc::clone(&new_node));
-                self.tail = Some(Rc::clone(&new_node));
+    fn allocate(&mut self, node: Node<T>) -> usize {
+        self.counter += 1;
+        match self.free.pop() {
+            Some(index) => {
+                self.nodes[index] = Some(node);
+                index
             }
             None => {
-                self.head = Some(Rc::clone(&new_node));
-                self.tail = Some(new_node);
+                self.nodes.push(Some(node));
+                self.nodes.len() - 1
             }
         }
-
-        self.counter += 1;
     }
 
-    pub fn remove_first(&mut self) -> Option<T> {
-        self.head.take().map(|node| {
-            self.head = node.borrow_mut().next.take();
-            self.counter -= 1;
-            if self.head.is_none() {
-                self.tail = None;
-            }
+    // Frees the slot of a node that is no longer linked and returns its data.
+    fn release(&mut self, index: usize) -> T {
+        let node = self.nodes[index]
+            .take()
+            .expect("LinkedListSinglyTail: released a free slot");
+        self.counter -= 1;
+        if self.counter == 0 {
+            // Nothing is linked anymore, start over instead of keeping a free list of every slot.
+            self.nodes.clear();
+            self.free.clear();
+        } else {
+            self.free.push(index);
+        }
+        node.data
+    }
 
-            // Consume Rc
-            // At this point, only the head or tail can have references to the first node.
-            // Since we've handled the head above and ensured there are no other references,
-            // it should be safe to unwrap the Rc. If not, something unexpected has occurred.
-            let data = match Rc::try_unwrap(node) {
-                Ok(refcell) => refcell.into_inner().data,
-                Err(_) => panic!("Remove first: at this point no other ref to node should exist."),
-            };
-            data
-        })
+    // Index of the node at `position`, which must be less than `counter`.
+    fn index_at(&self, position: usize) -> usize {
+        let mut current = self.head.unwrap();
+        for _ in 0..position {
+            current = self
+                .node(current)
+                .next
+                .expect("Error index_at: position is past the end of the list");
+        }
+        current
     }
 
-    pub fn remove_last(&mut self) {
+    pub fn add_first(&mut self, data: T) {
+        let next = self.head;
+        let index = self.allocate(Node { data, next });
         if self.head.is_none() {
-            return;
+            self.tail = Some(index);
         }
+        self.head = Some(index);
+    }
+
+    pub fn add_last(&mut self, data: T) {
+        let index = self.allocate(Node { data, next: None });
+        match self.tail {
+            Some(tail) => self.node_mut(tail).next = Some(index),
+            None => self.head = Some(index),
+        }
+        self.tail = Some(index);
+    }
 
-        if self.counter == 1 {
-            self.remove_first();
-            return;
+    pub fn remove_first(&mut self) -> Option<T> {
+        let head = self.head?;
+        self.head = self.node(head).next;
+        if self.head.is_none() {
+            self.tail = None;
         }
+        Some(self.release(head))
+    }
 
+    pub fn remove_last(&mut self) -> Option<T> {
+        if self.counter < 2 {
+            return self.remove_first();
+        }
         // Move to the second last node in the list
-        let mut current_link = Rc::clone(self.head.as_mut().unwrap());
-        for _ in 0..(self.counter - 2) {
-            let next_link: Option<Rc<RefCell<Node<T>>>>;
+        let previous = self.index_at(self.counter - 2);
+        let last = self.node_mut(previous).next.take().unwrap();
+        self.tail = Some(previous);
+        Some(self.release(last))
+    }
 
-            // Use a scoped block to explicitly end the borrow from `current_link`.
-            // This is necessary as NLL can't deduce that `node` isn't
-            // used after the 'map' operation, which leads to a borrow checker conflict when updating `current_link`.
+    /// Inserts `data` so that it ends up at `index`, shifting the elements after it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index > size`.
+    pub fn insert_at(&mut self, index: usize, data: T) {
+        assert!(
+            index <= self.counter,
+            "LinkedListSinglyTail::insert_at: index {} out of bounds for size {}",
+            index,
+            self.counter
+        );
+        if index == 0 {
+            return self.add_first(data);
+        }
+        if index == self.counter {
+            return self.add_last(data);
+        }
+        let previous = self.index_at(index - 1);
+        let next = self.node(previous).next;
+        let new_node = self.allocate(Node { data, next });
+        self.node_mut(previous).next = Some(new_node);
+    }
 
-            {
-                let node = current_link.borrow();
-                next_link = node.next.as_ref().map(Rc::clone);
-            }
+    /// Removes the element at `index`, None if `index >= size`.
+    pub fn remove_at(&mut self, index: usize) -> Option<T> {
+        if index >= self.counter {
+            return None;
+        }
+        if index == 0 {
+            return self.remove_first();
+        }
+        let previous = self.index_at(index - 1);
+        let removed = self.node(previous).next.unwrap();
+        self.node_mut(previous).next = self.node(removed).next;
+        if self.tail == Some(removed) {
+            self.tail = Some(previous);
+        }
+        Some(self.release(removed))
+    }
 
-            if let Some(link) = next_link {
-                current_link = link;
-            } else {
-                panic!("Error remove_last: list count is inconsistent with number of nodes. 'self.counter' is bigger than the actual number of nodes");
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.iter().nth(index)
+    }
+
+    /// Keeps only the elements for which `f` returns true, in order.
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut f: F) {
+        let mut previous = None;
+        let mut current = self.head;
+        while let Some(index) = current {
+            current = self.node(index).next;
+            if f(&self.node(index).data) {
+                previous = Some(index);
+                continue;
             }
+            match previous {
+                Some(previous) => self.node_mut(previous).next = current,
+                None => self.head = current,
+            }
+            self.release(index);
         }
+        self.tail = previous;
+    }
 
-        current_link.borrow_mut().next = None;
-        self.counter -= 1;
+    /// Removes every element equal to `data`, returning how many were removed.
+    pub fn remove_all(&mut self, data: &T) -> usize
+    where
+        T: PartialEq,
+    {
+        let before = self.counter;
+        self.retain(|element| element != data);
+        before - self.counter
     }
 
     pub fn reverse(&mut self) {
-        let mut prev_link = None;
-        let mut current_link = self.head.take();
-
-        while let Some(current_node) = current_link {
-            let next_link = current_node.borrow_mut().next.take();
-            current_node.borrow_mut().next = prev_link;
-            prev_link = Some(current_node);
-            current_link = next_link;
+        let mut previous = None;
+        let mut current = self.head;
+        while let Some(index) = current {
+            let node = self.node_mut(index);
+            current = std::mem::replace(&mut node.next, previous);
+            previous = Some(index);
         }
         std::mem::swap(&mut self.head, &mut self.tail);
-        self.head = prev_link;
     }
 
     pub fn size(&self) -> usize {
@@ -132,21 +219,95 @@ impl<T> LinkedListSinglyTail<T> {
     }
 
     pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.free.clear();
         self.head = None;
         self.tail = None;
         self.counter = 0;
     }
 
-    pub fn peek(&self) -> Option<Ref<T>> {
-        self.head
-            .as_ref()
-            .map(|node| Ref::map(node.borrow(), |node| &node.data))
+    pub fn peek(&self) -> Option<&T> {
+        self.head.map(|head| &self.node(head).data)
     }
 
-    pub fn peek_mut(&mut self) -> Option<RefMut<T>> {
-        self.head
-            .as_ref()
-            .map(|node| RefMut::map(node.borrow_mut(), |node| &mut node.data))
+    pub fn peek_mut(&mut self) -> Option<&mut T> {
+        let head = self.head?;
+        Some(&mut self.node_mut(head).data)
+    }
+
+    pub fn peek_last(&self) -> Option<&T> {
+        self.tail.map(|tail| &self.node(tail).data)
+    }
+
+    pub fn peek_last_mut(&mut self) -> Option<&mut T> {
+        let tail = self.tail?;
+        Some(&mut self.node_mut(tail).data)
+    }
+
+    pub fn contains(&self, data: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        self.iter().any(|element| element == data)
+    }
+
+    /// First element for which `predicate` returns true.
+    pub fn find<P: FnMut(&T) -> bool>(&self, mut predicate: P) -> Option<&T> {
+        self.iter().find(|element| predicate(element))
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            list: self,
+            next: self.head,
+        }
+    }
+
+    /// Moves the nodes into list order first, so handing out `&mut` to all of them is just
+    /// walking the arena as a slice. That's O(n) like the iteration and doesn't allocate.
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        self.compact();
+        IterMut(self.nodes.iter_mut())
+    }
+
+    /// Releases the slots of the removed elements, which the arena otherwise keeps for reuse.
+    /// O(n).
+    pub fn shrink_to_fit(&mut self) {
+        self.compact();
+        self.nodes.shrink_to_fit();
+        self.free.shrink_to_fit();
+    }
+
+    // Permutes the arena in place so that the node at position i of the list is in slot i,
+    // then drops the free slots at the end.
+    fn compact(&mut self) {
+        let mut next = self.head;
+        for position in 0..self.counter {
+            let mut index = next.unwrap();
+            // Slots before `position` are placed already. When a node was moved out of one of
+            // them, the placed node's `next` was left pointing to where it went.
+            while index < position {
+                index = self.node(index).next.unwrap();
+            }
+            next = self.node(index).next;
+            if index != position {
+                self.nodes.swap(position, index);
+                self.node_mut(position).next = Some(index);
+            }
+        }
+        self.nodes.truncate(self.counter);
+        self.free.clear();
+        for position in 0..self.counter {
+            self.node_mut(position).next = Some(position + 1).filter(|&next| next < self.counter);
+        }
+        self.head = Some(0).filter(|_| self.counter > 0);
+        self.tail = self.counter.checked_sub(1);
+    }
+}
+
+impl<T> Default for LinkedListSinglyTail<T> {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -164,16 +325,59 @@ impl<T> Iterator for IntoIter<T> {
     }
 }
 
+pub struct Iter<'a, T> {
+    list: &'a LinkedListSinglyTail<T>,
+    next: Link,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.list.node(self.next?);
+        self.next = node.next;
+        Some(&node.data)
+    }
+}
+
+// Walks an arena compacted into list order.
+pub struct IterMut<'a, T>(std::slice::IterMut<'a, Option<Node<T>>>);
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.0.next()?.as_mut();
+        let node = node.expect("LinkedListSinglyTail: a compacted arena has a free slot");
+        Some(&mut node.data)
+    }
+}
+
+impl<'a, T> IntoIterator for &'a LinkedListSinglyTail<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut LinkedListSinglyTail<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::safe_rust::test_utils::scrambled;
 
     #[test]
     fn add_first() {
         let mut x = LinkedListSinglyTail::new();
         x.add_first(10);
         x.add_first(5);
-        assert_eq!(x.head.unwrap().borrow().data, 5);
+        assert_eq!(x.node(x.head.unwrap()).data, 5);
     }
 
     #[test]
@@ -181,14 +385,14 @@ mod tests {
         let mut x = LinkedListSinglyTail::new();
         x.add_last(5);
         x.add_last(10);
-        assert_eq!(x.head.unwrap().borrow().data, 5);
+        assert_eq!(x.node(x.head.unwrap()).data, 5);
     }
 
     #[test]
     fn add_last_no_elements() {
         let mut x = LinkedListSinglyTail::new();
         x.add_last(5);
-        assert_eq!(x.head.unwrap().borrow().data, 5);
+        assert_eq!(x.node(x.head.unwrap()).data, 5);
     }
 
     #[test]
@@ -198,18 +402,7 @@ mod tests {
         x.add_first(5);
         x.add_last(15);
         assert_eq!(
-            x.head
-                .as_ref()
-                .unwrap()
-                .borrow()
-                .next
-                .as_ref()
-                .unwrap()
-                .borrow()
-                .next
-                .as_ref()
-                .unwrap()
-                .borrow()
+            x.node(x.node(x.node(x.head.unwrap()).next.unwrap()).next.unwrap())
                 .data,
             15
         );
@@ -221,8 +414,8 @@ mod tests {
         x.add_last(5);
         x.add_last(10);
         x.add_last(15);
-        assert_eq!(x.head.unwrap().borrow().data, 5);
-        assert_eq!(x.tail.unwrap().borrow().data, 15);
+        assert_eq!(x.node(x.head.unwrap()).data, 5);
+        assert_eq!(x.node(x.tail.unwrap()).data, 15);
     }
 
     #[test]
@@ -238,8 +431,8 @@ mod tests {
     fn add_last_single_element() {
         let mut x = LinkedListSinglyTail::new();
         x.add_last(5);
-        assert_eq!(x.head.unwrap().borrow().data, 5);
-        assert_eq!(x.tail.unwrap().borrow().data, 5);
+        assert_eq!(x.node(x.head.unwrap()).data, 5);
+        assert_eq!(x.node(x.tail.unwrap()).data, 5);
     }
 
     #[test]
@@ -281,7 +474,7 @@ mod tests {
         x.add_first(10);
         x.remove_first();
         assert_eq!(x.counter, 1);
-        assert_eq!(x.head.unwrap().borrow().data, 5);
+        assert_eq!(x.node(x.head.unwrap()).data, 5);
     }
 
     #[test]
@@ -343,4 +536,194 @@ mod tests {
         list.remove_first();
         list.remove_first();
     }
+
+    fn list_of(values: &[i32]) -> LinkedListSinglyTail<i32> {
+        let mut list = LinkedListSinglyTail::new();
+        for value in values {
+            list.add_last(*value);
+        }
+        list
+    }
+
+    #[test]
+    fn remove_last_updates_tail() {
+        let mut list = list_of(&[1, 2, 3]);
+        assert_eq!(list.remove_last(), Some(3));
+        list.add_last(4);
+        assert_eq!(list.remove_last(), Some(4));
+        assert_eq!(list.remove_last(), Some(2));
+        assert_eq!(list.remove_last(), Some(1));
+        assert_eq!(list.remove_last(), None);
+        assert!(list.tail.is_none());
+    }
+
+    #[test]
+    fn insert_at() {
+        let mut list = list_of(&[1, 3]);
+        list.insert_at(1, 2);
+        list.insert_at(0, 0);
+        list.insert_at(4, 4);
+        list.add_last(5);
+        assert_eq!(list.size(), 6);
+        assert_eq!(list.into_iter().collect::<Vec<_>>(), vec![0, 1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn insert_at_out_of_bounds() {
+        let mut list = list_of(&[1]);
+        list.insert_at(2, 2);
+    }
+
+    #[test]
+    fn remove_at_and_get() {
+        let mut list = list_of(&[1, 2, 3, 4]);
+        assert_eq!(list.get(2), Some(&3));
+        assert_eq!(list.get(4), None);
+        assert_eq!(list.remove_at(4), None);
+        assert_eq!(list.remove_at(1), Some(2));
+        assert_eq!(list.remove_at(2), Some(4));
+        list.add_last(5);
+        assert_eq!(list.remove_at(0), Some(1));
+        assert_eq!(list.into_iter().collect::<Vec<_>>(), vec![3, 5]);
+    }
+
+    #[test]
+    fn retain_and_remove_all() {
+        let mut list = list_of(&[2, 1, 2, 3, 4, 5, 6, 2]);
+        assert_eq!(list.remove_all(&2), 3);
+        assert_eq!(list.remove_all(&7), 0);
+        list.retain(|value| value % 2 == 1);
+        assert_eq!(list.size(), 3);
+        list.add_last(7);
+        assert_eq!(list.into_iter().collect::<Vec<_>>(), vec![1, 3, 5, 7]);
+    }
+
+    #[test]
+    fn retain_nothing() {
+        let mut list = list_of(&[1, 2]);
+        list.retain(|_| false);
+        assert!(list.head.is_none() && list.tail.is_none());
+        list.add_last(3);
+        assert_eq!(list.into_iter().collect::<Vec<_>>(), vec![3]);
+    }
+
+    #[test]
+    fn peek_last() {
+        let mut list = LinkedListSinglyTail::new();
+        assert!(list.peek_last().is_none());
+        list.add_first(2);
+        list.add_first(1);
+        assert_eq!(list.peek_last(), Some(&2));
+        *list.peek_last_mut().unwrap() = 5;
+        assert_eq!(list.peek_last(), Some(&5));
+        assert_eq!(list.peek(), Some(&1));
+    }
+
+    #[test]
+    fn iter_and_iter_mut() {
+        let mut list = list_of(&[1, 2, 3]);
+        for value in list.iter_mut() {
+            *value *= 2;
+        }
+        for value in &mut list {
+            *value += 1;
+        }
+        assert_eq!(list.iter().collect::<Vec<_>>(), vec![&3, &5, &7]);
+        assert_eq!((&list).into_iter().count(), 3);
+        assert_eq!(list.size(), 3);
+    }
+
+    #[test]
+    fn iter_follows_links_not_slots() {
+        let mut list = list_of(&[1, 2, 3]);
+        list.remove_first();
+        list.add_last(4);
+        list.add_first(0);
+        list.reverse();
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![4, 3, 2, 0]);
+        for value in list.iter_mut() {
+            *value += 10;
+        }
+        assert_eq!(list.into_iter().collect::<Vec<_>>(), vec![14, 13, 12, 10]);
+    }
+
+    #[test]
+    fn freed_slots_are_reused() {
+        let mut list = list_of(&[1, 2, 3]);
+        list.remove_at(1);
+        list.remove_first();
+        list.add_last(4);
+        list.add_first(5);
+        assert_eq!(list.nodes.len(), 3);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![5, 3, 4]);
+    }
+
+    #[test]
+    fn iter_mut_compacts_the_arena() {
+        let mut list = LinkedListSinglyTail::new();
+        let mut expected = Vec::new();
+        for (step, value) in scrambled(200).into_iter().enumerate() {
+            if step % 3 == 2 {
+                let index = value as usize % expected.len();
+                assert_eq!(list.remove_at(index), Some(expected.remove(index)));
+            } else {
+                let index = value as usize % (expected.len() + 1);
+                list.insert_at(index, value);
+                expected.insert(index, value);
+            }
+        }
+        list.reverse();
+        expected.reverse();
+        for value in list.iter_mut() {
+            *value += 1000;
+        }
+        expected.iter_mut().for_each(|value| *value += 1000);
+        assert_eq!(list.nodes.len(), list.size());
+        assert!(list.free.is_empty());
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), expected);
+        list.add_last(-1);
+        list.remove_first();
+        assert_eq!(list.peek_last(), Some(&-1));
+        assert_eq!(list.size(), expected.len());
+    }
+
+    #[test]
+    fn emptying_resets_the_arena() {
+        let mut list = list_of(&[1, 2, 3]);
+        list.remove_last();
+        list.remove_first();
+        assert_eq!(list.free.len(), 2);
+        list.remove_first();
+        assert!(list.nodes.is_empty());
+        assert!(list.free.is_empty());
+        list.add_last(4);
+        assert_eq!(list.nodes.len(), 1);
+        assert_eq!(list.peek(), Some(&4));
+    }
+
+    #[test]
+    fn shrink_to_fit() {
+        let mut list = list_of(&(0..100).collect::<Vec<_>>());
+        list.retain(|value| value % 10 == 0);
+        assert_eq!(list.nodes.len(), 100);
+        list.shrink_to_fit();
+        assert_eq!(list.nodes.len(), 10);
+        assert!(list.nodes.capacity() < 100);
+        assert!(list.free.is_empty());
+        list.add_last(100);
+        assert_eq!(
+            list.iter().copied().collect::<Vec<_>>(),
+            (0..=100).step_by(10).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn contains_and_find() {
+        let list = list_of(&[1, 4, 6, 9]);
+        assert!(list.contains(&6));
+        assert!(!list.contains(&5));
+        assert_eq!(list.find(|value| value % 2 == 0), Some(&4));
+        assert_eq!(list.find(|value| *value > 10), None);
+    }
 }
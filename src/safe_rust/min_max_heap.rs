@@ -0,0 +1,384 @@
+/*
+ * Min-Max Heap
+ * A double ended priority queue stored in a `Vec` with the same layout as the binary `Heap`:
+ * the children of index i are 2i + 1 and 2i + 2.
+ * Levels alternate between min levels (even depth, starting at the root) and max levels:
+ * - an item on a min level is smaller than or equal to every item in its subtree.
+ * - an item on a max level is greater than or equal to every item in its subtree.
+ * So the smallest item is the root and the largest item is one of the root's children.
+ * Items bubble up and down by comparing with their grandparents/grandchildren, which are on
+ * the same kind of level, giving O(log n) push, pop_min and pop_max.
+ */
+use crate::safe_rust::binary_heap::Heap;
+
+pub struct MinMaxHeap<T: Ord> {
+    items: Vec<T>,
+}
+
+// Same layout as the binary `Heap`, so its index helpers are reused. They ignore the item type.
+type BinaryLayout = Heap<()>;
+
+impl<T: Ord> MinMaxHeap<T> {
+    pub fn new() -> Self {
+        MinMaxHeap { items: Vec::new() }
+    }
+
+    pub fn from_vec(vec: Vec<T>) -> Self {
+        let mut heap = MinMaxHeap { items: vec };
+        for i in (0..heap.items.len()).rev() {
+            heap.trickle_down(i);
+        }
+        heap
+    }
+
+    pub fn push(&mut self, value: T) {
+        self.items.push(value);
+        let index = self.items.len() - 1;
+        let parent_index = match BinaryLayout::parent_index(index) {
+            Some(parent_index) => parent_index,
+            None => return,
+        };
+
+        // First decide which kind of level the new item belongs to, by comparing it with its
+        // parent (which is on the other kind of level), then bubble up along that kind of level.
+        if Self::is_min_level(index) {
+            if self.items[index] > self.items[parent_index] {
+                self.items.swap(index, parent_index);
+                self.bubble_up_max(parent_index);
+            } else {
+                self.bubble_up_min(index);
+            }
+        } else if self.items[index] < self.items[parent_index] {
+            self.items.swap(index, parent_index);
+            self.bubble_up_min(parent_index);
+        } else {
+            self.bubble_up_max(index);
+        }
+    }
+
+    pub fn peek_min(&self) -> Option<&T> {
+        self.items.first()
+    }
+
+    pub fn peek_max(&self) -> Option<&T> {
+        self.max_index().map(|index| &self.items[index])
+    }
+
+    pub fn pop_min(&mut self) -> Option<T> {
+        if self.items.is_empty() {
+            return None;
+        }
+        let min = self.items.swap_remove(0);
+        if !self.items.is_empty() {
+            self.trickle_down(0);
+        }
+        Some(min)
+    }
+
+    pub fn pop_max(&mut self) -> Option<T> {
+        let index = self.max_index()?;
+        let max = self.items.swap_remove(index);
+        if index < self.items.len() {
+            self.trickle_down(index);
+        }
+        Some(max)
+    }
+
+    /// Pushes `value` and then pops the smallest item, with a single trickle down.
+    /// Keeps the `n` largest items seen so far when the heap is capped at `n` items.
+    pub fn push_pop_min(&mut self, value: T) -> T {
+        match self.items.first() {
+            Some(min) if *min < value => {}
+            _ => return value,
+        }
+        let min = std::mem::replace(&mut self.items[0], value);
+        self.trickle_down_min(0);
+        min
+    }
+
+    /// Pushes `value` and then pops the largest item, with a single trickle down.
+    /// Keeps the `n` smallest items seen so far when the heap is capped at `n` items.
+    pub fn push_pop_max(&mut self, value: T) -> T {
+        let index = match self.max_index() {
+            Some(index) if self.items[index] > value => index,
+            _ => return value,
+        };
+        let max = std::mem::replace(&mut self.items[index], value);
+        // The max is a child of the root, so the new value could also be the new min.
+        if index != 0 && self.items[index] < self.items[0] {
+            self.items.swap(index, 0);
+        }
+        self.trickle_down_max(index);
+        max
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+    }
+
+    fn max_index(&self) -> Option<usize> {
+        match self.items.len() {
+            0 => None,
+            1 => Some(0),
+            2 => Some(1),
+            _ => Some(if self.items[2] > self.items[1] { 2 } else { 1 }),
+        }
+    }
+
+    fn bubble_up_min(&mut self, mut index: usize) {
+        while let Some(grandparent_index) = Self::grandparent_index(index) {
+            if self.items[index] >= self.items[grandparent_index] {
+                break;
+            }
+            self.items.swap(index, grandparent_index);
+            index = grandparent_index;
+        }
+    }
+
+    fn bubble_up_max(&mut self, mut index: usize) {
+        while let Some(grandparent_index) = Self::grandparent_index(index) {
+            if self.items[index] <= self.items[grandparent_index] {
+                break;
+            }
+            self.items.swap(index, grandparent_index);
+            index = grandparent_index;
+        }
+    }
+
+    fn trickle_down(&mut self, index: usize) {
+        if Self::is_min_level(index) {
+            self.trickle_down_min(index);
+        } else {
+            self.trickle_down_max(index);
+        }
+    }
+
+    fn trickle_down_min(&mut self, mut index: usize) {
+        while let Some(target_index) = self.extreme_descendant(index, |a, b| a < b) {
+            if self.items[target_index] >= self.items[index] {
+                break;
+            }
+            self.items.swap(target_index, index);
+            if target_index <= BinaryLayout::first_child_index(index) + 1 {
+                // A child has no descendants of its own, we are done.
+                break;
+            }
+            // The item moved down to a grandchild, it may be larger than the max level parent above it.
+            let parent_index = BinaryLayout::parent_index(target_index).unwrap();
+            if self.items[target_index] > self.items[parent_index] {
+                self.items.swap(target_index, parent_index);
+            }
+            index = target_index;
+        }
+    }
+
+    fn trickle_down_max(&mut self, mut index: usize) {
+        while let Some(target_index) = self.extreme_descendant(index, |a, b| a > b) {
+            if self.items[target_index] <= self.items[index] {
+                break;
+            }
+            self.items.swap(target_index, index);
+            if target_index <= BinaryLayout::first_child_index(index) + 1 {
+                break;
+            }
+            let parent_index = BinaryLayout::parent_index(target_index).unwrap();
+            if self.items[target_index] < self.items[parent_index] {
+                self.items.swap(target_index, parent_index);
+            }
+            index = target_index;
+        }
+    }
+
+    // Returns the index among the children and grandchildren of `index` whose item is the most
+    // extreme according to `is_better` (the smallest on min levels, the largest on max levels).
+    fn extreme_descendant(&self, index: usize, is_better: fn(&T, &T) -> bool) -> Option<usize> {
+        let len = self.items.len();
+        let left_child_index = BinaryLayout::first_child_index(index);
+        if left_child_index >= len {
+            return None;
+        }
+        let right_child_index = left_child_index + 1;
+        let first_grandchild_index = BinaryLayout::first_child_index(left_child_index);
+        let last_grandchild_index = BinaryLayout::first_child_index(right_child_index) + 1;
+
+        let mut target_index = left_child_index;
+        let candidates = (right_child_index..=right_child_index)
+            .chain(first_grandchild_index..=last_grandchild_index)
+            .take_while(|&candidate_index| candidate_index < len);
+        for candidate_index in candidates {
+            if is_better(&self.items[candidate_index], &self.items[target_index]) {
+                target_index = candidate_index;
+            }
+        }
+        Some(target_index)
+    }
+
+    fn is_min_level(index: usize) -> bool {
+        // The depth of index i is floor(log2(i + 1)).
+        (index + 1).ilog2().is_multiple_of(2)
+    }
+
+    fn grandparent_index(child_index: usize) -> Option<usize> {
+        BinaryLayout::parent_index(child_index).and_then(BinaryLayout::parent_index)
+    }
+}
+
+impl<T: Ord> Default for MinMaxHeap<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::safe_rust::test_utils::scrambled_with_duplicates;
+
+    // Checks the min-max property for every item against its children.
+    fn assert_valid<T: Ord + std::fmt::Debug>(heap: &MinMaxHeap<T>) {
+        for index in 1..heap.items.len() {
+            let mut ancestor = BinaryLayout::parent_index(index);
+            while let Some(ancestor_index) = ancestor {
+                if MinMaxHeap::<T>::is_min_level(ancestor_index) {
+                    assert!(heap.items[ancestor_index] <= heap.items[index]);
+                } else {
+                    assert!(heap.items[ancestor_index] >= heap.items[index]);
+                }
+                ancestor = BinaryLayout::parent_index(ancestor_index);
+            }
+        }
+    }
+
+    #[test]
+    fn empty_heap() {
+        let mut heap: MinMaxHeap<i32> = MinMaxHeap::new();
+        assert!(heap.is_empty());
+        assert_eq!(heap.peek_min(), None);
+        assert_eq!(heap.peek_max(), None);
+        assert_eq!(heap.pop_min(), None);
+        assert_eq!(heap.pop_max(), None);
+    }
+
+    #[test]
+    fn single_item() {
+        let mut heap = MinMaxHeap::new();
+        heap.push(5);
+        assert_eq!(heap.peek_min(), Some(&5));
+        assert_eq!(heap.peek_max(), Some(&5));
+        assert_eq!(heap.pop_max(), Some(5));
+        assert!(heap.is_empty());
+    }
+
+    #[test]
+    fn push_keeps_min_and_max() {
+        let mut heap = MinMaxHeap::new();
+        for value in [5, 1, 8, 3, 7, 2, 9, 4] {
+            heap.push(value);
+            assert_valid(&heap);
+        }
+        assert_eq!(heap.len(), 8);
+        assert_eq!(heap.peek_min(), Some(&1));
+        assert_eq!(heap.peek_max(), Some(&9));
+    }
+
+    #[test]
+    fn pop_min_in_order() {
+        let mut heap = MinMaxHeap::new();
+        for value in scrambled_with_duplicates(300) {
+            heap.push(value);
+        }
+        let mut expected = scrambled_with_duplicates(300);
+        expected.sort();
+        for value in expected {
+            assert_eq!(heap.pop_min(), Some(value));
+            assert_valid(&heap);
+        }
+        assert!(heap.is_empty());
+    }
+
+    #[test]
+    fn pop_max_in_order() {
+        let mut heap = MinMaxHeap::from_vec(scrambled_with_duplicates(300));
+        assert_valid(&heap);
+        let mut expected = scrambled_with_duplicates(300);
+        expected.sort_by(|a, b| b.cmp(a));
+        for value in expected {
+            assert_eq!(heap.pop_max(), Some(value));
+            assert_valid(&heap);
+        }
+        assert!(heap.is_empty());
+    }
+
+    #[test]
+    fn alternating_pops() {
+        let mut heap = MinMaxHeap::from_vec((0..100).collect());
+        for i in 0..50 {
+            assert_eq!(heap.pop_min(), Some(i));
+            assert_eq!(heap.pop_max(), Some(99 - i));
+            assert_valid(&heap);
+        }
+        assert!(heap.is_empty());
+    }
+
+    #[test]
+    fn push_pop_max_keeps_smallest() {
+        let mut heap = MinMaxHeap::new();
+        for value in scrambled_with_duplicates(300) {
+            if heap.len() < 10 {
+                heap.push(value);
+            } else {
+                heap.push_pop_max(value);
+            }
+            assert_valid(&heap);
+        }
+        let mut expected = scrambled_with_duplicates(300);
+        expected.sort();
+        for value in expected.into_iter().take(10) {
+            assert_eq!(heap.pop_min(), Some(value));
+        }
+    }
+
+    #[test]
+    fn push_pop_max_returns_larger_value() {
+        let mut heap = MinMaxHeap::from_vec(vec![1, 5, 3]);
+        assert_eq!(heap.push_pop_max(7), 7);
+        assert_eq!(heap.push_pop_max(0), 5);
+        assert_valid(&heap);
+        assert_eq!(heap.peek_min(), Some(&0));
+        assert_eq!(heap.peek_max(), Some(&3));
+    }
+
+    #[test]
+    fn push_pop_min_keeps_largest() {
+        let mut heap = MinMaxHeap::new();
+        for value in scrambled_with_duplicates(300) {
+            if heap.len() < 10 {
+                heap.push(value);
+            } else {
+                heap.push_pop_min(value);
+            }
+            assert_valid(&heap);
+        }
+        let mut expected = scrambled_with_duplicates(300);
+        expected.sort_by(|a, b| b.cmp(a));
+        for value in expected.into_iter().take(10) {
+            assert_eq!(heap.pop_max(), Some(value));
+        }
+    }
+
+    #[test]
+    fn push_pop_on_empty_heap() {
+        let mut heap = MinMaxHeap::new();
+        assert_eq!(heap.push_pop_max(3), 3);
+        assert_eq!(heap.push_pop_min(3), 3);
+        assert!(heap.is_empty());
+    }
+}
@@ -1,21 +1,62 @@
 pub mod binary_heap;
+pub mod binomial_heap;
+pub mod btree;
+pub mod disjoint_set;
+pub mod fenwick_tree;
 pub mod hash_map;
+pub mod heap_utils;
+pub mod interval_tree;
 pub mod linked_list_doubly;
 pub mod linked_list_singly;
 pub mod linked_list_singly_tail;
+pub mod min_max_heap;
+pub mod ordered_set;
+pub mod pairing_heap;
+pub mod persistent_list;
+pub mod priority_queue;
 pub mod queue_with_linked_list;
 pub mod queue_with_vec;
+pub mod radix_tree;
+pub(crate) mod random;
+pub mod segment_tree;
+pub mod skip_list;
 pub mod stack_with_linked_list;
 pub mod stack_with_vec;
+#[cfg(test)]
+pub(crate) mod test_utils;
+pub mod treap;
 pub mod tree_avl;
+pub mod tree_red_black;
+pub mod trie;
+pub mod unrolled_linked_list;
 
 pub use binary_heap::Heap;
+pub use binomial_heap::BinomialHeap;
+pub use btree::BTree;
+pub use disjoint_set::{DisjointSet, DisjointSetMap};
+pub use fenwick_tree::FenwickTree;
 pub use hash_map::HashMap;
-pub use linked_list_doubly::DoublyLinkedList;
+pub use heap_utils::{top_k, KMerge};
+pub use interval_tree::IntervalTree;
+pub use linked_list_doubly::{CursorMut, DoublyLinkedList};
 pub use linked_list_singly::LinkedList;
 pub use linked_list_singly_tail::LinkedListSinglyTail;
+pub use min_max_heap::MinMaxHeap;
+pub use ordered_set::OrderedSet;
+pub use pairing_heap::PairingHeap;
+pub use persistent_list::PersistentList;
+pub use priority_queue::PriorityQueue;
 pub use queue_with_linked_list::QueueLinkedList;
 pub use queue_with_vec::QueueVec;
+pub use radix_tree::RadixTree;
+pub use segment_tree::{
+    Assign, AssignOrAdd, Gcd, Max, Min, Monoid, RangeAdd, SegmentTree, Sum, Tag,
+};
+pub use skip_list::SkipList;
 pub use stack_with_linked_list::StackLinkedList;
 pub use stack_with_vec::StackVec;
+pub use treap::{ImplicitTreap, Treap};
 pub use tree_avl::TreeAVL;
+pub use tree_red_black::TreeRedBlack;
+pub use trie::Trie;
+pub use unrolled_linked_list::UnrolledLinkedList;
@@ -0,0 +1,265 @@
+/*
+ * Ordered Set trait
+ * Common interface for the balanced search trees, so call sites can swap one tree for another
+ * (e.g. `TreeAVL` for lookup heavy workloads, `TreeRedBlack` for insert/remove heavy ones)
+ * without being rewritten.
+ */
+use std::ops::{Bound, RangeBounds};
+
+pub trait OrderedSet<T: Ord> {
+    /// Iterator returned by `range`, yielding keys in ascending order.
+    type Range<'a>: Iterator<Item = &'a T>
+    where
+        Self: 'a,
+        T: 'a;
+
+    /// Returns `true` if the key was not in the set yet.
+    fn insert(&mut self, key: T) -> bool;
+
+    /// Returns `true` if the key was in the set.
+    fn remove(&mut self, key: &T) -> bool;
+
+    fn contains(&self, key: &T) -> bool;
+
+    /// Smallest key.
+    fn first(&self) -> Option<&T>;
+
+    /// Largest key.
+    fn last(&self) -> Option<&T>;
+
+    /// Iterates over the keys within `range`, in ascending order.
+    fn range<R: RangeBounds<T>>(&self, range: R) -> Self::Range<'_>;
+
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+// Whether `key` satisfies the lower bound of a range. Shared by the trees' range iterators.
+pub(crate) fn is_above_lower<T: Ord>(key: &T, bound: Bound<&T>) -> bool {
+    match bound {
+        Bound::Included(lower) => key >= lower,
+        Bound::Excluded(lower) => key > lower,
+        Bound::Unbounded => true,
+    }
+}
+
+// Whether `key` satisfies the upper bound of a range.
+pub(crate) fn is_below_upper<T: Ord>(key: &T, bound: Bound<&T>) -> bool {
+    match bound {
+        Bound::Included(upper) => key <= upper,
+        Bound::Excluded(upper) => key < upper,
+        Bound::Unbounded => true,
+    }
+}
+
+/// Read access to a binary search tree node: enough for the in-order `Iter` and `Range`
+/// below, which the trees share instead of each keeping its own copy.
+pub trait TreeNode {
+    type Key;
+    fn key(&self) -> &Self::Key;
+    fn left(&self) -> Option<&Self>;
+    fn right(&self) -> Option<&Self>;
+}
+
+// In-order iterator: the stack holds the nodes whose left subtree is being visited.
+pub struct Iter<'a, N> {
+    stack: Vec<&'a N>,
+}
+
+impl<'a, N: TreeNode> Iter<'a, N> {
+    pub(crate) fn new(root: Option<&'a N>) -> Self {
+        let mut iter = Iter { stack: Vec::new() };
+        iter.push_left_path(root);
+        iter
+    }
+
+    fn push_left_path(&mut self, mut current: Option<&'a N>) {
+        while let Some(node) = current {
+            self.stack.push(node);
+            current = node.left();
+        }
+    }
+}
+
+impl<'a, N: TreeNode> Iterator for Iter<'a, N> {
+    type Item = &'a N::Key;
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        self.push_left_path(node.right());
+        Some(node.key())
+    }
+}
+
+pub struct Range<'a, N: TreeNode> {
+    iter: Iter<'a, N>,
+    // The last key to yield, compared by address since the trees hold no duplicates.
+    last: Option<&'a N::Key>,
+}
+
+impl<'a, N: TreeNode> Range<'a, N>
+where
+    N::Key: Ord,
+{
+    pub(crate) fn new<R: RangeBounds<N::Key>>(root: Option<&'a N>, range: R) -> Self {
+        // Start from the path to the smallest key above the lower bound.
+        let mut iter = Iter { stack: Vec::new() };
+        let mut current = root;
+        while let Some(node) = current {
+            if is_above_lower(node.key(), range.start_bound()) {
+                iter.stack.push(node);
+                current = node.left();
+            } else {
+                current = node.right();
+            }
+        }
+
+        // Stop after the largest key below the upper bound.
+        let mut last = None;
+        let mut current = root;
+        while let Some(node) = current {
+            if is_below_upper(node.key(), range.end_bound()) {
+                last = Some(node.key());
+                current = node.right();
+            } else {
+                current = node.left();
+            }
+        }
+
+        let is_empty = match (iter.stack.last(), last) {
+            (Some(first), Some(last)) => first.key() > last,
+            _ => true,
+        };
+        if is_empty {
+            iter.stack.clear();
+        }
+        Range { iter, last }
+    }
+}
+
+impl<'a, N: TreeNode> Iterator for Range<'a, N> {
+    type Item = &'a N::Key;
+    fn next(&mut self) -> Option<Self::Item> {
+        let last = self.last?;
+        let key = self.iter.next()?;
+        if std::ptr::eq(key, last) {
+            self.last = None;
+        }
+        Some(key)
+    }
+}
+
+/// Conformance suite for `OrderedSet` implementations. Every implementation should
+/// run it from its own tests, e.g. `conformance::run(TreeAVL::new)`.
+#[cfg(test)]
+pub(crate) mod conformance {
+    use super::OrderedSet;
+    use crate::safe_rust::test_utils::scrambled;
+
+    pub(crate) fn run<S: OrderedSet<i32>>(new: fn() -> S) {
+        empty_set(new());
+        insert_and_contains(new());
+        insert_duplicate(new());
+        remove(new());
+        first_and_last(new());
+        range_bounds(new());
+        range_empty(new());
+        many_keys(new());
+    }
+
+    fn empty_set<S: OrderedSet<i32>>(mut set: S) {
+        assert!(set.is_empty());
+        assert_eq!(set.len(), 0);
+        assert!(!set.contains(&1));
+        assert!(!set.remove(&1));
+        assert_eq!(set.first(), None);
+        assert_eq!(set.last(), None);
+        assert_eq!(set.range(..).next(), None);
+    }
+
+    fn insert_and_contains<S: OrderedSet<i32>>(mut set: S) {
+        assert!(set.insert(5));
+        assert!(set.insert(3));
+        assert!(set.insert(8));
+        assert_eq!(set.len(), 3);
+        assert!(set.contains(&3));
+        assert!(set.contains(&5));
+        assert!(set.contains(&8));
+        assert!(!set.contains(&4));
+    }
+
+    fn insert_duplicate<S: OrderedSet<i32>>(mut set: S) {
+        assert!(set.insert(5));
+        assert!(!set.insert(5));
+        assert_eq!(set.len(), 1);
+    }
+
+    fn remove<S: OrderedSet<i32>>(mut set: S) {
+        for key in [5, 3, 8, 1, 4] {
+            set.insert(key);
+        }
+        assert!(set.remove(&3));
+        assert!(!set.remove(&3));
+        assert!(!set.remove(&7));
+        assert_eq!(set.len(), 4);
+        assert!(!set.contains(&3));
+        let keys: Vec<i32> = set.range(..).copied().collect();
+        assert_eq!(keys, vec![1, 4, 5, 8]);
+    }
+
+    fn first_and_last<S: OrderedSet<i32>>(mut set: S) {
+        for key in [5, 3, 8, 1, 9] {
+            set.insert(key);
+        }
+        assert_eq!(set.first(), Some(&1));
+        assert_eq!(set.last(), Some(&9));
+        set.remove(&1);
+        set.remove(&9);
+        assert_eq!(set.first(), Some(&3));
+        assert_eq!(set.last(), Some(&8));
+    }
+
+    fn range_bounds<S: OrderedSet<i32>>(mut set: S) {
+        for key in scrambled(20) {
+            set.insert(key * 2);
+        }
+        let collect = |iter: S::Range<'_>| iter.copied().collect::<Vec<i32>>();
+        assert_eq!(collect(set.range(4..10)), vec![4, 6, 8]);
+        assert_eq!(collect(set.range(5..=10)), vec![6, 8, 10]);
+        assert_eq!(collect(set.range(..3)), vec![0, 2]);
+        assert_eq!(collect(set.range(35..)), vec![36, 38]);
+        assert_eq!(collect(set.range(..)).len(), 20);
+        assert_eq!(
+            collect(set.range((std::ops::Bound::Excluded(4), std::ops::Bound::Excluded(10)))),
+            vec![6, 8]
+        );
+    }
+
+    fn range_empty<S: OrderedSet<i32>>(mut set: S) {
+        for key in [2, 4, 6] {
+            set.insert(key);
+        }
+        assert_eq!(set.range(3..4).next(), None);
+        assert_eq!(set.range(7..).next(), None);
+        assert_eq!(set.range(..2).next(), None);
+        assert_eq!(set.range(4..4).next(), None);
+    }
+
+    fn many_keys<S: OrderedSet<i32>>(mut set: S) {
+        for key in scrambled(500) {
+            assert!(set.insert(key));
+        }
+        assert_eq!(set.len(), 500);
+        for key in scrambled(500).into_iter().filter(|key| key % 3 == 0) {
+            assert!(set.remove(&key));
+        }
+        let expected: Vec<i32> = (0..500).filter(|key| key % 3 != 0).collect();
+        let keys: Vec<i32> = set.range(..).copied().collect();
+        assert_eq!(keys, expected);
+        assert_eq!(set.len(), expected.len());
+        assert_eq!(set.first(), Some(&1));
+        assert_eq!(set.last(), Some(&499));
+    }
+}
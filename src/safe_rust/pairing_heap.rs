@@ -0,0 +1,391 @@
+/*
+ * Pairing Heap
+ * A heap ordered tree where every node keeps its children as a linked list: `child` points
+ * to the leftmost child and `sibling` to the next child of the same parent.
+ * - push and meld link two roots together, O(1).
+ * - pop removes the root and merges its children back with the two pass pairing strategy,
+ *   amortized O(log n).
+ * - decrease_key cuts the node out of its parent's list and links it with the root.
+ *
+ * Nodes are shared through `Rc<RefCell<..>>` so a `Handle` (a `Weak` to the node) can reach
+ * an item inside the tree. A `Handle` stops working once its item has been popped.
+ */
+use std::cell::{Ref, RefCell};
+use std::rc::{Rc, Weak};
+
+use crate::safe_rust::priority_queue::PriorityQueue;
+
+type Link<T> = Rc<RefCell<Node<T>>>;
+struct Node<T> {
+    value: T,
+    child: Option<Link<T>>,
+    sibling: Option<Link<T>>,
+    // The parent if this node is the leftmost child, otherwise the sibling to its left.
+    previous: Weak<RefCell<Node<T>>>,
+}
+
+pub struct Handle<T>(Weak<RefCell<Node<T>>>);
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        Handle(Weak::clone(&self.0))
+    }
+}
+
+pub struct PairingHeap<T> {
+    root: Option<Link<T>>,
+    size: usize,
+    /// Returns `true` if the first argument should come before the second argument in the heap order.
+    comparator: fn(&T, &T) -> bool,
+}
+
+impl<T> PairingHeap<T> {
+    pub fn new(comparator: fn(&T, &T) -> bool) -> Self {
+        PairingHeap {
+            root: None,
+            size: 0,
+            comparator,
+        }
+    }
+
+    /// Adds `value` and returns a handle that can later be passed to `decrease_key`.
+    pub fn push(&mut self, value: T) -> Handle<T> {
+        let new_node = Rc::new(RefCell::new(Node {
+            value,
+            child: None,
+            sibling: None,
+            previous: Weak::new(),
+        }));
+        let handle = Handle(Rc::downgrade(&new_node));
+
+        self.root = Some(match self.root.take() {
+            Some(root) => self.link(root, new_node),
+            None => new_node,
+        });
+        self.size += 1;
+        handle
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        self.root.take().map(|root| {
+            let children = root.borrow_mut().child.take();
+            self.root = self.merge_pairs(children);
+            self.size -= 1;
+
+            // Children only keep a `Weak` to their parent and handles are `Weak` as well,
+            // so the root we just detached must be the last strong reference.
+            match Rc::try_unwrap(root) {
+                Ok(refcell) => refcell.into_inner().value,
+                Err(_) => panic!("Pop: at this point no other ref to the root should exist."),
+            }
+        })
+    }
+
+    pub fn peek(&self) -> Option<Ref<'_, T>> {
+        self.root
+            .as_ref()
+            .map(|node| Ref::map(node.borrow(), |node| &node.value))
+    }
+
+    /// Moves every item of `other` into `self` in O(1). Handles from `other` stay valid for `self`.
+    pub fn meld(&mut self, mut other: PairingHeap<T>) {
+        let other_root = match other.root.take() {
+            Some(root) => root,
+            None => return,
+        };
+        self.size += std::mem::take(&mut other.size);
+        self.root = Some(match self.root.take() {
+            Some(root) => self.link(root, other_root),
+            None => other_root,
+        });
+    }
+
+    /// Replaces the item behind `handle` with a value that is at least as preferable,
+    /// moving it towards the root.
+    ///
+    /// Returns `Err(value)` if the item was already popped, or if `value` is less preferable
+    /// than the current item. The handle must come from this heap or from one melded into it.
+    pub fn decrease_key(&mut self, handle: &Handle<T>, value: T) -> Result<(), T> {
+        let node = match handle.0.upgrade() {
+            Some(node) => node,
+            None => return Err(value),
+        };
+        if (self.comparator)(&node.borrow().value, &value) {
+            return Err(value);
+        }
+        node.borrow_mut().value = value;
+
+        let is_root = self
+            .root
+            .as_ref()
+            .is_some_and(|root| Rc::ptr_eq(root, &node));
+        if is_root {
+            return Ok(());
+        }
+
+        // Cut the node (with its subtree) out of its parent's children.
+        let previous = node
+            .borrow()
+            .previous
+            .upgrade()
+            .expect("decrease_key: handle does not belong to this heap");
+        let sibling = node.borrow_mut().sibling.take();
+        if let Some(sibling) = &sibling {
+            sibling.borrow_mut().previous = Rc::downgrade(&previous);
+        }
+        {
+            let mut previous_node = previous.borrow_mut();
+            let is_leftmost_child = previous_node
+                .child
+                .as_ref()
+                .is_some_and(|child| Rc::ptr_eq(child, &node));
+            if is_leftmost_child {
+                previous_node.child = sibling;
+            } else {
+                previous_node.sibling = sibling;
+            }
+        }
+        node.borrow_mut().previous = Weak::new();
+
+        let root = self.root.take().expect("decrease_key: heap is empty");
+        self.root = Some(self.link(root, node));
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    pub fn clear(&mut self) {
+        // Drop node by node, the default recursive drop overflows the stack on long sibling lists.
+        let mut stack: Vec<Link<T>> = self.root.take().into_iter().collect();
+        while let Some(node) = stack.pop() {
+            let mut node = node.borrow_mut();
+            stack.extend(node.child.take());
+            stack.extend(node.sibling.take());
+        }
+        self.size = 0;
+    }
+
+    // Links two roots: the less preferable one becomes the leftmost child of the other.
+    fn link(&self, first: Link<T>, second: Link<T>) -> Link<T> {
+        let (parent, child) = if (self.comparator)(&second.borrow().value, &first.borrow().value) {
+            (second, first)
+        } else {
+            (first, second)
+        };
+        {
+            let mut parent_node = parent.borrow_mut();
+            let mut child_node = child.borrow_mut();
+            child_node.sibling = parent_node.child.take();
+            if let Some(sibling) = &child_node.sibling {
+                sibling.borrow_mut().previous = Rc::downgrade(&child);
+            }
+            child_node.previous = Rc::downgrade(&parent);
+        }
+        parent.borrow_mut().child = Some(child);
+        parent
+    }
+
+    // Two pass pairing: link the children in pairs from left to right,
+    // then link the resulting trees from right to left.
+    fn merge_pairs(&self, first_child: Option<Link<T>>) -> Option<Link<T>> {
+        let mut trees = Vec::new();
+        let mut current = first_child;
+        while let Some(node) = current {
+            current = node.borrow_mut().sibling.take();
+            node.borrow_mut().previous = Weak::new();
+            trees.push(node);
+        }
+
+        let mut paired = Vec::with_capacity(trees.len() / 2 + 1);
+        let mut trees = trees.into_iter();
+        while let Some(first) = trees.next() {
+            match trees.next() {
+                Some(second) => paired.push(self.link(first, second)),
+                None => paired.push(first),
+            }
+        }
+
+        let mut root = paired.pop()?;
+        while let Some(tree) = paired.pop() {
+            root = self.link(tree, root);
+        }
+        Some(root)
+    }
+}
+
+impl<T> Drop for PairingHeap<T> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<T> PriorityQueue<T> for PairingHeap<T> {
+    type Peek<'a>
+        = Ref<'a, T>
+    where
+        Self: 'a;
+
+    fn push(&mut self, value: T) {
+        PairingHeap::push(self, value);
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        PairingHeap::pop(self)
+    }
+
+    fn peek(&self) -> Option<Self::Peek<'_>> {
+        PairingHeap::peek(self)
+    }
+
+    fn len(&self) -> usize {
+        PairingHeap::len(self)
+    }
+
+    fn meld(&mut self, other: Self) {
+        PairingHeap::meld(self, other);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn max_heap_comparator(a: &i32, b: &i32) -> bool {
+        a > b
+    }
+
+    fn min_heap_comparator(a: &i32, b: &i32) -> bool {
+        a < b
+    }
+
+    #[test]
+    fn push_and_pop_in_order() {
+        let mut heap = PairingHeap::new(max_heap_comparator);
+        for value in [5, 1, 8, 3, 7, 2] {
+            heap.push(value);
+        }
+        assert_eq!(heap.len(), 6);
+        assert_eq!(*heap.peek().unwrap(), 8);
+        assert_eq!(heap.pop(), Some(8));
+        assert_eq!(heap.pop(), Some(7));
+        assert_eq!(heap.pop(), Some(5));
+        assert_eq!(heap.pop(), Some(3));
+        assert_eq!(heap.pop(), Some(2));
+        assert_eq!(heap.pop(), Some(1));
+        assert_eq!(heap.pop(), None);
+        assert!(heap.is_empty());
+    }
+
+    #[test]
+    fn duplicates() {
+        let mut heap = PairingHeap::new(min_heap_comparator);
+        for value in [2, 1, 2, 1] {
+            heap.push(value);
+        }
+        assert_eq!(heap.pop(), Some(1));
+        assert_eq!(heap.pop(), Some(1));
+        assert_eq!(heap.pop(), Some(2));
+        assert_eq!(heap.pop(), Some(2));
+    }
+
+    #[test]
+    fn meld() {
+        let mut first = PairingHeap::new(min_heap_comparator);
+        let mut second = PairingHeap::new(min_heap_comparator);
+        first.push(4);
+        first.push(9);
+        let handle = second.push(6);
+        second.push(1);
+
+        first.meld(second);
+        assert_eq!(first.len(), 4);
+        assert_eq!(first.decrease_key(&handle, 0), Ok(()));
+        assert_eq!(first.pop(), Some(0));
+        assert_eq!(first.pop(), Some(1));
+        assert_eq!(first.pop(), Some(4));
+        assert_eq!(first.pop(), Some(9));
+    }
+
+    #[test]
+    fn meld_into_empty() {
+        let mut first = PairingHeap::new(min_heap_comparator);
+        let mut second = PairingHeap::new(min_heap_comparator);
+        second.push(3);
+        first.meld(second);
+        assert_eq!(first.len(), 1);
+        assert_eq!(first.pop(), Some(3));
+    }
+
+    #[test]
+    fn decrease_key_moves_item_to_root() {
+        let mut heap = PairingHeap::new(min_heap_comparator);
+        let handles: Vec<Handle<i32>> = (10..20).map(|value| heap.push(value)).collect();
+        heap.pop();
+        heap.pop();
+
+        assert_eq!(heap.decrease_key(&handles[7], 1), Ok(()));
+        assert_eq!(*heap.peek().unwrap(), 1);
+        assert_eq!(heap.decrease_key(&handles[5], 2), Ok(()));
+        assert_eq!(heap.pop(), Some(1));
+        assert_eq!(heap.pop(), Some(2));
+        assert_eq!(heap.pop(), Some(12));
+        assert_eq!(heap.len(), 5);
+    }
+
+    #[test]
+    fn decrease_key_root() {
+        let mut heap = PairingHeap::new(min_heap_comparator);
+        let handle = heap.push(5);
+        heap.push(7);
+        assert_eq!(heap.decrease_key(&handle, 3), Ok(()));
+        assert_eq!(heap.pop(), Some(3));
+        assert_eq!(heap.pop(), Some(7));
+    }
+
+    #[test]
+    fn decrease_key_rejects_worse_value() {
+        let mut heap = PairingHeap::new(min_heap_comparator);
+        let handle = heap.push(5);
+        assert_eq!(heap.decrease_key(&handle, 6), Err(6));
+        assert_eq!(heap.pop(), Some(5));
+    }
+
+    #[test]
+    fn decrease_key_after_pop() {
+        let mut heap = PairingHeap::new(min_heap_comparator);
+        let handle = heap.push(5);
+        heap.pop();
+        assert_eq!(heap.decrease_key(&handle, 1), Err(1));
+    }
+
+    #[test]
+    fn many_items_in_order() {
+        let mut heap = PairingHeap::new(min_heap_comparator);
+        let handles: Vec<Handle<i32>> = (0..1000)
+            .map(|i| heap.push((i * 7919) % 1000 + 1000))
+            .collect();
+        for (i, handle) in handles.iter().enumerate().step_by(3) {
+            heap.decrease_key(handle, (i as i32 * 31) % 1000).unwrap();
+        }
+        let mut previous = i32::MIN;
+        while let Some(value) = heap.pop() {
+            assert!(previous <= value);
+            previous = value;
+        }
+    }
+
+    #[test]
+    fn drop_long_sibling_list() {
+        let mut heap = PairingHeap::new(min_heap_comparator);
+        for value in 0..200_000 {
+            heap.push(value);
+        }
+        drop(heap);
+    }
+}
@@ -0,0 +1,226 @@
+/*
+Persistent List
+ Immutable singly linked list where every operation returns a new version and leaves the old
+ one untouched. Nodes are shared through `Rc`, so `prepend` and `tail` are O(1): the new
+ version points at the nodes of the old one instead of copying them.
+
+   a = [2, 3]        a.prepend(1)       a.tail()
+   2 -> 3            1 -> (2 -> 3)      (3)
+                          ^ shared       ^ shared
+
+ Versions are cheap to keep around, which fits undo/redo stacks and snapshots. Cloning a list
+ only bumps a reference count. Since nodes never change after creation there's no RefCell.
+*/
+
+use std::rc::Rc;
+
+type Link<T> = Option<Rc<Node<T>>>;
+struct Node<T> {
+    data: T,
+    next: Link<T>,
+}
+
+pub struct PersistentList<T> {
+    head: Link<T>,
+    counter: usize,
+}
+
+impl<T> PersistentList<T> {
+    pub fn new() -> Self {
+        PersistentList {
+            head: None,
+            counter: 0,
+        }
+    }
+
+    /// New version with `data` in front, sharing every node of this one.
+    pub fn prepend(&self, data: T) -> Self {
+        PersistentList {
+            head: Some(Rc::new(Node {
+                data,
+                next: self.head.clone(),
+            })),
+            counter: self.counter + 1,
+        }
+    }
+
+    pub fn head(&self) -> Option<&T> {
+        self.head.as_ref().map(|node| &node.data)
+    }
+
+    /// New version without the first element, sharing the rest. Empty if this one is empty.
+    pub fn tail(&self) -> Self {
+        match &self.head {
+            Some(node) => PersistentList {
+                head: node.next.clone(),
+                counter: self.counter - 1,
+            },
+            None => Self::new(),
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.counter
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counter == 0
+    }
+
+    /// Whether both versions are the very same nodes, not just equal elements.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        match (&self.head, &other.head) {
+            (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            next: self.head.as_deref(),
+        }
+    }
+}
+
+impl<T> Clone for PersistentList<T> {
+    fn clone(&self) -> Self {
+        PersistentList {
+            head: self.head.clone(),
+            counter: self.counter,
+        }
+    }
+}
+
+impl<T> Default for PersistentList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// The default drop recurses once per node. Drop iteratively instead, stopping at the first
+// node that another version still holds: everything after it is still in use.
+impl<T> Drop for PersistentList<T> {
+    fn drop(&mut self) {
+        let mut current = self.head.take();
+        while let Some(node) = current {
+            current = match Rc::try_unwrap(node) {
+                Ok(mut node) => node.next.take(),
+                Err(_) => break,
+            };
+        }
+    }
+}
+
+/// Builds the list in iteration order, the first item ends up at the head.
+impl<T> FromIterator<T> for PersistentList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let items: Vec<T> = iter.into_iter().collect();
+        items
+            .into_iter()
+            .rev()
+            .fold(Self::new(), |list, item| list.prepend(item))
+    }
+}
+
+pub struct Iter<'a, T> {
+    next: Option<&'a Node<T>>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next.map(|node| {
+            self.next = node.next.as_deref();
+            &node.data
+        })
+    }
+}
+
+impl<'a, T> IntoIterator for &'a PersistentList<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_vec(list: &PersistentList<i32>) -> Vec<i32> {
+        list.iter().copied().collect()
+    }
+
+    #[test]
+    fn prepend_head_tail() {
+        let empty = PersistentList::new();
+        assert_eq!(empty.head(), None);
+        assert!(empty.tail().is_empty());
+
+        let list = empty.prepend(3).prepend(2).prepend(1);
+        assert_eq!(list.head(), Some(&1));
+        assert_eq!(list.size(), 3);
+        assert_eq!(to_vec(&list), vec![1, 2, 3]);
+        assert_eq!(to_vec(&list.tail()), vec![2, 3]);
+        assert_eq!(list.tail().tail().tail().size(), 0);
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn versions_are_independent() {
+        let base: PersistentList<i32> = [2, 3].into_iter().collect();
+        let with_one = base.prepend(1);
+        let with_nine = base.prepend(9);
+        let dropped = with_one.tail();
+        assert_eq!(to_vec(&base), vec![2, 3]);
+        assert_eq!(to_vec(&with_one), vec![1, 2, 3]);
+        assert_eq!(to_vec(&with_nine), vec![9, 2, 3]);
+        assert_eq!(to_vec(&dropped), vec![2, 3]);
+    }
+
+    #[test]
+    fn nodes_are_shared() {
+        let base: PersistentList<i32> = (0..3).collect();
+        let longer = base.prepend(-1);
+        assert!(longer.tail().ptr_eq(&base));
+        assert!(base.clone().ptr_eq(&base));
+        assert!(!base.ptr_eq(&(0..3).collect()));
+        // `base`, `longer` and the clone inside `tail` all hold the first node of `base`.
+        let tail = longer.tail();
+        assert_eq!(Rc::strong_count(base.head.as_ref().unwrap()), 3);
+        drop(longer);
+        drop(tail);
+        assert_eq!(Rc::strong_count(base.head.as_ref().unwrap()), 1);
+    }
+
+    #[test]
+    fn drop_keeps_shared_nodes() {
+        let base: PersistentList<i32> = (0..5).collect();
+        let longer = base.prepend(-2).prepend(-3);
+        drop(base);
+        assert_eq!(to_vec(&longer), vec![-3, -2, 0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn drop_long_list() {
+        let list: PersistentList<i32> = (0..200_000).collect();
+        let shared = list.tail();
+        drop(list);
+        assert_eq!(shared.size(), 199_999);
+    }
+
+    #[test]
+    fn undo_stack() {
+        let mut history = vec![PersistentList::new()];
+        for edit in ["a", "b", "c"] {
+            let next = history.last().unwrap().prepend(edit);
+            history.push(next);
+        }
+        history.pop();
+        let current = history.last().unwrap();
+        assert_eq!(current.iter().copied().collect::<Vec<_>>(), vec!["b", "a"]);
+        assert_eq!((&history[1]).into_iter().count(), 1);
+    }
+}
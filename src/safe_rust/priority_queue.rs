@@ -0,0 +1,92 @@
+/*
+ * Priority Queue trait
+ * Common interface for `Heap`, `PairingHeap` and `BinomialHeap`, so call sites can switch
+ * between them depending on which operations dominate:
+ * - `Heap`: contiguous `Vec`, fastest `push`/`pop`, but `meld` is O(n + m).
+ * - `PairingHeap`: O(1) `push` and `meld`, amortized O(log n) `pop`, cheap `decrease_key`.
+ * - `BinomialHeap`: O(log n) worst case `meld`, `pop` and `decrease_key`, amortized O(1) `push`.
+ */
+use std::ops::Deref;
+
+pub trait PriorityQueue<T> {
+    /// What `peek` hands out: a plain reference for `Heap`, a `Ref` guard for the node based heaps.
+    type Peek<'a>: Deref<Target = T>
+    where
+        Self: 'a;
+
+    fn push(&mut self, value: T);
+
+    /// Removes and returns the most preferable item according to the comparator.
+    fn pop(&mut self) -> Option<T>;
+
+    fn peek(&self) -> Option<Self::Peek<'_>>;
+
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Moves every item of `other` into `self`. The comparator of `self` is kept.
+    fn meld(&mut self, other: Self);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PriorityQueue;
+    use crate::{BinomialHeap, Heap, PairingHeap};
+
+    fn min_heap_comparator(a: &i32, b: &i32) -> bool {
+        a < b
+    }
+
+    // Exercises a priority queue only through the trait.
+    fn check_priority_queue<Q: PriorityQueue<i32>>(mut first: Q, mut second: Q) {
+        assert!(first.is_empty());
+        assert!(first.peek().is_none());
+        assert_eq!(first.pop(), None);
+
+        for value in [5, 3, 9, 1] {
+            first.push(value);
+        }
+        for value in [4, 8, 2, 7, 6] {
+            second.push(value);
+        }
+        assert_eq!(first.len(), 4);
+        assert_eq!(first.peek().map(|value| *value), Some(1));
+
+        first.meld(second);
+        assert_eq!(first.len(), 9);
+
+        let mut popped = Vec::new();
+        while let Some(value) = first.pop() {
+            popped.push(value);
+        }
+        assert_eq!(popped, vec![1, 2, 3, 4, 5, 6, 7, 8, 9]);
+        assert!(first.is_empty());
+    }
+
+    #[test]
+    fn heap() {
+        check_priority_queue(
+            Heap::new(min_heap_comparator),
+            Heap::new(min_heap_comparator),
+        );
+    }
+
+    #[test]
+    fn pairing_heap() {
+        check_priority_queue(
+            PairingHeap::new(min_heap_comparator),
+            PairingHeap::new(min_heap_comparator),
+        );
+    }
+
+    #[test]
+    fn binomial_heap() {
+        check_priority_queue(
+            BinomialHeap::new(min_heap_comparator),
+            BinomialHeap::new(min_heap_comparator),
+        );
+    }
+}
@@ -1,5 +1,3 @@
-use std::cell::{Ref, RefMut};
-
 use crate::LinkedListSinglyTail;
 
 pub struct QueueLinkedList<T> {
@@ -33,11 +31,11 @@ impl<T> QueueLinkedList<T> {
         self.list.clear();
     }
 
-    pub fn peek(&self) -> Option<Ref<T>> {
+    pub fn peek(&self) -> Option<&T> {
         self.list.peek()
     }
 
-    pub fn peek_mut(&mut self) -> Option<RefMut<T>> {
+    pub fn peek_mut(&mut self) -> Option<&mut T> {
         self.list.peek_mut()
     }
 }
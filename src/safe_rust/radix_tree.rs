@@ -0,0 +1,464 @@
+/*
+Radix Tree
+ Compressed trie: a chain of nodes with a single child and no value becomes a single edge
+ labeled with all their bytes, so the tree has at most 2n nodes for n keys no matter how long
+ the keys are, and lookups compare whole runs of bytes at a time.
+
+ The children of a node start with different bytes, kept sorted so iteration is lexicographic.
+ -insert walks down matching labels. When a key diverges in the middle of a label,
+  the edge is split at that point and the new key hangs from the split node.
+ -remove does the reverse: a node left without a value is dropped if it has no children,
+  or merged into its parent's edge if it has a single child.
+
+ Labels are bytes rather than strings, since a split can land between the bytes of a char
+ (e.g. "é" and "è" share their first byte). Keys are still whole strings at every value.
+*/
+
+type Edge<V> = (Vec<u8>, Box<Node<V>>);
+
+struct Node<V> {
+    value: Option<V>,
+    // Sorted by the first byte of the label, labels are never empty.
+    children: Vec<Edge<V>>,
+}
+
+fn common_prefix_len(a: &[u8], b: &[u8]) -> usize {
+    a.iter().zip(b).take_while(|(x, y)| x == y).count()
+}
+
+impl<V> Node<V> {
+    fn new(value: Option<V>) -> Self {
+        Node {
+            value,
+            children: Vec::new(),
+        }
+    }
+
+    // Position of the child whose label starts with `byte`, or where it would go.
+    fn child_index(&self, byte: u8) -> Result<usize, usize> {
+        self.children
+            .binary_search_by_key(&byte, |(label, _)| label[0])
+    }
+
+    fn insert(&mut self, key: &[u8], value: V) -> Option<V> {
+        if key.is_empty() {
+            return self.value.replace(value);
+        }
+        let index = match self.child_index(key[0]) {
+            Ok(index) => index,
+            Err(index) => {
+                let leaf = Box::new(Node::new(Some(value)));
+                self.children.insert(index, (key.to_vec(), leaf));
+                return None;
+            }
+        };
+
+        let (label, child) = &mut self.children[index];
+        let common = common_prefix_len(label, key);
+        if common < label.len() {
+            // Split the edge: the shared part leads to a new node, which keeps the rest of the old edge.
+            let rest = label.split_off(common);
+            let old_child = std::mem::replace(child, Box::new(Node::new(None)));
+            child.children.push((rest, old_child));
+        }
+        child.insert(&key[common..], value)
+    }
+
+    fn find(&self, key: &[u8]) -> Option<&Node<V>> {
+        let mut node = self;
+        let mut key = key;
+        while !key.is_empty() {
+            let (label, child) = &node.children[node.child_index(key[0]).ok()?];
+            key = key.strip_prefix(label.as_slice())?;
+            node = child;
+        }
+        Some(node)
+    }
+
+    fn find_mut(&mut self, key: &[u8]) -> Option<&mut Node<V>> {
+        if key.is_empty() {
+            return Some(self);
+        }
+        let index = self.child_index(key[0]).ok()?;
+        let (label, child) = &mut self.children[index];
+        let rest = key.strip_prefix(label.as_slice())?;
+        child.find_mut(rest)
+    }
+
+    fn remove(&mut self, key: &[u8]) -> Option<V> {
+        if key.is_empty() {
+            return self.value.take();
+        }
+        let index = self.child_index(key[0]).ok()?;
+        let (label, child) = &mut self.children[index];
+        let removed = child.remove(key.strip_prefix(label.as_slice())?);
+        if removed.is_some() && child.value.is_none() {
+            match child.children.len() {
+                0 => {
+                    self.children.remove(index);
+                }
+                1 => {
+                    // Merge the only grandchild into this edge.
+                    let (grandchild_label, grandchild) = child.children.pop().unwrap();
+                    label.extend(grandchild_label);
+                    *child = grandchild;
+                }
+                _ => {}
+            }
+        }
+        removed
+    }
+}
+
+pub struct RadixTree<V> {
+    // The root is the only node that may have no value and a single child.
+    root: Node<V>,
+    len: usize,
+}
+
+impl<V> RadixTree<V> {
+    pub fn new() -> Self {
+        RadixTree {
+            root: Node::new(None),
+            len: 0,
+        }
+    }
+
+    /// Inserts a key-value pair, returning the previous value if the key was already present.
+    pub fn insert(&mut self, key: &str, value: V) -> Option<V> {
+        let replaced = self.root.insert(key.as_bytes(), value);
+        if replaced.is_none() {
+            self.len += 1;
+        }
+        replaced
+    }
+
+    pub fn get(&self, key: &str) -> Option<&V> {
+        self.root.find(key.as_bytes())?.value.as_ref()
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut V> {
+        self.root.find_mut(key.as_bytes())?.value.as_mut()
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Removes a key, returning its value if it was present.
+    pub fn remove(&mut self, key: &str) -> Option<V> {
+        let removed = self.root.remove(key.as_bytes());
+        if removed.is_some() {
+            self.len -= 1;
+        }
+        removed
+    }
+
+    /// Returns an iterator over the entries whose key starts with `prefix`, in lexicographic order.
+    pub fn starts_with(&self, prefix: &str) -> Iter<'_, V> {
+        let mut node = &self.root;
+        let mut key = Vec::new();
+        let mut prefix = prefix.as_bytes();
+        while !prefix.is_empty() {
+            let index = match node.child_index(prefix[0]) {
+                Ok(index) => index,
+                Err(_) => return Iter::empty(),
+            };
+            let (label, child) = &node.children[index];
+            let common = common_prefix_len(label, prefix);
+            // The prefix either covers the whole label or ends inside it.
+            if common < label.len() && common < prefix.len() {
+                return Iter::empty();
+            }
+            key.extend_from_slice(label);
+            prefix = &prefix[common..];
+            node = child;
+        }
+        Iter::new(node, key)
+    }
+
+    /// Longest key that is a prefix of `text`, with its value.
+    pub fn longest_prefix<'t>(&self, text: &'t str) -> Option<(&'t str, &V)> {
+        let mut node = &self.root;
+        let mut depth = 0;
+        let mut longest = node.value.as_ref().map(|value| (0, value));
+        let bytes = text.as_bytes();
+        while depth < bytes.len() {
+            let index = match node.child_index(bytes[depth]) {
+                Ok(index) => index,
+                Err(_) => break,
+            };
+            let (label, child) = &node.children[index];
+            if !bytes[depth..].starts_with(label) {
+                break;
+            }
+            depth += label.len();
+            node = child;
+            if let Some(value) = &node.value {
+                longest = Some((depth, value));
+            }
+        }
+        // Keys are whole strings, so the match always ends on a char boundary.
+        longest.map(|(len, value)| (&text[..len], value))
+    }
+
+    /// Returns an iterator over all the entries in lexicographic order.
+    pub fn iter(&self) -> Iter<'_, V> {
+        Iter::new(&self.root, Vec::new())
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.root = Node::new(None);
+        self.len = 0;
+    }
+}
+
+impl<V> Default for RadixTree<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Pre-order iterator over a subtree, rebuilding the keys in a shared buffer.
+pub struct Iter<'a, V> {
+    // Children left to visit, one level per node on the current path.
+    stack: Vec<std::slice::Iter<'a, Edge<V>>>,
+    // Length of the label that led to each node on the stack, to shorten the key on the way back.
+    label_lens: Vec<usize>,
+    key: Vec<u8>,
+    // Value of the subtree root, yielded first.
+    root_value: Option<&'a V>,
+}
+
+impl<'a, V> Iter<'a, V> {
+    fn new(root: &'a Node<V>, key: Vec<u8>) -> Self {
+        Iter {
+            stack: vec![root.children.iter()],
+            label_lens: Vec::new(),
+            key,
+            root_value: root.value.as_ref(),
+        }
+    }
+
+    fn empty() -> Self {
+        Iter {
+            stack: Vec::new(),
+            label_lens: Vec::new(),
+            key: Vec::new(),
+            root_value: None,
+        }
+    }
+
+    fn current_key(&self) -> String {
+        String::from_utf8(self.key.clone()).expect("RadixTree: a value ends a valid UTF-8 key")
+    }
+}
+
+impl<'a, V> Iterator for Iter<'a, V> {
+    type Item = (String, &'a V);
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(value) = self.root_value.take() {
+            return Some((self.current_key(), value));
+        }
+        loop {
+            match self.stack.last_mut()?.next() {
+                Some((label, child)) => {
+                    self.key.extend_from_slice(label);
+                    self.label_lens.push(label.len());
+                    self.stack.push(child.children.iter());
+                    if let Some(value) = &child.value {
+                        return Some((self.current_key(), value));
+                    }
+                }
+                None => {
+                    self.stack.pop();
+                    if let Some(len) = self.label_lens.pop() {
+                        self.key.truncate(self.key.len() - len);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<'a, V> IntoIterator for &'a RadixTree<V> {
+    type Item = (String, &'a V);
+    type IntoIter = Iter<'a, V>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys<V>(iter: Iter<'_, V>) -> Vec<String> {
+        iter.map(|(key, _)| key).collect()
+    }
+
+    // Checks the compression: no empty labels, distinct first bytes in order, and no node
+    // other than the root without a value and with fewer than two children.
+    fn assert_compressed<V>(node: &Node<V>, is_root: bool) -> usize {
+        if !is_root && node.value.is_none() {
+            assert!(node.children.len() >= 2);
+        }
+        assert!(node
+            .children
+            .windows(2)
+            .all(|pair| pair[0].0[0] < pair[1].0[0]));
+        1 + node
+            .children
+            .iter()
+            .map(|(label, child)| {
+                assert!(!label.is_empty());
+                assert_compressed(child, false)
+            })
+            .sum::<usize>()
+    }
+
+    #[test]
+    fn insert_and_get() {
+        let mut tree = RadixTree::new();
+        assert_eq!(tree.insert("romane", 1), None);
+        assert_eq!(tree.insert("romanus", 2), None);
+        assert_eq!(tree.insert("romulus", 3), None);
+        assert_eq!(tree.insert("rubens", 4), None);
+        assert_eq!(tree.insert("romane", 5), Some(1));
+        assert_compressed(&tree.root, true);
+        assert_eq!(tree.len(), 4);
+        assert_eq!(tree.get("romane"), Some(&5));
+        assert_eq!(tree.get("romulus"), Some(&3));
+        assert_eq!(tree.get("rom"), None);
+        assert_eq!(tree.get("romanes"), None);
+        assert_eq!(tree.get("ru"), None);
+    }
+
+    #[test]
+    fn split_edge_for_prefix_key() {
+        let mut tree = RadixTree::new();
+        tree.insert("testing", 1);
+        tree.insert("test", 2);
+        assert_compressed(&tree.root, true);
+        assert_eq!(tree.root.children[0].0, b"test");
+        assert_eq!(tree.get("test"), Some(&2));
+        assert_eq!(tree.get("testing"), Some(&1));
+    }
+
+    #[test]
+    fn get_mut() {
+        let mut tree = RadixTree::new();
+        tree.insert("abc", 1);
+        *tree.get_mut("abc").unwrap() += 1;
+        assert_eq!(tree.get("abc"), Some(&2));
+        assert_eq!(tree.get_mut("ab"), None);
+    }
+
+    #[test]
+    fn remove_merges_nodes() {
+        let mut tree = RadixTree::new();
+        tree.insert("test", 1);
+        tree.insert("team", 2);
+        tree.insert("toast", 3);
+        let nodes = assert_compressed(&tree.root, true);
+
+        // "te" is left with the single child "st", which merges back into "test".
+        assert_eq!(tree.remove("team"), Some(2));
+        assert_eq!(assert_compressed(&tree.root, true), nodes - 2);
+        assert_eq!(tree.get("test"), Some(&1));
+        assert_eq!(tree.remove("team"), None);
+        assert_eq!(tree.remove("te"), None);
+
+        assert_eq!(tree.remove("test"), Some(1));
+        assert_eq!(tree.remove("toast"), Some(3));
+        assert_eq!(assert_compressed(&tree.root, true), 1);
+        assert!(tree.is_empty());
+    }
+
+    #[test]
+    fn remove_inner_value_merges_with_child() {
+        let mut tree = RadixTree::new();
+        tree.insert("test", 1);
+        tree.insert("testing", 2);
+        assert_eq!(tree.remove("test"), Some(1));
+        assert_compressed(&tree.root, true);
+        assert_eq!(tree.root.children[0].0, b"testing");
+    }
+
+    #[test]
+    fn many_keys() {
+        let words: Vec<String> = (0..500)
+            .map(|i| format!("key{}", (i * 7919) % 500))
+            .collect();
+        let mut tree = RadixTree::new();
+        for word in &words {
+            tree.insert(word, word.clone());
+        }
+        assert_compressed(&tree.root, true);
+        for word in words.iter().filter(|word| word.len() % 2 == 0) {
+            assert_eq!(tree.remove(word).as_ref(), Some(word));
+        }
+        assert_compressed(&tree.root, true);
+        let mut expected: Vec<String> = words
+            .iter()
+            .filter(|word| word.len() % 2 == 1)
+            .cloned()
+            .collect();
+        expected.sort();
+        assert_eq!(keys(tree.iter()), expected);
+    }
+
+    #[test]
+    fn starts_with() {
+        let mut tree = RadixTree::new();
+        for word in ["tea", "ten", "to", "ted", "inn", "tea party", "i"] {
+            tree.insert(word, ());
+        }
+        assert_eq!(
+            keys(tree.starts_with("te")),
+            vec!["tea", "tea party", "ted", "ten"]
+        );
+        // Ends in the middle of the "a party" edge.
+        assert_eq!(keys(tree.starts_with("tea p")), vec!["tea party"]);
+        assert_eq!(keys(tree.starts_with("tex")), Vec::<String>::new());
+        assert_eq!(
+            keys(tree.starts_with("tea party time")),
+            Vec::<String>::new()
+        );
+        assert_eq!(keys((&tree).into_iter()).len(), 7);
+    }
+
+    #[test]
+    fn longest_prefix() {
+        let mut tree = RadixTree::new();
+        tree.insert("/", "root");
+        tree.insert("/api", "api");
+        tree.insert("/api/users", "users");
+        assert_eq!(
+            tree.longest_prefix("/api/users/42"),
+            Some(("/api/users", &"users"))
+        );
+        assert_eq!(tree.longest_prefix("/api/us"), Some(("/api", &"api")));
+        assert_eq!(tree.longest_prefix("/static"), Some(("/", &"root")));
+        assert_eq!(tree.longest_prefix("api"), None);
+    }
+
+    #[test]
+    fn multibyte_keys_split_inside_chars() {
+        let mut tree = RadixTree::new();
+        tree.insert("né", 1);
+        tree.insert("nè", 2);
+        assert_compressed(&tree.root, true);
+        assert_eq!(keys(tree.iter()), vec!["nè", "né"]);
+        assert_eq!(tree.remove("nè"), Some(2));
+        assert_eq!(tree.root.children[0].0, "né".as_bytes());
+    }
+}
@@ -0,0 +1,52 @@
+/*
+ * Pseudo random number generator (SplitMix64) for the randomized structures.
+ * Not suitable for anything security related, but fast, good enough to balance a tree,
+ * and deterministic: the same seed always builds the same structure, so tests are reproducible
+ * and the crate doesn't need a dependency.
+ */
+
+pub(crate) struct Random {
+    state: u64,
+}
+
+impl Random {
+    pub(crate) fn new(seed: u64) -> Self {
+        Random { state: seed }
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Random;
+
+    #[test]
+    fn same_seed_same_sequence() {
+        let mut first = Random::new(42);
+        let mut second = Random::new(42);
+        for _ in 0..100 {
+            assert_eq!(first.next_u64(), second.next_u64());
+        }
+    }
+
+    #[test]
+    fn different_seeds_differ() {
+        let mut first = Random::new(1);
+        let mut second = Random::new(2);
+        assert_ne!(first.next_u64(), second.next_u64());
+    }
+
+    #[test]
+    fn zero_seed_is_usable() {
+        let mut random = Random::new(0);
+        let values: Vec<u64> = (0..10).map(|_| random.next_u64()).collect();
+        assert!(values.iter().all(|&value| value != 0));
+    }
+}
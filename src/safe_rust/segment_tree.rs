@@ -0,0 +1,558 @@
+/*
+Segment Tree
+ Range aggregates over a fixed length array: every node stores the aggregate of a segment, the
+ root covers the whole array and each child covers half of its parent's segment.
+ Any range is the union of O(log n) node segments, so queries and point updates are O(log n).
+
+ The aggregate is defined by a monoid: an associative `combine` with an `identity`,
+ e.g. sum with 0, min with the largest value, gcd with 0. `Sum`, `Min`, `Max` and `Gcd` are
+ provided for the primitive numbers, other operations implement `Monoid`.
+
+ Range assignment and range addition are lazy: an update that covers a node's whole segment
+ only fixes that node's aggregate and leaves a pending tag, which is pushed down to the children
+ the next time something goes through the node. Both stay O(log n).
+ Range addition needs to know how adding to every element changes an aggregate (sum grows by
+ delta * count, min by delta), so it's only available for operations implementing `RangeAdd`,
+ on trees whose pending tags can hold an addition: `SegmentTree<T, Op, AssignOrAdd<T>>`.
+ The default `Assign` tags keep range assignment working for any monoid, e.g. gcd.
+*/
+
+use std::ops::{Bound, RangeBounds};
+
+pub trait Monoid<T> {
+    fn identity() -> T;
+
+    /// Must be associative: `combine(a, combine(b, c)) == combine(combine(a, b), c)`.
+    fn combine(a: &T, b: &T) -> T;
+
+    /// Aggregate of `count` copies of `value`, used by range assignment.
+    /// The default combines by doubling in O(log count).
+    fn repeat(value: &T, count: usize) -> T {
+        let mut result = Self::identity();
+        let mut power = Self::combine(&Self::identity(), value);
+        let mut count = count;
+        while count > 0 {
+            if count & 1 == 1 {
+                result = Self::combine(&result, &power);
+            }
+            power = Self::combine(&power, &power);
+            count >>= 1;
+        }
+        result
+    }
+}
+
+pub trait RangeAdd<T>: Monoid<T> {
+    /// Aggregate of a segment of `count` elements after adding `delta` to each of them.
+    /// With a `count` of 1 this must be the plain sum `aggregate + delta`.
+    fn add(aggregate: &T, delta: &T, count: usize) -> T;
+}
+
+pub struct Sum;
+pub struct Min;
+pub struct Max;
+pub struct Gcd;
+
+// `count` copies of a number added up. It overflows exactly when adding them one by one with
+// `combine` would, so the lazy updates panic (or wrap in release builds) like the eager ones.
+trait Times {
+    fn times(&self, count: usize) -> Self;
+}
+
+macro_rules! impl_times_integer {
+    ($($t:ty),*) => {$(
+        impl Times for $t {
+            fn times(&self, count: usize) -> $t {
+                match <$t>::try_from(count) {
+                    Ok(count) => self * count,
+                    // Only types narrower than usize get here, their product fits in an i128.
+                    Err(_) => {
+                        let product = *self as i128 * count as i128;
+                        <$t>::try_from(product).unwrap_or_else(|_| {
+                            if cfg!(debug_assertions) {
+                                panic!("attempt to multiply with overflow");
+                            }
+                            product as $t
+                        })
+                    }
+                }
+            }
+        }
+    )*};
+}
+
+macro_rules! impl_times_float {
+    ($($t:ty),*) => {$(
+        impl Times for $t {
+            fn times(&self, count: usize) -> $t {
+                self * count as $t
+            }
+        }
+    )*};
+}
+
+macro_rules! impl_numeric_monoids {
+    ($($t:ty),*) => {$(
+        impl Monoid<$t> for Sum {
+            fn identity() -> $t {
+                0 as $t
+            }
+            fn combine(a: &$t, b: &$t) -> $t {
+                a + b
+            }
+            fn repeat(value: &$t, count: usize) -> $t {
+                value.times(count)
+            }
+        }
+
+        impl RangeAdd<$t> for Sum {
+            fn add(aggregate: &$t, delta: &$t, count: usize) -> $t {
+                aggregate + delta.times(count)
+            }
+        }
+
+        impl Monoid<$t> for Min {
+            fn identity() -> $t {
+                <$t>::MAX
+            }
+            fn combine(a: &$t, b: &$t) -> $t {
+                (*a).min(*b)
+            }
+            fn repeat(value: &$t, _count: usize) -> $t {
+                *value
+            }
+        }
+
+        impl RangeAdd<$t> for Min {
+            fn add(aggregate: &$t, delta: &$t, _count: usize) -> $t {
+                aggregate + delta
+            }
+        }
+
+        impl Monoid<$t> for Max {
+            fn identity() -> $t {
+                <$t>::MIN
+            }
+            fn combine(a: &$t, b: &$t) -> $t {
+                (*a).max(*b)
+            }
+            fn repeat(value: &$t, _count: usize) -> $t {
+                *value
+            }
+        }
+
+        impl RangeAdd<$t> for Max {
+            fn add(aggregate: &$t, delta: &$t, _count: usize) -> $t {
+                aggregate + delta
+            }
+        }
+    )*};
+}
+
+macro_rules! impl_gcd {
+    ($($t:ty),*) => {$(
+        impl Monoid<$t> for Gcd {
+            fn identity() -> $t {
+                0
+            }
+            fn combine(a: &$t, b: &$t) -> $t {
+                let (mut a, mut b) = (*a, *b);
+                while b != 0 {
+                    (a, b) = (b, a % b);
+                }
+                a
+            }
+            fn repeat(value: &$t, _count: usize) -> $t {
+                *value
+            }
+        }
+    )*};
+}
+
+impl_times_integer!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+impl_times_float!(f32, f64);
+impl_numeric_monoids!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64);
+impl_gcd!(u8, u16, u32, u64, u128, usize);
+
+/// A pending update of a node's whole segment, left at the node until something goes through it.
+pub trait Tag<T, Op: Monoid<T>>: Clone {
+    /// The tag setting every element to `value`.
+    fn assign(value: T) -> Self;
+
+    /// Aggregate of a segment of `count` elements, `aggregate` before the update.
+    fn apply(&self, aggregate: &T, count: usize) -> T;
+
+    /// The single tag doing `self` then `later`.
+    fn then(self, later: Self) -> Self;
+}
+
+/// Tag of the trees only supporting range assignment, the default.
+#[derive(Clone)]
+pub struct Assign<T>(T);
+
+impl<T: Clone, Op: Monoid<T>> Tag<T, Op> for Assign<T> {
+    fn assign(value: T) -> Self {
+        Assign(value)
+    }
+
+    fn apply(&self, _aggregate: &T, count: usize) -> T {
+        Op::repeat(&self.0, count)
+    }
+
+    fn then(self, later: Self) -> Self {
+        later
+    }
+}
+
+/// Tag of the trees supporting range addition too, e.g. `SegmentTree<i64, Sum, AssignOrAdd<i64>>`.
+#[derive(Clone)]
+pub enum AssignOrAdd<T> {
+    Assign(T),
+    Add(T),
+}
+
+impl<T: Clone, Op: RangeAdd<T>> Tag<T, Op> for AssignOrAdd<T> {
+    fn assign(value: T) -> Self {
+        AssignOrAdd::Assign(value)
+    }
+
+    fn apply(&self, aggregate: &T, count: usize) -> T {
+        match self {
+            AssignOrAdd::Assign(value) => Op::repeat(value, count),
+            AssignOrAdd::Add(delta) => Op::add(aggregate, delta, count),
+        }
+    }
+
+    // Adding on top of a pending update folds into it.
+    fn then(self, later: Self) -> Self {
+        match (self, later) {
+            (_, AssignOrAdd::Assign(value)) => AssignOrAdd::Assign(value),
+            (AssignOrAdd::Add(previous), AssignOrAdd::Add(delta)) => {
+                AssignOrAdd::Add(Op::add(&previous, &delta, 1))
+            }
+            (AssignOrAdd::Assign(value), AssignOrAdd::Add(delta)) => {
+                AssignOrAdd::Assign(Op::add(&value, &delta, 1))
+            }
+        }
+    }
+}
+
+pub struct SegmentTree<T, Op: Monoid<T>, P: Tag<T, Op> = Assign<T>> {
+    len: usize,
+    // Node 1 is the root, the children of node i are 2i and 2i + 1.
+    aggregates: Vec<T>,
+    pending: Vec<Option<P>>,
+    op: std::marker::PhantomData<Op>,
+}
+
+impl<T: Clone, Op: Monoid<T>, P: Tag<T, Op>> SegmentTree<T, Op, P> {
+    /// Creates a tree over `len` elements, all set to the identity.
+    pub fn new(len: usize) -> Self {
+        Self::from_vec(vec![Op::identity(); len])
+    }
+
+    pub fn from_vec(values: Vec<T>) -> Self {
+        let len = values.len();
+        let nodes = 4 * len.max(1);
+        let mut tree = SegmentTree {
+            len,
+            aggregates: vec![Op::identity(); nodes],
+            pending: vec![None; nodes],
+            op: std::marker::PhantomData,
+        };
+        if len > 0 {
+            tree.build(1, 0, len, &values);
+        }
+        tree
+    }
+
+    fn build(&mut self, node: usize, low: usize, high: usize, values: &[T]) {
+        if high - low == 1 {
+            self.aggregates[node] = values[low].clone();
+            return;
+        }
+        let middle = low + (high - low) / 2;
+        self.build(2 * node, low, middle, values);
+        self.build(2 * node + 1, middle, high, values);
+        self.pull(node);
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Aggregate of the elements in `range`, the identity for an empty range.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds.
+    pub fn query<R: RangeBounds<usize>>(&self, range: R) -> T {
+        let (start, end) = self.bounds(range);
+        if start == end {
+            return Op::identity();
+        }
+        self.query_recursive(1, 0, self.len, start, end)
+    }
+
+    pub fn get(&self, index: usize) -> T {
+        self.query(index..=index)
+    }
+
+    /// Sets the element at `index` to `value`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= len`.
+    pub fn update(&mut self, index: usize, value: T) {
+        self.range_assign(index..=index, value);
+    }
+
+    /// Sets every element in `range` to `value`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds.
+    pub fn range_assign<R: RangeBounds<usize>>(&mut self, range: R, value: T) {
+        let (start, end) = self.bounds(range);
+        if start < end {
+            self.update_recursive(1, 0, self.len, start, end, &P::assign(value));
+        }
+    }
+
+    fn bounds<R: RangeBounds<usize>>(&self, range: R) -> (usize, usize) {
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start + 1,
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&end) => end + 1,
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => self.len,
+        };
+        assert!(
+            start <= end && end <= self.len,
+            "SegmentTree: range {}..{} out of bounds for length {}",
+            start,
+            end,
+            self.len
+        );
+        (start, end)
+    }
+
+    // Doesn't push pending tags down, so queries don't need `&mut self`: below a node with a
+    // pending tag the children are stale, and the tag is applied to their result instead.
+    fn query_recursive(&self, node: usize, low: usize, high: usize, start: usize, end: usize) -> T {
+        if start <= low && high <= end {
+            return self.aggregates[node].clone();
+        }
+        let middle = low + (high - low) / 2;
+        let result = if end <= middle {
+            self.query_recursive(2 * node, low, middle, start, end)
+        } else if start >= middle {
+            self.query_recursive(2 * node + 1, middle, high, start, end)
+        } else {
+            let left = self.query_recursive(2 * node, low, middle, start, end);
+            let right = self.query_recursive(2 * node + 1, middle, high, start, end);
+            Op::combine(&left, &right)
+        };
+        match &self.pending[node] {
+            Some(tag) => tag.apply(&result, end.min(high) - start.max(low)),
+            None => result,
+        }
+    }
+
+    fn update_recursive(
+        &mut self,
+        node: usize,
+        low: usize,
+        high: usize,
+        start: usize,
+        end: usize,
+        tag: &P,
+    ) {
+        if end <= low || high <= start {
+            return;
+        }
+        if start <= low && high <= end {
+            self.apply(node, high - low, tag.clone());
+            return;
+        }
+        self.push_down(node, low, high);
+        let middle = low + (high - low) / 2;
+        self.update_recursive(2 * node, low, middle, start, end, tag);
+        self.update_recursive(2 * node + 1, middle, high, start, end, tag);
+        self.pull(node);
+    }
+
+    // Applies an update to the whole segment of `node`, which has `count` elements.
+    fn apply(&mut self, node: usize, count: usize, tag: P) {
+        self.aggregates[node] = tag.apply(&self.aggregates[node], count);
+        if count > 1 {
+            self.pending[node] = Some(match self.pending[node].take() {
+                Some(previous) => previous.then(tag),
+                None => tag,
+            });
+        }
+    }
+
+    fn push_down(&mut self, node: usize, low: usize, high: usize) {
+        if let Some(tag) = self.pending[node].take() {
+            let middle = low + (high - low) / 2;
+            self.apply(2 * node, middle - low, tag.clone());
+            self.apply(2 * node + 1, high - middle, tag);
+        }
+    }
+
+    fn pull(&mut self, node: usize) {
+        self.aggregates[node] =
+            Op::combine(&self.aggregates[2 * node], &self.aggregates[2 * node + 1]);
+    }
+}
+
+impl<T: Clone, Op: RangeAdd<T>> SegmentTree<T, Op, AssignOrAdd<T>> {
+    /// Adds `delta` to every element in `range`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds.
+    pub fn range_add<R: RangeBounds<usize>>(&mut self, range: R, delta: T) {
+        let (start, end) = self.bounds(range);
+        if start < end {
+            self.update_recursive(1, 0, self.len, start, end, &AssignOrAdd::Add(delta));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::safe_rust::test_utils::scrambled;
+
+    #[test]
+    fn sum_query() {
+        let tree: SegmentTree<i64, Sum> = SegmentTree::from_vec(vec![1, 2, 3, 4, 5]);
+        assert_eq!(tree.query(..), 15);
+        assert_eq!(tree.query(1..4), 9);
+        assert_eq!(tree.query(2..=2), 3);
+        assert_eq!(tree.query(3..3), 0);
+        assert_eq!(tree.len(), 5);
+    }
+
+    #[test]
+    fn min_max_gcd() {
+        let values: Vec<u32> = vec![12, 18, 6, 30, 42];
+        let min: SegmentTree<u32, Min> = SegmentTree::from_vec(values.clone());
+        let max: SegmentTree<u32, Max> = SegmentTree::from_vec(values.clone());
+        let mut gcd: SegmentTree<u32, Gcd> = SegmentTree::from_vec(values);
+        assert_eq!(min.query(0..2), 12);
+        assert_eq!(min.query(..), 6);
+        assert_eq!(max.query(1..3), 18);
+        assert_eq!(max.query(..), 42);
+        assert_eq!(gcd.query(0..2), 6);
+        assert_eq!(gcd.query(3..5), 6);
+        gcd.update(2, 9);
+        assert_eq!(gcd.query(..3), 3);
+    }
+
+    #[test]
+    fn point_update() {
+        let mut tree: SegmentTree<i64, Sum> = SegmentTree::new(8);
+        tree.update(3, 10);
+        tree.update(5, -4);
+        assert_eq!(tree.query(..), 6);
+        assert_eq!(tree.query(4..), -4);
+        assert_eq!(tree.get(3), 10);
+    }
+
+    #[test]
+    fn range_assign_and_add_match_vec() {
+        let mut values = scrambled(100);
+        let mut sum: SegmentTree<i64, Sum, AssignOrAdd<i64>> =
+            SegmentTree::from_vec(values.clone());
+        let mut min: SegmentTree<i64, Min, AssignOrAdd<i64>> =
+            SegmentTree::from_vec(values.clone());
+        for step in 0..200_i64 {
+            let a = ((step * 7919) % 100) as usize;
+            let b = ((step * 104_729) % 100) as usize;
+            let (start, end) = (a.min(b), a.max(b) + 1);
+            if step % 3 == 0 {
+                sum.range_assign(start..end, step);
+                min.range_assign(start..end, step);
+                values[start..end]
+                    .iter_mut()
+                    .for_each(|value| *value = step);
+            } else {
+                sum.range_add(start..end, step - 100);
+                min.range_add(start..end, step - 100);
+                values[start..end]
+                    .iter_mut()
+                    .for_each(|value| *value += step - 100);
+            }
+            let (query_start, query_end) = (a / 2, (b + 100) / 2);
+            let window = &values[query_start..query_end];
+            assert_eq!(sum.query(query_start..query_end), window.iter().sum());
+            assert_eq!(
+                min.query(query_start..query_end),
+                *window.iter().min().unwrap_or(&i64::MAX)
+            );
+        }
+        for (index, value) in values.iter().enumerate() {
+            assert_eq!(sum.get(index), *value);
+        }
+    }
+
+    #[test]
+    fn sum_repeat_converts_count_without_truncating() {
+        // 256 copies wrap to 0 when the count is cast to a u8.
+        assert_eq!(<Sum as Monoid<u8>>::repeat(&0, 256), 0);
+        assert_eq!(<Sum as Monoid<i8>>::repeat(&-1, 128), -128);
+        assert_eq!(<Sum as RangeAdd<u16>>::add(&1, &0, 70_000), 1);
+        assert_eq!(<Sum as Monoid<f64>>::repeat(&0.5, 300), 150.0);
+    }
+
+    #[test]
+    #[should_panic]
+    #[cfg(debug_assertions)]
+    fn sum_repeat_overflow_panics_like_combine() {
+        <Sum as Monoid<u8>>::repeat(&1, 256);
+    }
+
+    // A monoid not provided by the module: string concatenation, which isn't commutative.
+    struct Concat;
+    impl Monoid<String> for Concat {
+        fn identity() -> String {
+            String::new()
+        }
+        fn combine(a: &String, b: &String) -> String {
+            format!("{}{}", a, b)
+        }
+    }
+
+    #[test]
+    fn custom_monoid() {
+        let letters = "abcdef".chars().map(String::from).collect();
+        let mut tree: SegmentTree<String, Concat> = SegmentTree::from_vec(letters);
+        assert_eq!(tree.query(1..4), "bcd");
+        tree.range_assign(2..5, "x".to_string());
+        assert_eq!(tree.query(..), "abxxxf");
+    }
+
+    #[test]
+    fn empty_tree() {
+        let tree: SegmentTree<i32, Sum> = SegmentTree::from_vec(Vec::new());
+        assert!(tree.is_empty());
+        assert_eq!(tree.query(..), 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn query_out_of_bounds() {
+        let tree: SegmentTree<i32, Sum> = SegmentTree::new(4);
+        tree.query(2..5);
+    }
+}
@@ -0,0 +1,562 @@
+/*
+Skip List
+ An ordered map made of a sorted linked list plus "express lanes": every node gets a random
+ height, and on level l it links to the next node that is at least l + 1 levels tall.
+ With heights drawn so that half the nodes reach level 2, a quarter level 3 and so on,
+ searches skip over O(log n) nodes on average, no rebalancing needed.
+
+ Nodes live in an arena (a Vec) and link to each other by index instead of by pointer.
+ Removed slots go to a free list and get reused by the next insert.
+
+ Every link also stores its span, the number of level 0 steps it jumps over. Adding up spans
+ along a search gives the rank of a key, and following spans finds the key with a given rank,
+ both in O(log n) expected.
+
+ Heights come from an in-crate PRNG, `with_seed` builds the same list for the same inputs.
+*/
+
+use std::ops::RangeBounds;
+
+use crate::safe_rust::ordered_set::{is_above_lower, is_below_upper};
+use crate::safe_rust::random::Random;
+
+const MAX_LEVEL: usize = 32;
+const DEFAULT_SEED: u64 = 0x5EED;
+
+#[derive(Clone, Copy)]
+struct Link {
+    next: Option<usize>,
+    // Only meaningful when `next` is some.
+    span: usize,
+}
+
+const NO_LINK: Link = Link {
+    next: None,
+    span: 0,
+};
+
+struct Node<K, V> {
+    key: K,
+    value: V,
+    // One link per level the node reaches.
+    links: Vec<Link>,
+}
+
+// For every level, the last position (`None` for the head) whose key comes before a search key,
+// and the rank of that position, counting from 1 with the head at 0.
+struct Path {
+    positions: [Option<usize>; MAX_LEVEL],
+    ranks: [usize; MAX_LEVEL],
+}
+
+pub struct SkipList<K: Ord, V> {
+    // Arena of nodes, `None` for free slots.
+    nodes: Vec<Option<Node<K, V>>>,
+    free: Vec<usize>,
+    head: [Link; MAX_LEVEL],
+    // Number of levels in use.
+    level: usize,
+    len: usize,
+    random: Random,
+}
+
+impl<K: Ord, V> SkipList<K, V> {
+    pub fn new() -> Self {
+        Self::with_seed(DEFAULT_SEED)
+    }
+
+    /// Creates an empty list whose node heights are drawn from a PRNG seeded with `seed`.
+    pub fn with_seed(seed: u64) -> Self {
+        SkipList {
+            nodes: Vec::new(),
+            free: Vec::new(),
+            head: [NO_LINK; MAX_LEVEL],
+            level: 0,
+            len: 0,
+            random: Random::new(seed),
+        }
+    }
+
+    fn node(&self, index: usize) -> &Node<K, V> {
+        self.nodes[index]
+            .as_ref()
+            .expect("SkipList: a link points to a free slot")
+    }
+
+    fn links(&self, position: Option<usize>) -> &[Link] {
+        match position {
+            None => &self.head,
+            Some(index) => &self.node(index).links,
+        }
+    }
+
+    fn links_mut(&mut self, position: Option<usize>) -> &mut [Link] {
+        match position {
+            None => &mut self.head,
+            Some(index) => {
+                &mut self.nodes[index]
+                    .as_mut()
+                    .expect("SkipList: a link points to a free slot")
+                    .links
+            }
+        }
+    }
+
+    // Goes down from the top level, moving right while the next key satisfies `before`.
+    // `before` must be true for a prefix of the keys, e.g. "less than the key".
+    fn find_path<F: Fn(&K) -> bool>(&self, before: F) -> Path {
+        let mut path = Path {
+            positions: [None; MAX_LEVEL],
+            ranks: [0; MAX_LEVEL],
+        };
+        let mut position = None;
+        let mut rank = 0;
+        for level in (0..self.level).rev() {
+            while let Link {
+                next: Some(next),
+                span,
+            } = self.links(position)[level]
+            {
+                if !before(&self.node(next).key) {
+                    break;
+                }
+                rank += span;
+                position = Some(next);
+            }
+            path.positions[level] = position;
+            path.ranks[level] = rank;
+        }
+        path
+    }
+
+    // Index of the node with exactly `key`, if any.
+    fn find(&self, key: &K) -> Option<usize> {
+        let path = self.find_path(|other| other < key);
+        self.links(path.positions[0])[0]
+            .next
+            .filter(|&next| self.node(next).key == *key)
+    }
+
+    // Height of a new node: 1 plus the number of coin flips in a row that came up tails.
+    fn random_height(&mut self) -> usize {
+        let flips = self.random.next_u64().trailing_zeros() as usize;
+        (flips + 1).min(MAX_LEVEL)
+    }
+
+    fn allocate(&mut self, node: Node<K, V>) -> usize {
+        match self.free.pop() {
+            Some(index) => {
+                self.nodes[index] = Some(node);
+                index
+            }
+            None => {
+                self.nodes.push(Some(node));
+                self.nodes.len() - 1
+            }
+        }
+    }
+
+    /// Inserts a key-value pair, returning the previous value if the key was already present.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let path = self.find_path(|other| *other < key);
+        if let Some(next) = self.links(path.positions[0])[0].next {
+            if self.node(next).key == key {
+                let node = self.nodes[next].as_mut().unwrap();
+                return Some(std::mem::replace(&mut node.value, value));
+            }
+        }
+
+        // New levels start at the head with rank 0, which is what `find_path` left in the path.
+        let height = self.random_height();
+        self.level = self.level.max(height);
+
+        // The new node goes right after `path.positions[0]`, at rank `path.ranks[0] + 1`.
+        let offsets: Vec<usize> = (0..height)
+            .map(|level| path.ranks[0] - path.ranks[level])
+            .collect();
+        let links = (0..height)
+            .map(|level| {
+                let before = self.links(path.positions[level])[level];
+                Link {
+                    next: before.next,
+                    span: before.next.map_or(0, |_| before.span - offsets[level]),
+                }
+            })
+            .collect();
+        let index = self.allocate(Node { key, value, links });
+
+        for (level, offset) in offsets.into_iter().enumerate() {
+            self.links_mut(path.positions[level])[level] = Link {
+                next: Some(index),
+                span: offset + 1,
+            };
+        }
+        // Links above the new node now jump over one more node.
+        for level in height..self.level {
+            let link = &mut self.links_mut(path.positions[level])[level];
+            if link.next.is_some() {
+                link.span += 1;
+            }
+        }
+        self.len += 1;
+        None
+    }
+
+    /// Removes a key, returning its value if it was present.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let path = self.find_path(|other| other < key);
+        let index = self.links(path.positions[0])[0]
+            .next
+            .filter(|&next| self.node(next).key == *key)?;
+        let node = self.nodes[index].take().unwrap();
+        self.free.push(index);
+
+        for level in 0..self.level {
+            let link = &mut self.links_mut(path.positions[level])[level];
+            if link.next == Some(index) {
+                let removed = node.links[level];
+                *link = Link {
+                    next: removed.next,
+                    span: removed.next.map_or(0, |_| link.span + removed.span - 1),
+                };
+            } else if link.next.is_some() {
+                link.span -= 1;
+            }
+        }
+        while self.level > 0 && self.head[self.level - 1].next.is_none() {
+            self.level -= 1;
+        }
+        self.len -= 1;
+        Some(node.value)
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.find(key).map(|index| &self.node(index).value)
+    }
+
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let index = self.find(key)?;
+        self.nodes[index].as_mut().map(|node| &mut node.value)
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.find(key).is_some()
+    }
+
+    /// Number of keys smaller than `key`, which is also its index if it's in the list.
+    pub fn rank(&self, key: &K) -> usize {
+        self.find_path(|other| other < key).ranks[0]
+    }
+
+    /// Entry at `index` in ascending key order.
+    pub fn get_by_rank(&self, index: usize) -> Option<(&K, &V)> {
+        if index >= self.len {
+            return None;
+        }
+        let target = index + 1;
+        let mut position = None;
+        let mut rank = 0;
+        for level in (0..self.level).rev() {
+            while let Link {
+                next: Some(next),
+                span,
+            } = self.links(position)[level]
+            {
+                if rank + span > target {
+                    break;
+                }
+                rank += span;
+                position = Some(next);
+            }
+            if rank == target {
+                break;
+            }
+        }
+        let node = self.node(position?);
+        Some((&node.key, &node.value))
+    }
+
+    /// Entry with the smallest key.
+    pub fn first(&self) -> Option<(&K, &V)> {
+        let node = self.node(self.head[0].next?);
+        Some((&node.key, &node.value))
+    }
+
+    /// Entry with the largest key.
+    pub fn last(&self) -> Option<(&K, &V)> {
+        let mut position = None;
+        for level in (0..self.level).rev() {
+            while let Some(next) = self.links(position)[level].next {
+                position = Some(next);
+            }
+        }
+        let node = self.node(position?);
+        Some((&node.key, &node.value))
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.free.clear();
+        self.head = [NO_LINK; MAX_LEVEL];
+        self.level = 0;
+        self.len = 0;
+    }
+
+    /// Returns an iterator over the entries in ascending key order.
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            list: self,
+            next: self.head[0].next,
+            remaining: self.len,
+        }
+    }
+
+    /// Returns an iterator over the entries whose key is within `range`, in ascending key order.
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> Iter<'_, K, V> {
+        let start = self.find_path(|key| !is_above_lower(key, range.start_bound()));
+        // Everything below the upper bound, minus what comes before the start.
+        let below_upper = self
+            .find_path(|key| is_below_upper(key, range.end_bound()))
+            .ranks[0];
+        Iter {
+            list: self,
+            next: self.links(start.positions[0])[0].next,
+            remaining: below_upper.saturating_sub(start.ranks[0]),
+        }
+    }
+}
+
+impl<K: Ord, V> Default for SkipList<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Iterator over the entries of a `SkipList`, for the whole list or for a `range`.
+pub struct Iter<'a, K: Ord, V> {
+    list: &'a SkipList<K, V>,
+    next: Option<usize>,
+    remaining: usize,
+}
+
+impl<'a, K: Ord, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let node = self.list.node(self.next?);
+        self.next = node.links[0].next;
+        self.remaining -= 1;
+        Some((&node.key, &node.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, K: Ord, V> IntoIterator for &'a SkipList<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::safe_rust::test_utils::scrambled;
+
+    // Checks that level 0 is sorted and complete, that every level only links to taller
+    // nodes further right, and that every span matches the ranks.
+    fn assert_valid<K: Ord, V>(list: &SkipList<K, V>) {
+        let mut ranks = vec![0; list.nodes.len()];
+        let mut position = None;
+        let mut rank = 0;
+        while let Some(next) = list.links(position)[0].next {
+            if let Some(index) = position {
+                assert!(list.node(index).key < list.node(next).key);
+            }
+            rank += 1;
+            ranks[next] = rank;
+            position = Some(next);
+        }
+        assert_eq!(rank, list.len);
+        assert_eq!(list.nodes.len() - list.free.len(), list.len);
+
+        let rank_of = |position: Option<usize>| position.map_or(0, |index| ranks[index]);
+        for level in 0..MAX_LEVEL {
+            if level >= list.level {
+                assert!(list.head[level].next.is_none());
+                continue;
+            }
+            let mut position = None;
+            while let Some(next) = list.links(position)[level].next {
+                assert!(list.node(next).links.len() > level);
+                let span = list.links(position)[level].span;
+                assert_eq!(span, rank_of(Some(next)) - rank_of(position));
+                position = Some(next);
+            }
+        }
+    }
+
+    #[test]
+    fn insert_and_get() {
+        let mut list = SkipList::new();
+        for key in scrambled(300) {
+            assert_eq!(list.insert(key, key * 10), None);
+        }
+        assert_valid(&list);
+        assert_eq!(list.len(), 300);
+        assert_eq!(list.get(&42), Some(&420));
+        assert_eq!(list.get(&300), None);
+        assert!(list.contains_key(&0));
+    }
+
+    #[test]
+    fn insert_existing_replaces_value() {
+        let mut list = SkipList::new();
+        list.insert("a", 1);
+        assert_eq!(list.insert("a", 2), Some(1));
+        assert_eq!(list.get(&"a"), Some(&2));
+        assert_eq!(list.len(), 1);
+    }
+
+    #[test]
+    fn get_mut() {
+        let mut list = SkipList::new();
+        list.insert(1, 10);
+        *list.get_mut(&1).unwrap() += 5;
+        assert_eq!(list.get(&1), Some(&15));
+        assert_eq!(list.get_mut(&2), None);
+    }
+
+    #[test]
+    fn remove() {
+        let mut list = SkipList::new();
+        for key in scrambled(200) {
+            list.insert(key, ());
+        }
+        for key in scrambled(200).into_iter().filter(|key| key % 2 == 0) {
+            assert_eq!(list.remove(&key), Some(()));
+        }
+        assert_eq!(list.remove(&0), None);
+        assert_valid(&list);
+        let keys: Vec<i32> = list.iter().map(|(key, _)| *key).collect();
+        assert_eq!(keys, (1..200).step_by(2).collect::<Vec<i32>>());
+    }
+
+    #[test]
+    fn remove_everything_then_reuse_slots() {
+        let mut list = SkipList::new();
+        for key in 0..50 {
+            list.insert(key, ());
+        }
+        for key in 0..50 {
+            list.remove(&key);
+        }
+        assert!(list.is_empty());
+        assert_eq!(list.level, 0);
+        for key in 0..50 {
+            list.insert(key, ());
+        }
+        assert_valid(&list);
+        assert_eq!(list.nodes.len(), 50);
+    }
+
+    #[test]
+    fn same_seed_same_levels() {
+        let mut first = SkipList::with_seed(9);
+        let mut second = SkipList::with_seed(9);
+        for key in scrambled(100) {
+            first.insert(key, ());
+            second.insert(key, ());
+        }
+        let heights = |list: &SkipList<i32, ()>| -> Vec<usize> {
+            list.nodes
+                .iter()
+                .map(|node| node.as_ref().unwrap().links.len())
+                .collect()
+        };
+        assert_eq!(heights(&first), heights(&second));
+    }
+
+    #[test]
+    fn rank_and_get_by_rank() {
+        let mut list = SkipList::new();
+        for key in scrambled(100) {
+            list.insert(key * 2, ());
+        }
+        assert_eq!(list.rank(&0), 0);
+        assert_eq!(list.rank(&50), 25);
+        assert_eq!(list.rank(&51), 26);
+        assert_eq!(list.rank(&1000), 100);
+        for index in 0..100 {
+            let expected = index as i32 * 2;
+            assert_eq!(list.get_by_rank(index), Some((&expected, &())));
+        }
+        assert_eq!(list.get_by_rank(100), None);
+    }
+
+    #[test]
+    fn first_and_last() {
+        let mut list = SkipList::new();
+        assert_eq!(list.first(), None);
+        assert_eq!(list.last(), None);
+        for key in scrambled(30) {
+            list.insert(key, ());
+        }
+        assert_eq!(list.first(), Some((&0, &())));
+        assert_eq!(list.last(), Some((&29, &())));
+    }
+
+    #[test]
+    fn range() {
+        let mut list = SkipList::new();
+        for key in scrambled(50) {
+            list.insert(key * 2, ());
+        }
+        let keys = |range: Iter<'_, i32, ()>| range.map(|(k, _)| *k).collect::<Vec<i32>>();
+        assert_eq!(keys(list.range(10..17)), vec![10, 12, 14, 16]);
+        assert_eq!(keys(list.range(11..=16)), vec![12, 14, 16]);
+        assert_eq!(keys(list.range(..4)), vec![0, 2]);
+        assert_eq!(keys(list.range(95..)), vec![96, 98]);
+        assert_eq!(keys(list.range(..)).len(), 50);
+        assert_eq!(keys(list.range(11..12)), Vec::<i32>::new());
+        assert_eq!(list.range(10..17).size_hint(), (4, Some(4)));
+    }
+
+    #[test]
+    fn iter_in_order() {
+        let mut list = SkipList::new();
+        for key in scrambled(100) {
+            list.insert(key, -key);
+        }
+        let entries: Vec<(i32, i32)> = (&list).into_iter().map(|(k, v)| (*k, *v)).collect();
+        let expected: Vec<(i32, i32)> = (0..100).map(|k| (k, -k)).collect();
+        assert_eq!(entries, expected);
+    }
+
+    #[test]
+    fn clear() {
+        let mut list = SkipList::new();
+        for key in 0..10 {
+            list.insert(key, ());
+        }
+        list.clear();
+        assert!(list.is_empty());
+        assert_eq!(list.iter().next(), None);
+        list.insert(1, ());
+        assert_valid(&list);
+    }
+}
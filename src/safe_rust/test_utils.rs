@@ -0,0 +1,30 @@
+/*
+ * Fixtures shared by the test modules.
+ */
+
+/// `0..len` in a deterministic scrambled order: 7919 is prime, so as long as it doesn't
+/// divide `len` every value appears exactly once.
+pub(crate) fn scrambled<T: Copy + TryFrom<usize> + TryInto<usize>>(len: T) -> Vec<T> {
+    let len = to_usize(len);
+    (0..len).map(|i| from_usize(i * 7919 % len)).collect()
+}
+
+/// `len` values in `0..=len / 2`, in a deterministic order and with plenty of duplicates.
+pub(crate) fn scrambled_with_duplicates<T: Copy + TryFrom<usize> + TryInto<usize>>(
+    len: T,
+) -> Vec<T> {
+    let len = to_usize(len);
+    (0..len)
+        .map(|i| from_usize(i * 7919 % (len / 2 + 1)))
+        .collect()
+}
+
+fn to_usize<T: TryInto<usize>>(len: T) -> usize {
+    len.try_into()
+        .unwrap_or_else(|_| panic!("scrambled: negative length"))
+}
+
+fn from_usize<T: TryFrom<usize>>(value: usize) -> T {
+    T::try_from(value)
+        .unwrap_or_else(|_| panic!("scrambled: {} doesn't fit the element type", value))
+}
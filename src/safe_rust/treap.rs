@@ -0,0 +1,677 @@
+/*
+Treap
+ A binary search tree on the keys that is at the same time a heap on random priorities:
+ every node's priority is greater than its children's. With random priorities the shape is the
+ same as a BST built from a random insertion order, so the expected height is O(log n).
+ Like the AVL tree, equal values are ignored.
+
+ Everything is built on two operations, both expected O(log n):
+ -split: cuts a treap into the keys before a position and the keys from that position on.
+ -merge: joins two treaps where every key of the first comes before every key of the second,
+  by putting the root with the higher priority on top.
+ Insert is split + merge + merge, remove is split + split + merge.
+
+ImplicitTreap
+ Same structure, but the "key" of a node is its position in the sequence, which is never stored:
+ it's the size of the left subtree plus everything to the left on the way down. This gives a
+ sequence with O(log n) insert, remove, split and merge at any index.
+
+Priorities come from an in-crate PRNG, `with_seed` builds the same tree for the same inputs.
+*/
+
+use std::cmp::Ordering;
+use std::ops::RangeBounds;
+
+use crate::safe_rust::ordered_set::{self, is_above_lower, is_below_upper, OrderedSet, TreeNode};
+use crate::safe_rust::random::Random;
+
+const DEFAULT_SEED: u64 = 0x5EED;
+
+type Link<T> = Option<Box<Node<T>>>;
+pub struct Node<T> {
+    left: Link<T>,
+    right: Link<T>,
+    priority: u64,
+    // Number of nodes in this subtree, used for the implicit keys.
+    size: usize,
+    value: T,
+}
+
+impl<T> TreeNode for Node<T> {
+    type Key = T;
+    fn key(&self) -> &T {
+        &self.value
+    }
+    fn left(&self) -> Option<&Self> {
+        self.left.as_deref()
+    }
+    fn right(&self) -> Option<&Self> {
+        self.right.as_deref()
+    }
+}
+
+impl<T> Node<T> {
+    fn new(value: T, priority: u64) -> Box<Node<T>> {
+        Box::new(Node {
+            left: None,
+            right: None,
+            priority,
+            size: 1,
+            value,
+        })
+    }
+
+    fn update_size(&mut self) {
+        self.size = 1 + size(&self.left) + size(&self.right);
+    }
+}
+
+fn size<T>(link: &Link<T>) -> usize {
+    link.as_ref().map_or(0, |node| node.size)
+}
+
+// Joins two treaps, every value of `left` must come before every value of `right`.
+fn merge<T>(left: Link<T>, right: Link<T>) -> Link<T> {
+    match (left, right) {
+        (None, right) => right,
+        (left, None) => left,
+        (Some(mut left), Some(mut right)) => {
+            if left.priority > right.priority {
+                left.right = merge(left.right.take(), Some(right));
+                left.update_size();
+                Some(left)
+            } else {
+                right.left = merge(Some(left), right.left.take());
+                right.update_size();
+                Some(right)
+            }
+        }
+    }
+}
+
+// Splits a treap into the values for which `goes_left` is true and the rest.
+// `goes_left` must be true for a prefix of the values, e.g. "less than the key".
+fn split_by<T, F: Fn(&T) -> bool>(link: Link<T>, goes_left: &F) -> (Link<T>, Link<T>) {
+    match link {
+        None => (None, None),
+        Some(mut node) => {
+            if goes_left(&node.value) {
+                let (middle, right) = split_by(node.right.take(), goes_left);
+                node.right = middle;
+                node.update_size();
+                (Some(node), right)
+            } else {
+                let (left, middle) = split_by(node.left.take(), goes_left);
+                node.left = middle;
+                node.update_size();
+                (left, Some(node))
+            }
+        }
+    }
+}
+
+// Splits a treap into its first `index` values and the rest.
+fn split_at<T>(link: Link<T>, index: usize) -> (Link<T>, Link<T>) {
+    match link {
+        None => (None, None),
+        Some(mut node) => {
+            let left_size = size(&node.left);
+            if index <= left_size {
+                let (left, middle) = split_at(node.left.take(), index);
+                node.left = middle;
+                node.update_size();
+                (left, Some(node))
+            } else {
+                let (middle, right) = split_at(node.right.take(), index - left_size - 1);
+                node.right = middle;
+                node.update_size();
+                (Some(node), right)
+            }
+        }
+    }
+}
+
+pub struct Treap<T: Ord> {
+    root: Link<T>,
+    random: Random,
+}
+
+impl<T: Ord> Treap<T> {
+    pub fn new() -> Self {
+        Self::with_seed(DEFAULT_SEED)
+    }
+
+    /// Creates an empty treap whose priorities are drawn from a PRNG seeded with `seed`.
+    pub fn with_seed(seed: u64) -> Self {
+        Treap {
+            root: None,
+            random: Random::new(seed),
+        }
+    }
+
+    pub fn insert(&mut self, key: T) {
+        self.insert_key(key);
+    }
+
+    fn insert_key(&mut self, key: T) -> bool {
+        if self.contains_key(&key) {
+            return false;
+        }
+        let new_node = Node::new(key, self.random.next_u64());
+        let (left, right) = split_by(self.root.take(), &|value: &T| *value < new_node.value);
+        self.root = merge(merge(left, Some(new_node)), right);
+        true
+    }
+
+    pub fn remove(&mut self, key: T) {
+        self.remove_key(&key);
+    }
+
+    fn remove_key(&mut self, key: &T) -> bool {
+        let (left, rest) = split_by(self.root.take(), &|value: &T| value < key);
+        let (removed, right) = split_by(rest, &|value: &T| value <= key);
+        self.root = merge(left, right);
+        removed.is_some()
+    }
+
+    pub fn contains(&self, key: T) -> bool {
+        self.contains_key(&key)
+    }
+
+    fn contains_key(&self, key: &T) -> bool {
+        let mut current = &self.root;
+        while let Some(node) = current {
+            match key.cmp(&node.value) {
+                Ordering::Less => current = &node.left,
+                Ordering::Equal => return true,
+                Ordering::Greater => current = &node.right,
+            }
+        }
+        false
+    }
+
+    /// Moves every key greater than or equal to `key` into a new treap.
+    pub fn split(&mut self, key: &T) -> Treap<T> {
+        let (left, right) = split_by(self.root.take(), &|value: &T| value < key);
+        self.root = left;
+        Treap {
+            root: right,
+            random: Random::new(self.random.next_u64()),
+        }
+    }
+
+    /// Appends every key of `other`, which must all be greater than the keys of `self`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a key of `other` is not greater than every key of `self`.
+    pub fn merge(&mut self, mut other: Treap<T>) {
+        if let (Some(last), Some(first)) = (self.last(), other.first()) {
+            assert!(
+                last < first,
+                "Treap::merge: every key of other must be greater than the keys of self"
+            );
+        }
+        self.root = merge(self.root.take(), other.root.take());
+    }
+
+    pub fn first(&self) -> Option<&T> {
+        let mut current = self.root.as_ref()?;
+        while let Some(left) = &current.left {
+            current = left;
+        }
+        Some(&current.value)
+    }
+
+    pub fn last(&self) -> Option<&T> {
+        let mut current = self.root.as_ref()?;
+        while let Some(right) = &current.right {
+            current = right;
+        }
+        Some(&current.value)
+    }
+
+    pub fn size(&self) -> usize {
+        size(&self.root)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    pub fn clear(&mut self) {
+        self.root = None;
+    }
+
+    /// Returns an iterator over the keys in ascending order.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter::new(self.root.as_deref())
+    }
+
+    /// Returns an iterator over the keys within `range`, in ascending order.
+    pub fn range<R: RangeBounds<T>>(&self, range: R) -> Range<'_, T> {
+        // The range holds this many keys: the keys below the upper bound minus the keys below
+        // the lower bound, both counted with the subtree sizes.
+        let below_upper = self.count_while(|key| is_below_upper(key, range.end_bound()));
+        let below_lower = self.count_while(|key| !is_above_lower(key, range.start_bound()));
+        Range {
+            remaining: below_upper.saturating_sub(below_lower),
+            iter: ordered_set::Range::new(self.root.as_deref(), range),
+        }
+    }
+
+    // Counts the keys for which `f` is true, `f` must be true for a prefix of the keys.
+    fn count_while<F: Fn(&T) -> bool>(&self, f: F) -> usize {
+        let mut count = 0;
+        let mut current = &self.root;
+        while let Some(node) = current {
+            if f(&node.value) {
+                count += size(&node.left) + 1;
+                current = &node.right;
+            } else {
+                current = &node.left;
+            }
+        }
+        count
+    }
+}
+
+impl<T: Ord> Default for Treap<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Ord> OrderedSet<T> for Treap<T> {
+    type Range<'a>
+        = Range<'a, T>
+    where
+        Self: 'a,
+        T: 'a;
+
+    fn insert(&mut self, key: T) -> bool {
+        self.insert_key(key)
+    }
+
+    fn remove(&mut self, key: &T) -> bool {
+        self.remove_key(key)
+    }
+
+    fn contains(&self, key: &T) -> bool {
+        self.contains_key(key)
+    }
+
+    fn first(&self) -> Option<&T> {
+        Treap::first(self)
+    }
+
+    fn last(&self) -> Option<&T> {
+        Treap::last(self)
+    }
+
+    fn range<R: RangeBounds<T>>(&self, range: R) -> Self::Range<'_> {
+        Treap::range(self, range)
+    }
+
+    fn len(&self) -> usize {
+        self.size()
+    }
+}
+
+pub struct ImplicitTreap<T> {
+    root: Link<T>,
+    random: Random,
+}
+
+impl<T> ImplicitTreap<T> {
+    pub fn new() -> Self {
+        Self::with_seed(DEFAULT_SEED)
+    }
+
+    /// Creates an empty sequence whose priorities are drawn from a PRNG seeded with `seed`.
+    pub fn with_seed(seed: u64) -> Self {
+        ImplicitTreap {
+            root: None,
+            random: Random::new(seed),
+        }
+    }
+
+    /// Inserts `value` so that it ends up at `index`, shifting everything after it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index > len`.
+    pub fn insert(&mut self, index: usize, value: T) {
+        assert!(
+            index <= self.len(),
+            "ImplicitTreap::insert: index {} is out of bounds (len {})",
+            index,
+            self.len()
+        );
+        let new_node = Node::new(value, self.random.next_u64());
+        let (left, right) = split_at(self.root.take(), index);
+        self.root = merge(merge(left, Some(new_node)), right);
+    }
+
+    pub fn push_front(&mut self, value: T) {
+        self.insert(0, value);
+    }
+
+    pub fn push_back(&mut self, value: T) {
+        self.insert(self.len(), value);
+    }
+
+    /// Removes and returns the value at `index`, or `None` if `index` is out of bounds.
+    pub fn remove(&mut self, index: usize) -> Option<T> {
+        if index >= self.len() {
+            return None;
+        }
+        let (left, rest) = split_at(self.root.take(), index);
+        let (removed, right) = split_at(rest, 1);
+        self.root = merge(left, right);
+        removed.map(|node| node.value)
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        let mut index = index;
+        let mut current = &self.root;
+        while let Some(node) = current {
+            let left_size = size(&node.left);
+            match index.cmp(&left_size) {
+                Ordering::Less => current = &node.left,
+                Ordering::Equal => return Some(&node.value),
+                Ordering::Greater => {
+                    index -= left_size + 1;
+                    current = &node.right;
+                }
+            }
+        }
+        None
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        let mut index = index;
+        let mut current = &mut self.root;
+        while let Some(node) = current {
+            let left_size = size(&node.left);
+            match index.cmp(&left_size) {
+                Ordering::Less => current = &mut node.left,
+                Ordering::Equal => return Some(&mut node.value),
+                Ordering::Greater => {
+                    index -= left_size + 1;
+                    current = &mut node.right;
+                }
+            }
+        }
+        None
+    }
+
+    /// Moves the values from `index` on into a new sequence.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index > len`.
+    pub fn split(&mut self, index: usize) -> ImplicitTreap<T> {
+        assert!(
+            index <= self.len(),
+            "ImplicitTreap::split: index {} is out of bounds (len {})",
+            index,
+            self.len()
+        );
+        let (left, right) = split_at(self.root.take(), index);
+        self.root = left;
+        ImplicitTreap {
+            root: right,
+            random: Random::new(self.random.next_u64()),
+        }
+    }
+
+    /// Appends every value of `other` to the end of the sequence.
+    pub fn merge(&mut self, mut other: ImplicitTreap<T>) {
+        self.root = merge(self.root.take(), other.root.take());
+    }
+
+    pub fn len(&self) -> usize {
+        size(&self.root)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    pub fn clear(&mut self) {
+        self.root = None;
+    }
+
+    /// Returns an iterator over the values in sequence order.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter::new(self.root.as_deref())
+    }
+}
+
+impl<T> Default for ImplicitTreap<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub type Iter<'a, T> = ordered_set::Iter<'a, Node<T>>;
+
+// The shared range iterator, plus the exact number of keys left for `size_hint`.
+pub struct Range<'a, T> {
+    iter: ordered_set::Range<'a, Node<T>>,
+    remaining: usize,
+}
+
+impl<'a, T> Iterator for Range<'a, T> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        let key = self.iter.next()?;
+        self.remaining -= 1;
+        Some(key)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::safe_rust::test_utils::scrambled;
+
+    // Checks the search tree order, the heap order of the priorities and the subtree sizes.
+    fn assert_valid<T: Ord>(link: &Link<T>) -> usize {
+        let node = match link {
+            Some(node) => node,
+            None => return 0,
+        };
+        for child in [&node.left, &node.right].into_iter().flatten() {
+            assert!(child.priority <= node.priority);
+        }
+        if let Some(left) = &node.left {
+            assert!(left.value < node.value);
+        }
+        if let Some(right) = &node.right {
+            assert!(right.value > node.value);
+        }
+        let size = 1 + assert_valid(&node.left) + assert_valid(&node.right);
+        assert_eq!(node.size, size);
+        size
+    }
+
+    #[test]
+    fn insert_and_contains() {
+        let mut treap = Treap::new();
+        for key in scrambled(200) {
+            treap.insert(key);
+        }
+        assert_valid(&treap.root);
+        assert_eq!(treap.size(), 200);
+        assert!(treap.contains(0));
+        assert!(treap.contains(199));
+        assert!(!treap.contains(200));
+    }
+
+    #[test]
+    fn insert_duplicates_ignored() {
+        let mut treap = Treap::new();
+        treap.insert(5);
+        treap.insert(5);
+        assert_eq!(treap.size(), 1);
+    }
+
+    #[test]
+    fn remove() {
+        let mut treap = Treap::new();
+        for key in scrambled(100) {
+            treap.insert(key);
+        }
+        for key in (0..100).step_by(2) {
+            treap.remove(key);
+        }
+        assert_valid(&treap.root);
+        let keys: Vec<i32> = treap.iter().copied().collect();
+        assert_eq!(keys, (1..100).step_by(2).collect::<Vec<i32>>());
+    }
+
+    #[test]
+    fn same_seed_same_shape() {
+        let mut first = Treap::with_seed(7);
+        let mut second = Treap::with_seed(7);
+        for key in scrambled(50) {
+            first.insert(key);
+            second.insert(key);
+        }
+        let root_key = |treap: &Treap<i32>| treap.root.as_ref().unwrap().value;
+        assert_eq!(root_key(&first), root_key(&second));
+    }
+
+    #[test]
+    fn expected_height_is_logarithmic() {
+        fn height<T>(link: &Link<T>) -> usize {
+            link.as_ref()
+                .map_or(0, |node| 1 + height(&node.left).max(height(&node.right)))
+        }
+        let mut treap = Treap::new();
+        // Sorted input would make a plain BST a list.
+        for key in 0..10_000 {
+            treap.insert(key);
+        }
+        assert!(height(&treap.root) < 60);
+    }
+
+    #[test]
+    fn split_and_merge() {
+        let mut treap = Treap::new();
+        for key in scrambled(100) {
+            treap.insert(key);
+        }
+        let upper = treap.split(&40);
+        assert_valid(&treap.root);
+        assert_valid(&upper.root);
+        assert_eq!(treap.size(), 40);
+        assert_eq!(upper.size(), 60);
+        assert_eq!(treap.last(), Some(&39));
+        assert_eq!(upper.first(), Some(&40));
+
+        treap.merge(upper);
+        assert_valid(&treap.root);
+        let keys: Vec<i32> = treap.iter().copied().collect();
+        assert_eq!(keys, (0..100).collect::<Vec<i32>>());
+    }
+
+    #[test]
+    #[should_panic]
+    fn merge_overlapping_panics() {
+        let mut first = Treap::new();
+        first.insert(5);
+        let mut second = Treap::new();
+        second.insert(3);
+        first.merge(second);
+    }
+
+    #[test]
+    fn range() {
+        let mut treap = Treap::new();
+        for key in scrambled(50) {
+            treap.insert(key);
+        }
+        let keys: Vec<i32> = treap.range(10..15).copied().collect();
+        assert_eq!(keys, vec![10, 11, 12, 13, 14]);
+        assert_eq!(treap.range(10..15).size_hint(), (5, Some(5)));
+    }
+
+    #[test]
+    fn ordered_set_conformance() {
+        crate::safe_rust::ordered_set::conformance::run(Treap::new);
+    }
+
+    #[test]
+    fn implicit_insert_at_index() {
+        let mut sequence = ImplicitTreap::new();
+        sequence.push_back('b');
+        sequence.push_back('d');
+        sequence.push_front('a');
+        sequence.insert(2, 'c');
+        sequence.insert(4, 'e');
+        let values: String = sequence.iter().collect();
+        assert_eq!(values, "abcde");
+        assert_eq!(sequence.len(), 5);
+        assert_eq!(sequence.get(3), Some(&'d'));
+        assert_eq!(sequence.get(5), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn implicit_insert_out_of_bounds() {
+        let mut sequence = ImplicitTreap::new();
+        sequence.insert(1, 'a');
+    }
+
+    #[test]
+    fn implicit_matches_vec() {
+        let mut sequence = ImplicitTreap::with_seed(3);
+        let mut expected = Vec::new();
+        for i in 0..500 {
+            let index = (i * 7919) % (expected.len() + 1);
+            sequence.insert(index, i);
+            expected.insert(index, i);
+        }
+        for i in 0..200 {
+            let index = (i * 104_729) % expected.len();
+            assert_eq!(sequence.remove(index), Some(expected.remove(index)));
+        }
+        assert_eq!(sequence.remove(expected.len()), None);
+        let values: Vec<usize> = sequence.iter().copied().collect();
+        assert_eq!(values, expected);
+    }
+
+    #[test]
+    fn implicit_get_mut() {
+        let mut sequence = ImplicitTreap::new();
+        for i in 0..10 {
+            sequence.push_back(i);
+        }
+        *sequence.get_mut(4).unwrap() = 40;
+        assert_eq!(sequence.get(4), Some(&40));
+    }
+
+    #[test]
+    fn implicit_split_and_merge() {
+        let mut sequence = ImplicitTreap::new();
+        for i in 0..10 {
+            sequence.push_back(i);
+        }
+        let mut tail = sequence.split(6);
+        assert_eq!(sequence.len(), 6);
+        assert_eq!(tail.len(), 4);
+        assert_eq!(tail.get(0), Some(&6));
+
+        // Move the tail to the front.
+        tail.merge(sequence);
+        let values: Vec<i32> = tail.iter().copied().collect();
+        assert_eq!(values, vec![6, 7, 8, 9, 0, 1, 2, 3, 4, 5]);
+    }
+}
@@ -16,14 +16,174 @@ we could have a conditional check to avoid unecessary balance factor calculation
 */
 
 use std::cmp::Ordering;
+use std::ops::RangeBounds;
 
-struct Node<T> {
+use crate::safe_rust::ordered_set::{self, OrderedSet, TreeNode};
+
+pub struct Node<T> {
     left: Option<Box<Node<T>>>,
     right: Option<Box<Node<T>>>,
     height: usize,
     key: T,
 }
 
+impl<T> TreeNode for Node<T> {
+    type Key = T;
+    fn key(&self) -> &T {
+        &self.key
+    }
+    fn left(&self) -> Option<&Self> {
+        self.left.as_deref()
+    }
+    fn right(&self) -> Option<&Self> {
+        self.right.as_deref()
+    }
+}
+
+impl<T> AvlNode for Node<T> {
+    fn left_link(&self) -> &Option<Box<Self>> {
+        &self.left
+    }
+    fn right_link(&self) -> &Option<Box<Self>> {
+        &self.right
+    }
+    fn left_link_mut(&mut self) -> &mut Option<Box<Self>> {
+        &mut self.left
+    }
+    fn right_link_mut(&mut self) -> &mut Option<Box<Self>> {
+        &mut self.right
+    }
+    fn height(&self) -> usize {
+        self.height
+    }
+    fn set_height(&mut self, height: usize) {
+        self.height = height;
+    }
+}
+
+/// What the balancing below needs from a node. Trees that cache more about a subtree than its
+/// height (e.g. the interval tree's largest end point) refresh it in `update_summary` and
+/// reuse the same rotations.
+pub(crate) trait AvlNode: Sized {
+    fn left_link(&self) -> &Option<Box<Self>>;
+    fn right_link(&self) -> &Option<Box<Self>>;
+    fn left_link_mut(&mut self) -> &mut Option<Box<Self>>;
+    fn right_link_mut(&mut self) -> &mut Option<Box<Self>>;
+    fn height(&self) -> usize;
+    fn set_height(&mut self, height: usize);
+
+    /// Called after the height is recomputed, when the children of the node have changed.
+    fn update_summary(&mut self) {}
+}
+
+// Recomputes the height, and whatever else the node caches, from the children.
+pub(crate) fn update<N: AvlNode>(node: &mut Box<N>) {
+    let height = calculate_height(node.as_ref());
+    node.set_height(height);
+    node.update_summary();
+}
+
+pub(crate) fn calculate_height<N: AvlNode>(node: &N) -> usize {
+    if node.left_link().is_none() && node.right_link().is_none() {
+        return 0;
+    }
+    1 + std::cmp::max(
+        node.left_link().as_ref().map_or(0, |child| child.height()),
+        node.right_link().as_ref().map_or(0, |child| child.height()),
+    )
+}
+
+pub(crate) fn balance_factor<N: AvlNode>(node: &N) -> isize {
+    let height_left: isize = node
+        .left_link()
+        .as_ref()
+        .map_or(-1, |child| child.height().try_into().unwrap());
+    let height_right: isize = node
+        .right_link()
+        .as_ref()
+        .map_or(-1, |child| child.height().try_into().unwrap());
+
+    let balance_factor: isize = height_left - height_right;
+    balance_factor
+}
+
+pub(crate) fn check_balance<N: AvlNode>(node: &mut Box<N>) {
+    let balance_factor = balance_factor(node.as_ref());
+    if balance_factor > 1 || balance_factor < -1 {
+        rebalance(node, balance_factor);
+    }
+}
+
+/*
+Balance factor is positive:
+    This indicates that the left subtree is taller than the right subtree.
+    If the balance factor is 1, then the left subtree is just one level deeper than the right subtree.
+    If the balance factor is greater than 1 then it's a sign the AVL property is violated, and the tree is too heavy on the left side.
+Balance factor is negative:
+    This indicates that the right subtree is taller than the left subtree.
+    If the balance factor is -1, then the right subtree is one level deeper than the left subtree.
+    If the balance factor is less than -1, then it's a sign that the AVL property is violated, and the t ree is too heavy on the right side.
+ */
+fn rebalance<N: AvlNode>(node: &mut Box<N>, balance_factor: isize) {
+    // Too heavy on the left side
+    if balance_factor > 1 {
+        let left_child_balance_factor = self::balance_factor(node.left_link().as_deref().unwrap());
+        // A child balance factor of 0 can't happen after an insert, but it can after a remove,
+        // and a single rotation is enough to fix it.
+        if left_child_balance_factor >= 0 {
+            rotate_right(node);
+        } else {
+            rotate_left_then_right(node);
+        }
+    }
+    // Too heavy on the right side
+    else if balance_factor < -1 {
+        let right_child_balance_factor =
+            self::balance_factor(node.right_link().as_deref().unwrap());
+        if right_child_balance_factor <= 0 {
+            rotate_left(node);
+        } else {
+            rotate_right_then_left(node);
+        }
+    }
+}
+
+// Balance factor of the current node is < -1, and balance factor of the right child is <= -1.
+fn rotate_left<N: AvlNode>(node: &mut Box<N>) {
+    let mut right_child = node.right_link_mut().take().unwrap();
+    *node.right_link_mut() = right_child.left_link_mut().take();
+    std::mem::swap(&mut right_child, node);
+
+    // The old root is now the left child, update it first since the new root depends on it.
+    update(&mut right_child);
+    *node.left_link_mut() = Some(right_child);
+    update(node);
+}
+
+// Balance factor of the current node is > 1, and balance factor of the right child is <= -1.
+fn rotate_right<N: AvlNode>(node: &mut Box<N>) {
+    let mut left_child = node.left_link_mut().take().unwrap();
+    *node.left_link_mut() = left_child.right_link_mut().take();
+    std::mem::swap(&mut left_child, node);
+
+    // The old root is now the right child, update it first since the new root depends on it.
+    update(&mut left_child);
+    *node.right_link_mut() = Some(left_child);
+    update(node);
+}
+
+// Balance factor of the current node is < -1, and balance factor of the right child is >= 1.
+fn rotate_left_then_right<N: AvlNode>(node: &mut Box<N>) {
+    rotate_left(node.left_link_mut().as_mut().unwrap());
+    rotate_right(node);
+}
+
+// Balance factor of the current node is > 1, and balance factor of the right child is >= 1.
+fn rotate_right_then_left<N: AvlNode>(node: &mut Box<N>) {
+    rotate_right(node.right_link_mut().as_mut().unwrap());
+    rotate_left(node);
+}
+
 pub struct TreeAVL<T: Ord> {
     root: Option<Box<Node<T>>>,
     size: usize,
@@ -83,128 +243,24 @@ impl<T: Ord> TreeAVL<T> {
         };
 
         if inserted {
-            parent.height = Self::calculate_height(parent);
-            Self::check_balance(parent);
+            update(parent);
+            check_balance(parent);
         }
         inserted
     }
 
-    fn calculate_height(node: &Box<Node<T>>) -> usize {
-        if node.left.is_none() && node.right.is_none() {
-            return 0;
-        }
-        1 + std::cmp::max(
-            node.left.as_ref().map_or(0, |child| child.height),
-            node.right.as_ref().map_or(0, |child| child.height),
-        )
-    }
-
-    fn balance_factor(node: &Box<Node<T>>) -> isize {
-        let height_left: isize = node
-            .left
-            .as_ref()
-            .map_or(-1, |child| child.height.try_into().unwrap());
-        let height_right: isize = node
-            .right
-            .as_ref()
-            .map_or(-1, |child| child.height.try_into().unwrap());
-
-        let balance_factor: isize = height_left - height_right;
-        balance_factor
-    }
-
-    fn check_balance(node: &mut Box<Node<T>>) {
-        let balance_factor = Self::balance_factor(node);
-        if balance_factor > 1 || balance_factor < -1 {
-            Self::rebalance(node, balance_factor);
-        }
-    }
-
-    /*
-    Balance factor is positive:
-        This indicates that the left subtree is taller than the right subtree.
-        If the balance factor is 1, then the left subtree is just one level deeper than the right subtree.
-        If the balance factor is greater than 1 then it's a sign the AVL property is violated, and the tree is too heavy on the left side.
-    Balance factor is negative:
-        This indicates that the right subtree is taller than the left subtree.
-        If the balance factor is -1, then the right subtree is one level deeper than the left subtree.
-        If the balance factor is less than -1, then it's a sign that the AVL property is violated, and the t ree is too heavy on the right side.
-     */
-    fn rebalance(node: &mut Box<Node<T>>, balance_factor: isize) {
-        // Too heavy on the left side
-        if balance_factor > 1 {
-            let left_child_balance_factor = Self::balance_factor(node.left.as_ref().unwrap());
-            if left_child_balance_factor >= 1 {
-                Self::rotate_right(node);
-            } else if left_child_balance_factor <= -1 {
-                Self::rotate_left_then_right(node);
-            } else {
-                panic!("reached impossible case child balance factor is between -1 and 1 non-inclusive");
-            }
-        }
-        // Too heavy on the right side
-        else if balance_factor < -1 {
-            let right_child_balance_factor = Self::balance_factor(node.right.as_ref().unwrap());
-            if right_child_balance_factor <= -1 {
-                Self::rotate_left(node);
-            } else if right_child_balance_factor >= 1 {
-                Self::rotate_right_then_left(node);
-            } else {
-                panic!("reached impossible case child balance factor is between -1 and 1 non-inclusive");
-            }
-        }
-    }
-
-    // Balance factor of the current node is < -1, and balance factor of the right child is <= -1.
-    fn rotate_left(node: &mut Box<Node<T>>) {
-        let mut right_child = node.right.take().unwrap();
-        node.right = right_child.left.take();
-        std::mem::swap(&mut right_child, node);
-
-        // Update height and then attach as right child of node
-        right_child.height = Self::calculate_height(&right_child);
-        node.left = Some(right_child);
-
-        // Update height
-        node.height = Self::calculate_height(node);
-    }
-
-    // Balance factor of the current node is > 1, and balance factor of the right child is <= -1.
-    fn rotate_right(node: &mut Box<Node<T>>) {
-        let mut left_child = node.left.take().unwrap();
-        node.left = left_child.right.take();
-        std::mem::swap(&mut left_child, node);
-
-        // Update height and then attach as right child of node
-        left_child.height = Self::calculate_height(&left_child);
-        node.right = Some(left_child);
-
-        // Update height
-        node.height = Self::calculate_height(node);
-    }
-
-    // Balance factor of the current node is < -1, and balance factor of the right child is >= 1.
-    fn rotate_left_then_right(node: &mut Box<Node<T>>) {
-        Self::rotate_left(node.left.as_mut().unwrap()); // change this line
-        Self::rotate_right(node);
-    }
-
-    // Balance factor of the current node is > 1, and balance factor of the right child is >= 1.
-    fn rotate_right_then_left(node: &mut Box<Node<T>>) {
-        Self::rotate_right(node.right.as_mut().unwrap());
-        Self::rotate_left(node);
-    }
-
     pub fn remove(&mut self, key: T) {
-        Self::remove_recursive(&mut self.root, key);
+        if Self::remove_recursive(&mut self.root, &key) {
+            self.size -= 1;
+        }
     }
 
-    fn remove_recursive(node_option: &mut Option<Box<Node<T>>>, key: T) -> bool {
+    fn remove_recursive(node_option: &mut Option<Box<Node<T>>>, key: &T) -> bool {
         let node = match node_option {
             Some(node) => node,
             None => return false,
         };
-        let found_node_to_delete = match &key.cmp(&node.key) {
+        let found_node_to_delete = match key.cmp(&node.key) {
             Ordering::Less => {
                 // move to the left child
                 Self::remove_recursive(&mut node.left, key)
@@ -238,8 +294,8 @@ impl<T: Ord> TreeAVL<T> {
 
         if found_node_to_delete {
             if let Some(node) = node_option {
-                node.height = Self::calculate_height(node);
-                Self::check_balance(node);
+                update(node);
+                check_balance(node);
             }
         }
 
@@ -247,6 +303,10 @@ impl<T: Ord> TreeAVL<T> {
     }
 
     pub fn contains(&self, key: T) -> bool {
+        self.contains_key(&key)
+    }
+
+    fn contains_key(&self, key: &T) -> bool {
         let mut current = &self.root;
         while let Some(node) = current {
             match key.cmp(&node.key) {
@@ -264,7 +324,7 @@ impl<T: Ord> TreeAVL<T> {
 
     pub fn height(&self) -> usize {
         match &self.root {
-            Some(node) => Self::calculate_height(node),
+            Some(node) => calculate_height(node.as_ref()),
             None => 0,
         }
     }
@@ -275,6 +335,92 @@ impl<T: Ord> TreeAVL<T> {
 
     pub fn clear(&mut self) {
         self.root = None;
+        self.size = 0;
+    }
+
+    pub fn first(&self) -> Option<&T> {
+        let mut current = self.root.as_ref()?;
+        while let Some(left) = &current.left {
+            current = left;
+        }
+        Some(&current.key)
+    }
+
+    pub fn last(&self) -> Option<&T> {
+        let mut current = self.root.as_ref()?;
+        while let Some(right) = &current.right {
+            current = right;
+        }
+        Some(&current.key)
+    }
+
+    /// Returns an iterator over the keys in ascending order.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter::new(self.root.as_deref())
+    }
+
+    /// Returns an iterator over the keys within `range`, in ascending order.
+    pub fn range<R: RangeBounds<T>>(&self, range: R) -> Range<'_, T> {
+        Range::new(self.root.as_deref(), range)
+    }
+}
+
+impl<T: Ord> Default for TreeAVL<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub type Iter<'a, T> = ordered_set::Iter<'a, Node<T>>;
+pub type Range<'a, T> = ordered_set::Range<'a, Node<T>>;
+
+impl<'a, T: Ord> IntoIterator for &'a TreeAVL<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T: Ord> OrderedSet<T> for TreeAVL<T> {
+    type Range<'a>
+        = Range<'a, T>
+    where
+        Self: 'a,
+        T: 'a;
+
+    fn insert(&mut self, key: T) -> bool {
+        let size = self.size;
+        TreeAVL::insert(self, key);
+        self.size != size
+    }
+
+    fn remove(&mut self, key: &T) -> bool {
+        if Self::remove_recursive(&mut self.root, key) {
+            self.size -= 1;
+            return true;
+        }
+        false
+    }
+
+    fn contains(&self, key: &T) -> bool {
+        self.contains_key(key)
+    }
+
+    fn first(&self) -> Option<&T> {
+        TreeAVL::first(self)
+    }
+
+    fn last(&self) -> Option<&T> {
+        TreeAVL::last(self)
+    }
+
+    fn range<R: RangeBounds<T>>(&self, range: R) -> Self::Range<'_> {
+        TreeAVL::range(self, range)
+    }
+
+    fn len(&self) -> usize {
+        self.size
     }
 }
 #[cfg(test)]
@@ -584,4 +730,44 @@ mod tests {
         x.insert(6);
         assert!(x.contains(6));
     }
+
+    #[test]
+    fn remove_updates_size() {
+        let mut x = TreeAVL::new();
+        x.insert(5);
+        x.insert(4);
+        x.remove(4);
+        x.remove(7);
+        assert_eq!(x.size(), 1);
+        x.clear();
+        assert_eq!(x.size(), 0);
+    }
+
+    #[test]
+    fn delete_with_balanced_sibling() {
+        let mut x = TreeAVL::new();
+        for key in [5, 3, 8, 7, 9] {
+            x.insert(key);
+        }
+        // Removing 3 leaves 5 right heavy, with 8 perfectly balanced.
+        x.remove(3);
+        assert_eq!(x.root.as_ref().unwrap().key, 8);
+        assert_eq!(x.root.as_ref().unwrap().left.as_ref().unwrap().key, 5);
+        assert_eq!(x.height(), 2);
+    }
+
+    #[test]
+    fn iter_in_order() {
+        let mut x = TreeAVL::new();
+        for key in [50, 20, 80, 10, 30, 70, 90] {
+            x.insert(key);
+        }
+        let keys: Vec<i32> = x.iter().copied().collect();
+        assert_eq!(keys, vec![10, 20, 30, 50, 70, 80, 90]);
+    }
+
+    #[test]
+    fn ordered_set_conformance() {
+        crate::safe_rust::ordered_set::conformance::run(TreeAVL::new);
+    }
 }
@@ -1 +1,657 @@
-todo!();
+/*
+Red-Black Tree
+ Every node is red or black and the tree keeps these invariants:
+ 1) The root is black.
+ 2) A red node never has a red child.
+ 3) Every path from a node down to its empty subtrees goes through the same number of black nodes.
+ Together they keep the longest path at most twice the shortest one, so the height is O(log n).
+ Compared to the AVL tree the balance is looser, lookups can be slightly slower but
+ insert and remove do at most 2 and 3 rotations.
+ Like the AVL tree, equal values are ignored.
+
+The nodes have no parent pointers, so the fixups are done while the recursion unwinds:
+-Insert: after inserting below a node, the node looks at its children and grandchildren for a
+ red child with a red child, and either recolors (red uncle) or rotates (black uncle).
+-Remove: removing a black node leaves its subtree one black node short ("double black").
+ The recursion returns whether the subtree got shorter, and the parent fixes it using the
+ sibling subtree, or passes the shortage up.
+*/
+
+use std::cmp::Ordering;
+use std::ops::RangeBounds;
+
+use crate::safe_rust::ordered_set::{self, OrderedSet, TreeNode};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Color {
+    Red,
+    Black,
+}
+
+type Link<T> = Option<Box<Node<T>>>;
+pub struct Node<T> {
+    left: Link<T>,
+    right: Link<T>,
+    color: Color,
+    key: T,
+}
+
+impl<T> TreeNode for Node<T> {
+    type Key = T;
+    fn key(&self) -> &T {
+        &self.key
+    }
+    fn left(&self) -> Option<&Self> {
+        self.left.as_deref()
+    }
+    fn right(&self) -> Option<&Self> {
+        self.right.as_deref()
+    }
+}
+
+pub struct TreeRedBlack<T: Ord> {
+    root: Link<T>,
+    size: usize,
+}
+
+impl<T: Ord> TreeRedBlack<T> {
+    pub fn new() -> Self {
+        TreeRedBlack {
+            root: None,
+            size: 0,
+        }
+    }
+
+    pub fn insert(&mut self, key: T) {
+        if Self::insert_recursive(&mut self.root, key) {
+            self.size += 1;
+        }
+        if let Some(root) = &mut self.root {
+            root.color = Color::Black;
+        }
+    }
+
+    fn insert_recursive(link: &mut Link<T>, key: T) -> bool {
+        let node = match link {
+            Some(node) => node,
+            None => {
+                *link = Some(Box::new(Node {
+                    left: None,
+                    right: None,
+                    color: Color::Red,
+                    key,
+                }));
+                return true;
+            }
+        };
+        let inserted = match key.cmp(&node.key) {
+            Ordering::Less => Self::insert_recursive(&mut node.left, key),
+            Ordering::Equal => false,
+            Ordering::Greater => Self::insert_recursive(&mut node.right, key),
+        };
+        if inserted {
+            Self::fix_red_red(node);
+        }
+        inserted
+    }
+
+    // Looks for a red child with a red child below `node` (the grandparent) and fixes it.
+    fn fix_red_red(node: &mut Box<Node<T>>) {
+        if Self::has_red_red(&node.left) {
+            if Self::is_red(&node.right) {
+                // Red uncle: push the red up, the grandparent may now conflict with its own parent.
+                Self::flip_colors(node);
+            } else {
+                // Black uncle: rotate the red child with the red grandchild up.
+                let left = node.left.as_mut().unwrap();
+                if Self::is_red(&left.right) {
+                    Self::rotate_left(left);
+                }
+                Self::rotate_right(node);
+                node.color = Color::Black;
+                node.right.as_mut().unwrap().color = Color::Red;
+            }
+        } else if Self::has_red_red(&node.right) {
+            if Self::is_red(&node.left) {
+                Self::flip_colors(node);
+            } else {
+                let right = node.right.as_mut().unwrap();
+                if Self::is_red(&right.left) {
+                    Self::rotate_right(right);
+                }
+                Self::rotate_left(node);
+                node.color = Color::Black;
+                node.left.as_mut().unwrap().color = Color::Red;
+            }
+        }
+    }
+
+    fn has_red_red(link: &Link<T>) -> bool {
+        match link {
+            Some(node) => {
+                node.color == Color::Red && (Self::is_red(&node.left) || Self::is_red(&node.right))
+            }
+            None => false,
+        }
+    }
+
+    fn is_red(link: &Link<T>) -> bool {
+        link.as_ref().is_some_and(|node| node.color == Color::Red)
+    }
+
+    fn flip_colors(node: &mut Box<Node<T>>) {
+        node.color = Color::Red;
+        node.left.as_mut().unwrap().color = Color::Black;
+        node.right.as_mut().unwrap().color = Color::Black;
+    }
+
+    // Same rotations as the AVL tree, colors are left to the caller.
+    fn rotate_left(node: &mut Box<Node<T>>) {
+        let mut right_child = node.right.take().unwrap();
+        node.right = right_child.left.take();
+        std::mem::swap(&mut right_child, node);
+        node.left = Some(right_child);
+    }
+
+    fn rotate_right(node: &mut Box<Node<T>>) {
+        let mut left_child = node.left.take().unwrap();
+        node.left = left_child.right.take();
+        std::mem::swap(&mut left_child, node);
+        node.right = Some(left_child);
+    }
+
+    pub fn remove(&mut self, key: T) {
+        self.remove_key(&key);
+    }
+
+    fn remove_key(&mut self, key: &T) -> bool {
+        let removed = Self::remove_recursive(&mut self.root, key).is_some();
+        if removed {
+            self.size -= 1;
+        }
+        if let Some(root) = &mut self.root {
+            root.color = Color::Black;
+        }
+        removed
+    }
+
+    // Returns `None` if the key wasn't found, otherwise whether the subtree lost one black node
+    // on every path (it is "double black") and the caller has to fix it.
+    fn remove_recursive(link: &mut Link<T>, key: &T) -> Option<bool> {
+        let node = link.as_mut()?;
+        match key.cmp(&node.key) {
+            Ordering::Less => {
+                let shorter = Self::remove_recursive(&mut node.left, key)?;
+                Some(shorter && Self::fix_left_shorter(node))
+            }
+            Ordering::Greater => {
+                let shorter = Self::remove_recursive(&mut node.right, key)?;
+                Some(shorter && Self::fix_right_shorter(node))
+            }
+            Ordering::Equal => {
+                if node.left.is_some() && node.right.is_some() {
+                    // Replace the key with its in-order successor and remove the successor instead.
+                    let (successor, shorter) = Self::remove_min(&mut node.right);
+                    node.key = successor;
+                    Some(shorter && Self::fix_right_shorter(node))
+                } else {
+                    Some(Self::remove_node(link).1)
+                }
+            }
+        }
+    }
+
+    // Removes the smallest node of a non-empty subtree, returning its key and whether the subtree got shorter.
+    fn remove_min(link: &mut Link<T>) -> (T, bool) {
+        let node = link.as_mut().unwrap();
+        if node.left.is_some() {
+            let (min, shorter) = Self::remove_min(&mut node.left);
+            return (min, shorter && Self::fix_left_shorter(node));
+        }
+        Self::remove_node(link)
+    }
+
+    // Removes a node with at most one child, returning its key and whether the subtree got shorter.
+    fn remove_node(link: &mut Link<T>) -> (T, bool) {
+        let mut node = link.take().unwrap();
+        // With a single child, that child must be a red leaf, otherwise the black heights would differ.
+        *link = node.left.take().or(node.right.take());
+        let shorter = match link {
+            _ if node.color == Color::Red => false,
+            Some(child) => {
+                child.color = Color::Black;
+                false
+            }
+            None => true,
+        };
+        (node.key, shorter)
+    }
+
+    // The left subtree of `node` is one black node short. Returns whether `node`'s subtree is still short.
+    fn fix_left_shorter(node: &mut Box<Node<T>>) -> bool {
+        if Self::is_red(&node.right) {
+            // Red sibling: rotate it up so the short side gets a black sibling and a red parent.
+            Self::rotate_left(node);
+            node.color = Color::Black;
+            let old_parent = node.left.as_mut().unwrap();
+            old_parent.color = Color::Red;
+            Self::fix_left_shorter(old_parent);
+            return false;
+        }
+
+        let sibling = node
+            .right
+            .as_mut()
+            .expect("fix_left_shorter: a black node always has a sibling");
+        if !Self::is_red(&sibling.left) && !Self::is_red(&sibling.right) {
+            // Black sibling with black children: remove one black from the sibling side too.
+            sibling.color = Color::Red;
+            if node.color == Color::Red {
+                node.color = Color::Black;
+                return false;
+            }
+            return true;
+        }
+
+        if !Self::is_red(&sibling.right) {
+            // Only the near nephew is red: rotate it to the far side first.
+            Self::rotate_right(sibling);
+            sibling.color = Color::Black;
+            sibling.right.as_mut().unwrap().color = Color::Red;
+        }
+        // The far nephew is red: rotating the sibling up adds a black node to the short side.
+        let color = node.color;
+        Self::rotate_left(node);
+        node.color = color;
+        node.left.as_mut().unwrap().color = Color::Black;
+        node.right.as_mut().unwrap().color = Color::Black;
+        false
+    }
+
+    // Mirror of `fix_left_shorter`.
+    fn fix_right_shorter(node: &mut Box<Node<T>>) -> bool {
+        if Self::is_red(&node.left) {
+            Self::rotate_right(node);
+            node.color = Color::Black;
+            let old_parent = node.right.as_mut().unwrap();
+            old_parent.color = Color::Red;
+            Self::fix_right_shorter(old_parent);
+            return false;
+        }
+
+        let sibling = node
+            .left
+            .as_mut()
+            .expect("fix_right_shorter: a black node always has a sibling");
+        if !Self::is_red(&sibling.left) && !Self::is_red(&sibling.right) {
+            sibling.color = Color::Red;
+            if node.color == Color::Red {
+                node.color = Color::Black;
+                return false;
+            }
+            return true;
+        }
+
+        if !Self::is_red(&sibling.left) {
+            Self::rotate_left(sibling);
+            sibling.color = Color::Black;
+            sibling.left.as_mut().unwrap().color = Color::Red;
+        }
+        let color = node.color;
+        Self::rotate_right(node);
+        node.color = color;
+        node.left.as_mut().unwrap().color = Color::Black;
+        node.right.as_mut().unwrap().color = Color::Black;
+        false
+    }
+
+    pub fn contains(&self, key: T) -> bool {
+        self.contains_key(&key)
+    }
+
+    fn contains_key(&self, key: &T) -> bool {
+        let mut current = &self.root;
+        while let Some(node) = current {
+            match key.cmp(&node.key) {
+                Ordering::Less => current = &node.left,
+                Ordering::Equal => return true,
+                Ordering::Greater => current = &node.right,
+            }
+        }
+        false
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn height(&self) -> usize {
+        fn height<T>(link: &Link<T>) -> usize {
+            match link {
+                Some(node) if node.left.is_some() || node.right.is_some() => {
+                    1 + std::cmp::max(height(&node.left), height(&node.right))
+                }
+                _ => 0,
+            }
+        }
+        height(&self.root)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    pub fn clear(&mut self) {
+        self.root = None;
+        self.size = 0;
+    }
+
+    pub fn first(&self) -> Option<&T> {
+        let mut current = self.root.as_ref()?;
+        while let Some(left) = &current.left {
+            current = left;
+        }
+        Some(&current.key)
+    }
+
+    pub fn last(&self) -> Option<&T> {
+        let mut current = self.root.as_ref()?;
+        while let Some(right) = &current.right {
+            current = right;
+        }
+        Some(&current.key)
+    }
+
+    /// Returns an iterator over the keys in ascending order.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter::new(self.root.as_deref())
+    }
+
+    /// Returns an iterator over the keys within `range`, in ascending order.
+    pub fn range<R: RangeBounds<T>>(&self, range: R) -> Range<'_, T> {
+        Range::new(self.root.as_deref(), range)
+    }
+
+    /// Checks every red-black invariant, the ordering of the keys and the size.
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if Self::is_red(&self.root) {
+            return Err("the root is red");
+        }
+        let mut count = 0;
+        Self::validate_recursive(&self.root, None, None, &mut count)?;
+        if count != self.size {
+            return Err("size doesn't match the number of nodes");
+        }
+        Ok(())
+    }
+
+    // Returns the black height of the subtree.
+    fn validate_recursive(
+        link: &Link<T>,
+        lower: Option<&T>,
+        upper: Option<&T>,
+        count: &mut usize,
+    ) -> Result<usize, &'static str> {
+        let node = match link {
+            Some(node) => node,
+            None => return Ok(1),
+        };
+        *count += 1;
+        if lower.is_some_and(|lower| node.key <= *lower)
+            || upper.is_some_and(|upper| node.key >= *upper)
+        {
+            return Err("keys are out of order");
+        }
+        if node.color == Color::Red && (Self::is_red(&node.left) || Self::is_red(&node.right)) {
+            return Err("a red node has a red child");
+        }
+        let left_black_height =
+            Self::validate_recursive(&node.left, lower, Some(&node.key), count)?;
+        let right_black_height =
+            Self::validate_recursive(&node.right, Some(&node.key), upper, count)?;
+        if left_black_height != right_black_height {
+            return Err("black heights differ");
+        }
+        Ok(left_black_height + usize::from(node.color == Color::Black))
+    }
+}
+
+impl<T: Ord> Default for TreeRedBlack<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub type Iter<'a, T> = ordered_set::Iter<'a, Node<T>>;
+pub type Range<'a, T> = ordered_set::Range<'a, Node<T>>;
+
+impl<'a, T: Ord> IntoIterator for &'a TreeRedBlack<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T: Ord> OrderedSet<T> for TreeRedBlack<T> {
+    type Range<'a>
+        = Range<'a, T>
+    where
+        Self: 'a,
+        T: 'a;
+
+    fn insert(&mut self, key: T) -> bool {
+        let size = self.size;
+        TreeRedBlack::insert(self, key);
+        self.size != size
+    }
+
+    fn remove(&mut self, key: &T) -> bool {
+        self.remove_key(key)
+    }
+
+    fn contains(&self, key: &T) -> bool {
+        self.contains_key(key)
+    }
+
+    fn first(&self) -> Option<&T> {
+        TreeRedBlack::first(self)
+    }
+
+    fn last(&self) -> Option<&T> {
+        TreeRedBlack::last(self)
+    }
+
+    fn range<R: RangeBounds<T>>(&self, range: R) -> Self::Range<'_> {
+        TreeRedBlack::range(self, range)
+    }
+
+    fn len(&self) -> usize {
+        self.size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::safe_rust::test_utils::scrambled;
+
+    #[test]
+    fn rotate_right_three_elements() {
+        let mut x: TreeRedBlack<i32> = TreeRedBlack::new();
+        x.insert(10);
+        x.insert(7);
+        x.insert(5);
+        let root = x.root.as_ref().unwrap();
+        assert_eq!(root.key, 7);
+        assert_eq!(root.color, Color::Black);
+        assert_eq!(root.left.as_ref().unwrap().key, 5);
+        assert_eq!(root.left.as_ref().unwrap().color, Color::Red);
+        assert_eq!(root.right.as_ref().unwrap().key, 10);
+        assert_eq!(root.right.as_ref().unwrap().color, Color::Red);
+    }
+
+    #[test]
+    fn rotate_left_then_right_three_elements() {
+        let mut x: TreeRedBlack<i32> = TreeRedBlack::new();
+        x.insert(10);
+        x.insert(5);
+        x.insert(7);
+        assert_eq!(x.root.as_ref().unwrap().key, 7);
+        assert_eq!(x.root.as_ref().unwrap().left.as_ref().unwrap().key, 5);
+        assert_eq!(x.root.as_ref().unwrap().right.as_ref().unwrap().key, 10);
+    }
+
+    #[test]
+    fn recolor_red_uncle() {
+        let mut x: TreeRedBlack<i32> = TreeRedBlack::new();
+        x.insert(7);
+        x.insert(5);
+        x.insert(10);
+        x.insert(1);
+        let root = x.root.as_ref().unwrap();
+        assert_eq!(root.key, 7);
+        assert_eq!(root.left.as_ref().unwrap().color, Color::Black);
+        assert_eq!(root.right.as_ref().unwrap().color, Color::Black);
+        assert_eq!(
+            root.left.as_ref().unwrap().left.as_ref().unwrap().color,
+            Color::Red
+        );
+        assert_eq!(x.validate(), Ok(()));
+    }
+
+    #[test]
+    fn insert_ascending_stays_valid() {
+        let mut x = TreeRedBlack::new();
+        for key in 0..1000 {
+            x.insert(key);
+            assert_eq!(x.validate(), Ok(()));
+        }
+        assert_eq!(x.size(), 1000);
+        // A red-black tree with n nodes has height at most 2 * log2(n + 1).
+        assert!(x.height() <= 20);
+    }
+
+    #[test]
+    fn insert_duplicates_ignored() {
+        let mut x = TreeRedBlack::new();
+        x.insert(5);
+        x.insert(5);
+        assert_eq!(x.size(), 1);
+    }
+
+    #[test]
+    fn contains() {
+        let mut x = TreeRedBlack::new();
+        x.insert(5);
+        x.insert(4);
+        x.insert(6);
+        assert!(x.contains(6));
+        assert!(!x.contains(7));
+    }
+
+    #[test]
+    fn iter_in_order() {
+        let mut x = TreeRedBlack::new();
+        for key in scrambled(100) {
+            x.insert(key);
+        }
+        let keys: Vec<i32> = x.iter().copied().collect();
+        assert_eq!(keys, (0..100).collect::<Vec<i32>>());
+        assert_eq!((&x).into_iter().count(), 100);
+    }
+
+    #[test]
+    fn delete_leaf() {
+        let mut x = TreeRedBlack::new();
+        x.insert(5);
+        x.insert(4);
+        x.insert(6);
+        x.remove(4);
+        assert!(x.root.as_ref().unwrap().left.is_none());
+        assert_eq!(x.validate(), Ok(()));
+    }
+
+    #[test]
+    fn delete_root_with_two_children() {
+        let mut x = TreeRedBlack::new();
+        x.insert(5);
+        x.insert(4);
+        x.insert(6);
+        x.remove(5);
+        assert_eq!(x.root.as_ref().unwrap().key, 6);
+        assert_eq!(x.size(), 2);
+        assert_eq!(x.validate(), Ok(()));
+    }
+
+    #[test]
+    fn delete_black_leaf_double_black() {
+        let mut x = TreeRedBlack::new();
+        for key in 1..=7 {
+            x.insert(key);
+        }
+        assert_eq!(x.validate(), Ok(()));
+        x.remove(1);
+        assert_eq!(x.validate(), Ok(()));
+        x.remove(3);
+        assert_eq!(x.validate(), Ok(()));
+        x.remove(2);
+        assert_eq!(x.validate(), Ok(()));
+        let keys: Vec<i32> = x.iter().copied().collect();
+        assert_eq!(keys, vec![4, 5, 6, 7]);
+    }
+
+    #[test]
+    fn delete_non_existent() {
+        let mut x = TreeRedBlack::new();
+        x.insert(5);
+        x.remove(7);
+        assert_eq!(x.size(), 1);
+        let mut empty: TreeRedBlack<i32> = TreeRedBlack::new();
+        empty.remove(1);
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn delete_everything_stays_valid() {
+        let mut x = TreeRedBlack::new();
+        for key in scrambled(500) {
+            x.insert(key);
+        }
+        for (removed, key) in scrambled(500).into_iter().rev().enumerate() {
+            x.remove(key);
+            assert!(!x.contains(key));
+            assert_eq!(x.size(), 499 - removed);
+            assert_eq!(x.validate(), Ok(()));
+        }
+        assert!(x.is_empty());
+    }
+
+    #[test]
+    fn delete_ascending_stays_valid() {
+        let mut x = TreeRedBlack::new();
+        for key in 0..300 {
+            x.insert(key);
+        }
+        for key in 0..300 {
+            x.remove(key);
+            assert_eq!(x.validate(), Ok(()));
+        }
+        assert!(x.is_empty());
+    }
+
+    #[test]
+    fn clear() {
+        let mut x = TreeRedBlack::new();
+        x.insert(1);
+        x.insert(2);
+        x.clear();
+        assert!(x.is_empty());
+        assert_eq!(x.size(), 0);
+    }
+
+    #[test]
+    fn ordered_set_conformance() {
+        crate::safe_rust::ordered_set::conformance::run(TreeRedBlack::new);
+    }
+}
@@ -0,0 +1,370 @@
+/*
+Trie
+ Map from strings to values where every edge is one byte of the key, so all the keys sharing
+ a prefix share the path for it. Lookups cost O(key length) no matter how many keys are stored,
+ and every key starting with a prefix sits in the subtree at the end of that prefix's path.
+
+ Keys are walked as UTF-8 bytes. Children are kept sorted by byte, which makes iteration
+ lexicographic (byte order of UTF-8 is the same as char order).
+
+ Removing a key prunes the nodes left without a value and without children.
+ See radix_tree.rs for the compressed version, where chains of single child nodes become one edge.
+*/
+
+type Edge<V> = (u8, Box<Node<V>>);
+
+struct Node<V> {
+    value: Option<V>,
+    // Sorted by byte.
+    children: Vec<Edge<V>>,
+}
+
+impl<V> Node<V> {
+    fn new() -> Self {
+        Node {
+            value: None,
+            children: Vec::new(),
+        }
+    }
+
+    fn child(&self, byte: u8) -> Option<&Node<V>> {
+        let index = self
+            .children
+            .binary_search_by_key(&byte, |(edge, _)| *edge)
+            .ok()?;
+        Some(&self.children[index].1)
+    }
+
+    fn child_mut(&mut self, byte: u8) -> Option<&mut Node<V>> {
+        let index = self
+            .children
+            .binary_search_by_key(&byte, |(edge, _)| *edge)
+            .ok()?;
+        Some(&mut self.children[index].1)
+    }
+
+    fn remove(&mut self, key: &[u8]) -> Option<V> {
+        let (&byte, rest) = match key.split_first() {
+            Some(split) => split,
+            None => return self.value.take(),
+        };
+        let index = self
+            .children
+            .binary_search_by_key(&byte, |(edge, _)| *edge)
+            .ok()?;
+        let child = &mut self.children[index].1;
+        let removed = child.remove(rest);
+        if child.value.is_none() && child.children.is_empty() {
+            self.children.remove(index);
+        }
+        removed
+    }
+}
+
+pub struct Trie<V> {
+    root: Node<V>,
+    len: usize,
+}
+
+impl<V> Trie<V> {
+    pub fn new() -> Self {
+        Trie {
+            root: Node::new(),
+            len: 0,
+        }
+    }
+
+    /// Inserts a key-value pair, returning the previous value if the key was already present.
+    pub fn insert(&mut self, key: &str, value: V) -> Option<V> {
+        let mut node = &mut self.root;
+        for &byte in key.as_bytes() {
+            let index = match node.children.binary_search_by_key(&byte, |(edge, _)| *edge) {
+                Ok(index) => index,
+                Err(index) => {
+                    node.children.insert(index, (byte, Box::new(Node::new())));
+                    index
+                }
+            };
+            node = &mut node.children[index].1;
+        }
+        let replaced = node.value.replace(value);
+        if replaced.is_none() {
+            self.len += 1;
+        }
+        replaced
+    }
+
+    // Node at the end of the path for `key`, if the path exists.
+    fn find(&self, key: &str) -> Option<&Node<V>> {
+        key.bytes()
+            .try_fold(&self.root, |node, byte| node.child(byte))
+    }
+
+    pub fn get(&self, key: &str) -> Option<&V> {
+        self.find(key)?.value.as_ref()
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut V> {
+        let node = key
+            .bytes()
+            .try_fold(&mut self.root, |node, byte| node.child_mut(byte))?;
+        node.value.as_mut()
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Removes a key, returning its value if it was present.
+    pub fn remove(&mut self, key: &str) -> Option<V> {
+        let removed = self.root.remove(key.as_bytes());
+        if removed.is_some() {
+            self.len -= 1;
+        }
+        removed
+    }
+
+    /// Returns an iterator over the entries whose key starts with `prefix`, in lexicographic order.
+    pub fn starts_with(&self, prefix: &str) -> Iter<'_, V> {
+        match self.find(prefix) {
+            Some(node) => Iter::new(node, prefix.as_bytes().to_vec()),
+            None => Iter::empty(),
+        }
+    }
+
+    /// Longest key that is a prefix of `text`, with its value.
+    pub fn longest_prefix<'t>(&self, text: &'t str) -> Option<(&'t str, &V)> {
+        let mut node = &self.root;
+        let mut longest = node.value.as_ref().map(|value| (0, value));
+        for (depth, byte) in text.bytes().enumerate() {
+            node = match node.child(byte) {
+                Some(child) => child,
+                None => break,
+            };
+            if let Some(value) = &node.value {
+                longest = Some((depth + 1, value));
+            }
+        }
+        // Keys are whole strings, so the match always ends on a char boundary.
+        longest.map(|(len, value)| (&text[..len], value))
+    }
+
+    /// Returns an iterator over all the entries in lexicographic order.
+    pub fn iter(&self) -> Iter<'_, V> {
+        Iter::new(&self.root, Vec::new())
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.root = Node::new();
+        self.len = 0;
+    }
+}
+
+impl<V> Default for Trie<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Pre-order iterator over a subtree, rebuilding the keys in a shared buffer.
+pub struct Iter<'a, V> {
+    // Children left to visit, one level per node on the current path.
+    stack: Vec<std::slice::Iter<'a, Edge<V>>>,
+    key: Vec<u8>,
+    // Value of the subtree root, yielded first.
+    root_value: Option<&'a V>,
+}
+
+impl<'a, V> Iter<'a, V> {
+    fn new(root: &'a Node<V>, key: Vec<u8>) -> Self {
+        Iter {
+            stack: vec![root.children.iter()],
+            key,
+            root_value: root.value.as_ref(),
+        }
+    }
+
+    fn empty() -> Self {
+        Iter {
+            stack: Vec::new(),
+            key: Vec::new(),
+            root_value: None,
+        }
+    }
+
+    fn current_key(&self) -> String {
+        String::from_utf8(self.key.clone()).expect("Trie: a value ends a valid UTF-8 key")
+    }
+}
+
+impl<'a, V> Iterator for Iter<'a, V> {
+    type Item = (String, &'a V);
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(value) = self.root_value.take() {
+            return Some((self.current_key(), value));
+        }
+        loop {
+            match self.stack.last_mut()?.next() {
+                Some((byte, child)) => {
+                    self.key.push(*byte);
+                    self.stack.push(child.children.iter());
+                    if let Some(value) = &child.value {
+                        return Some((self.current_key(), value));
+                    }
+                }
+                None => {
+                    self.stack.pop();
+                    // The bottom level is the subtree root, whose key is the prefix.
+                    if !self.stack.is_empty() {
+                        self.key.pop();
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<'a, V> IntoIterator for &'a Trie<V> {
+    type Item = (String, &'a V);
+    type IntoIter = Iter<'a, V>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys<V>(iter: Iter<'_, V>) -> Vec<String> {
+        iter.map(|(key, _)| key).collect()
+    }
+
+    fn count_nodes<V>(node: &Node<V>) -> usize {
+        1 + node
+            .children
+            .iter()
+            .map(|(_, child)| count_nodes(child))
+            .sum::<usize>()
+    }
+
+    #[test]
+    fn insert_and_get() {
+        let mut trie = Trie::new();
+        assert_eq!(trie.insert("car", 1), None);
+        assert_eq!(trie.insert("cart", 2), None);
+        assert_eq!(trie.insert("car", 3), Some(1));
+        assert_eq!(trie.len(), 2);
+        assert_eq!(trie.get("car"), Some(&3));
+        assert_eq!(trie.get("cart"), Some(&2));
+        assert_eq!(trie.get("ca"), None);
+        assert_eq!(trie.get("carts"), None);
+        assert!(trie.contains_key("cart"));
+    }
+
+    #[test]
+    fn empty_key() {
+        let mut trie = Trie::new();
+        trie.insert("", 0);
+        assert_eq!(trie.get(""), Some(&0));
+        assert_eq!(keys(trie.iter()), vec![""]);
+    }
+
+    #[test]
+    fn get_mut() {
+        let mut trie = Trie::new();
+        trie.insert("a", 1);
+        *trie.get_mut("a").unwrap() += 1;
+        assert_eq!(trie.get("a"), Some(&2));
+        assert_eq!(trie.get_mut("b"), None);
+    }
+
+    #[test]
+    fn remove_prunes_nodes() {
+        let mut trie = Trie::new();
+        trie.insert("car", 1);
+        trie.insert("cart", 2);
+        trie.insert("care", 3);
+        let nodes = count_nodes(&trie.root);
+
+        assert_eq!(trie.remove("cart"), Some(2));
+        assert_eq!(count_nodes(&trie.root), nodes - 1);
+        // "car" still has a child, its node stays.
+        assert_eq!(trie.remove("car"), Some(1));
+        assert_eq!(count_nodes(&trie.root), nodes - 1);
+        assert_eq!(trie.remove("car"), None);
+        assert_eq!(trie.remove("ca"), None);
+
+        assert_eq!(trie.remove("care"), Some(3));
+        assert_eq!(count_nodes(&trie.root), 1);
+        assert!(trie.is_empty());
+    }
+
+    #[test]
+    fn starts_with() {
+        let mut trie = Trie::new();
+        for word in ["tea", "ten", "to", "ted", "inn", "tea party", "i"] {
+            trie.insert(word, word.len());
+        }
+        assert_eq!(
+            keys(trie.starts_with("te")),
+            vec!["tea", "tea party", "ted", "ten"]
+        );
+        assert_eq!(keys(trie.starts_with("tea")), vec!["tea", "tea party"]);
+        assert_eq!(keys(trie.starts_with("x")), Vec::<String>::new());
+        assert_eq!(keys(trie.starts_with("")).len(), 7);
+    }
+
+    #[test]
+    fn iter_in_lexicographic_order() {
+        let mut trie = Trie::new();
+        let mut words = vec!["banana", "apple", "band", "ban", "ápice", "zebra", "b"];
+        for word in &words {
+            trie.insert(word, ());
+        }
+        words.sort();
+        assert_eq!(keys((&trie).into_iter()), words);
+    }
+
+    #[test]
+    fn longest_prefix() {
+        let mut trie = Trie::new();
+        trie.insert("/", "root");
+        trie.insert("/api", "api");
+        trie.insert("/api/users", "users");
+        assert_eq!(
+            trie.longest_prefix("/api/users/42"),
+            Some(("/api/users", &"users"))
+        );
+        assert_eq!(trie.longest_prefix("/api/items"), Some(("/api", &"api")));
+        assert_eq!(trie.longest_prefix("/static"), Some(("/", &"root")));
+        assert_eq!(trie.longest_prefix("api"), None);
+    }
+
+    #[test]
+    fn multibyte_keys() {
+        let mut trie = Trie::new();
+        trie.insert("né", 1);
+        trie.insert("nè", 2);
+        assert_eq!(trie.get("né"), Some(&1));
+        assert_eq!(keys(trie.starts_with("n")), vec!["nè", "né"]);
+        assert_eq!(trie.longest_prefix("nèfle"), Some(("nè", &2)));
+    }
+
+    #[test]
+    fn clear() {
+        let mut trie = Trie::new();
+        trie.insert("a", ());
+        trie.clear();
+        assert!(trie.is_empty());
+        assert_eq!(trie.iter().next(), None);
+    }
+}
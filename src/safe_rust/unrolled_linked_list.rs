@@ -0,0 +1,603 @@
+/*
+Unrolled Linked List
+ A doubly linked list where every node holds up to CAP elements in a small array instead of a
+ single one. One allocation covers many elements and a walk reads them from contiguous memory,
+ so it's far more cache friendly than one Box per element, while inserting in the middle still
+ only shifts the elements of one node.
+
+ Nodes live in an arena (a Vec) and link to each other by index, like in skip_list.rs.
+ -insert into a full node splits it in two halves first.
+ -remove (and pop) from a node that drops below half full merges it with its next or previous
+  node if both fit in one, and otherwise takes elements from that neighbour until both are at
+  least half full.
+ Only the first and last nodes can be less than half full, so finding position i skips over
+ whole nodes of at least CAP / 2 elements: O(n / CAP).
+*/
+
+type Link = Option<usize>;
+struct Node<T> {
+    // Never empty, at most CAP elements.
+    items: Vec<T>,
+    previous: Link,
+    next: Link,
+}
+
+pub struct UnrolledLinkedList<T, const CAP: usize = 16> {
+    // Arena of nodes, `None` for free slots.
+    nodes: Vec<Option<Node<T>>>,
+    free: Vec<usize>,
+    head: Link,
+    tail: Link,
+    len: usize,
+}
+
+impl<T, const CAP: usize> UnrolledLinkedList<T, CAP> {
+    const VALID_CAPACITY: () = assert!(
+        CAP >= 2,
+        "an unrolled linked list node needs room for at least two elements"
+    );
+
+    /// Creates an empty list, the node capacity comes from the type, e.g. `UnrolledLinkedList::<u64, 64>::new()`.
+    pub fn new() -> Self {
+        let () = Self::VALID_CAPACITY;
+        UnrolledLinkedList {
+            nodes: Vec::new(),
+            free: Vec::new(),
+            head: None,
+            tail: None,
+            len: 0,
+        }
+    }
+
+    fn node(&self, index: usize) -> &Node<T> {
+        self.nodes[index]
+            .as_ref()
+            .expect("UnrolledLinkedList: a link points to a free slot")
+    }
+
+    fn node_mut(&mut self, index: usize) -> &mut Node<T> {
+        self.nodes[index]
+            .as_mut()
+            .expect("UnrolledLinkedList: a link points to a free slot")
+    }
+
+    // Links a new node holding `items` after `previous`, or at the front if it's None.
+    fn insert_node_after(&mut self, previous: Link, items: Vec<T>) -> usize {
+        let next = match previous {
+            Some(previous) => self.node(previous).next,
+            None => self.head,
+        };
+        let node = Node {
+            items,
+            previous,
+            next,
+        };
+        let index = match self.free.pop() {
+            Some(index) => {
+                self.nodes[index] = Some(node);
+                index
+            }
+            None => {
+                self.nodes.push(Some(node));
+                self.nodes.len() - 1
+            }
+        };
+        match next {
+            Some(next) => self.node_mut(next).previous = Some(index),
+            None => self.tail = Some(index),
+        }
+        match previous {
+            Some(previous) => self.node_mut(previous).next = Some(index),
+            None => self.head = Some(index),
+        }
+        index
+    }
+
+    // Unlinks the node at `index` and frees its slot.
+    fn remove_node(&mut self, index: usize) -> Node<T> {
+        let node = self.nodes[index]
+            .take()
+            .expect("UnrolledLinkedList: removed a free slot");
+        self.free.push(index);
+        match node.next {
+            Some(next) => self.node_mut(next).previous = node.previous,
+            None => self.tail = node.previous,
+        }
+        match node.previous {
+            Some(previous) => self.node_mut(previous).next = node.next,
+            None => self.head = node.next,
+        }
+        node
+    }
+
+    // Node holding position `index` (which must be less than len) and the offset in it,
+    // walking from whichever end is closer.
+    fn locate(&self, index: usize) -> (usize, usize) {
+        if index < self.len / 2 {
+            let mut current = self.head.unwrap();
+            let mut offset = index;
+            while offset >= self.node(current).items.len() {
+                offset -= self.node(current).items.len();
+                current = self.node(current).next.unwrap();
+            }
+            (current, offset)
+        } else {
+            let mut current = self.tail.unwrap();
+            // Elements from `index` to the end that are in nodes before `current`.
+            let mut from_end = self.len - index;
+            while from_end > self.node(current).items.len() {
+                from_end -= self.node(current).items.len();
+                current = self.node(current).previous.unwrap();
+            }
+            (current, self.node(current).items.len() - from_end)
+        }
+    }
+
+    pub fn push_front(&mut self, data: T) {
+        let head = match self.head {
+            Some(head) if self.node(head).items.len() < CAP => head,
+            _ => self.insert_node_after(None, Vec::with_capacity(CAP)),
+        };
+        self.node_mut(head).items.insert(0, data);
+        self.len += 1;
+    }
+
+    pub fn push_back(&mut self, data: T) {
+        let tail = match self.tail {
+            Some(tail) if self.node(tail).items.len() < CAP => tail,
+            tail => self.insert_node_after(tail, Vec::with_capacity(CAP)),
+        };
+        self.node_mut(tail).items.push(data);
+        self.len += 1;
+    }
+
+    // Restores the fill of `node` after an element was removed from it. An empty node is
+    // unlinked, one below half full is merged with a neighbour if both fit in one node, and
+    // otherwise shares the elements of the next (or previous) neighbour evenly with it.
+    fn rebalance(&mut self, node: usize) {
+        let len = self.node(node).items.len();
+        if len == 0 {
+            self.remove_node(node);
+            return;
+        }
+        if len >= CAP / 2 {
+            return;
+        }
+        let (previous, next) = (self.node(node).previous, self.node(node).next);
+        if let Some(next) = next {
+            if len + self.node(next).items.len() <= CAP {
+                return self.merge(node, next);
+            }
+        }
+        if let Some(previous) = previous {
+            if self.node(previous).items.len() + len <= CAP {
+                return self.merge(previous, node);
+            }
+        }
+        // Neither fits, so both neighbours are more than half full.
+        match (previous, next) {
+            (_, Some(next)) => self.redistribute(node, next),
+            (Some(previous), None) => self.redistribute(previous, node),
+            (None, None) => {}
+        }
+    }
+
+    // Moves the elements of `second` to the end of `first`, the node before it, and unlinks `second`.
+    fn merge(&mut self, first: usize, second: usize) {
+        let second = self.remove_node(second);
+        self.node_mut(first).items.extend(second.items);
+    }
+
+    // Moves elements between `first` and `second`, the node after it, until they hold half each.
+    fn redistribute(&mut self, first: usize, second: usize) {
+        let mut second_items = std::mem::take(&mut self.node_mut(second).items);
+        let first_items = &mut self.node_mut(first).items;
+        let half = (first_items.len() + second_items.len()) / 2;
+        if first_items.len() < half {
+            first_items.extend(second_items.drain(..half - first_items.len()));
+        } else {
+            second_items.splice(0..0, first_items.drain(half..));
+        }
+        self.node_mut(second).items = second_items;
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        let head = self.head?;
+        let data = self.node_mut(head).items.remove(0);
+        self.rebalance(head);
+        self.len -= 1;
+        Some(data)
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        let tail = self.tail?;
+        let data = self.node_mut(tail).items.pop()?;
+        self.rebalance(tail);
+        self.len -= 1;
+        Some(data)
+    }
+
+    /// Inserts `data` so that it ends up at `index`, shifting the elements after it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index > len`.
+    pub fn insert(&mut self, index: usize, data: T) {
+        assert!(
+            index <= self.len,
+            "UnrolledLinkedList::insert: index {} out of bounds for length {}",
+            index,
+            self.len
+        );
+        if index == self.len {
+            return self.push_back(data);
+        }
+        let (mut node, mut offset) = self.locate(index);
+        if self.node(node).items.len() == CAP {
+            // Split the full node, the second half goes to a new node right after it.
+            let second_half = self.node_mut(node).items.split_off(CAP / 2);
+            let mut items = Vec::with_capacity(CAP);
+            items.extend(second_half);
+            let new_node = self.insert_node_after(Some(node), items);
+            if offset > CAP / 2 {
+                node = new_node;
+                offset -= CAP / 2;
+            }
+        }
+        self.node_mut(node).items.insert(offset, data);
+        self.len += 1;
+    }
+
+    /// Removes the element at `index`, None if `index >= len`.
+    pub fn remove(&mut self, index: usize) -> Option<T> {
+        if index >= self.len {
+            return None;
+        }
+        let (node, offset) = self.locate(index);
+        let data = self.node_mut(node).items.remove(offset);
+        self.rebalance(node);
+        self.len -= 1;
+        Some(data)
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index >= self.len {
+            return None;
+        }
+        let (node, offset) = self.locate(index);
+        Some(&self.node(node).items[offset])
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        if index >= self.len {
+            return None;
+        }
+        let (node, offset) = self.locate(index);
+        Some(&mut self.node_mut(node).items[offset])
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.head.and_then(|head| self.node(head).items.first())
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        self.tail.and_then(|tail| self.node(tail).items.last())
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.free.clear();
+        self.head = None;
+        self.tail = None;
+        self.len = 0;
+    }
+
+    /// Returns an iterator over the elements from front to back.
+    pub fn iter(&self) -> Iter<'_, T, CAP> {
+        Iter {
+            list: self,
+            items: [].iter(),
+            next_node: self.head,
+            remaining: self.len,
+        }
+    }
+}
+
+impl<T, const CAP: usize> Default for UnrolledLinkedList<T, CAP> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const CAP: usize> FromIterator<T> for UnrolledLinkedList<T, CAP> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = Self::new();
+        for item in iter {
+            list.push_back(item);
+        }
+        list
+    }
+}
+
+pub struct Iter<'a, T, const CAP: usize> {
+    list: &'a UnrolledLinkedList<T, CAP>,
+    // Elements left in the current node.
+    items: std::slice::Iter<'a, T>,
+    next_node: Link,
+    remaining: usize,
+}
+
+impl<'a, T, const CAP: usize> Iterator for Iter<'a, T, CAP> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.items.next() {
+                self.remaining -= 1;
+                return Some(item);
+            }
+            let node = self.list.node(self.next_node?);
+            self.items = node.items.iter();
+            self.next_node = node.next;
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T, const CAP: usize> ExactSizeIterator for Iter<'_, T, CAP> {}
+
+impl<'a, T, const CAP: usize> IntoIterator for &'a UnrolledLinkedList<T, CAP> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T, CAP>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+pub struct IntoIter<T, const CAP: usize>(UnrolledLinkedList<T, CAP>);
+
+impl<T, const CAP: usize> Iterator for IntoIter<T, CAP> {
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.len, Some(self.0.len))
+    }
+}
+
+impl<T, const CAP: usize> DoubleEndedIterator for IntoIter<T, CAP> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.pop_back()
+    }
+}
+
+impl<T, const CAP: usize> ExactSizeIterator for IntoIter<T, CAP> {}
+
+impl<T, const CAP: usize> IntoIterator for UnrolledLinkedList<T, CAP> {
+    type Item = T;
+    type IntoIter = IntoIter<T, CAP>;
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::safe_rust::test_utils::scrambled;
+
+    // Checks the links in both directions, that no node is empty or over capacity, that every
+    // node but the first and last is at least half full, and that the nodes add up to `len`.
+    fn check<T, const CAP: usize>(list: &UnrolledLinkedList<T, CAP>) {
+        let mut previous = None;
+        let mut current = list.head;
+        let mut len = 0;
+        while let Some(index) = current {
+            let node = list.node(index);
+            assert_eq!(node.previous, previous);
+            assert!(!node.items.is_empty() && node.items.len() <= CAP);
+            if current != list.head && current != list.tail {
+                assert!(node.items.len() >= CAP / 2, "sparse node in the middle");
+            }
+            len += node.items.len();
+            previous = current;
+            current = node.next;
+        }
+        assert_eq!(list.tail, previous);
+        assert_eq!(len, list.len);
+        let used = list.nodes.iter().filter(|node| node.is_some()).count();
+        assert_eq!(used + list.free.len(), list.nodes.len());
+    }
+
+    fn to_vec<const CAP: usize>(list: &UnrolledLinkedList<usize, CAP>) -> Vec<usize> {
+        list.iter().copied().collect()
+    }
+
+    #[test]
+    fn push_and_pop_both_ends() {
+        let mut list: UnrolledLinkedList<usize, 4> = UnrolledLinkedList::new();
+        for value in 0..10 {
+            list.push_back(value);
+            list.push_front(100 + value);
+        }
+        check(&list);
+        assert_eq!(list.len(), 20);
+        assert_eq!((list.front(), list.back()), (Some(&109), Some(&9)));
+        assert_eq!(list.pop_front(), Some(109));
+        assert_eq!(list.pop_back(), Some(9));
+        check(&list);
+        let mut drained = Vec::new();
+        while let Some(value) = list.pop_back() {
+            drained.push(value);
+        }
+        assert_eq!(drained.len(), 18);
+        assert_eq!(drained[0], 8);
+        assert!(list.is_empty());
+        assert_eq!((list.front(), list.back()), (None, None));
+        check(&list);
+    }
+
+    #[test]
+    fn nodes_fill_up() {
+        let list: UnrolledLinkedList<usize, 8> = (0..20).collect();
+        check(&list);
+        assert_eq!(list.nodes.len(), 3);
+        assert_eq!(to_vec(&list), (0..20).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn insert_splits_full_nodes() {
+        let mut list: UnrolledLinkedList<usize, 4> = (0..4).collect();
+        list.insert(1, 10);
+        check(&list);
+        assert_eq!(list.nodes.len(), 2);
+        list.insert(4, 11);
+        list.insert(0, 12);
+        list.insert(7, 13);
+        check(&list);
+        assert_eq!(to_vec(&list), vec![12, 0, 10, 1, 2, 11, 3, 13]);
+    }
+
+    #[test]
+    fn insert_matches_vec() {
+        let mut list: UnrolledLinkedList<usize, 5> = UnrolledLinkedList::new();
+        let mut expected = Vec::new();
+        for (step, value) in scrambled(300).into_iter().enumerate() {
+            let index = value % (step + 1);
+            list.insert(index, value);
+            expected.insert(index, value);
+        }
+        check(&list);
+        assert_eq!(to_vec(&list), expected);
+        for (index, value) in expected.iter().enumerate() {
+            assert_eq!(list.get(index), Some(value));
+        }
+        assert_eq!(list.get(300), None);
+    }
+
+    #[test]
+    fn remove_merges_nodes() {
+        // Two nodes: [0, 1, 2, 3] and [4, 5].
+        let mut list: UnrolledLinkedList<usize, 4> = (0..6).collect();
+        assert_eq!(list.remove(1), Some(1));
+        assert_eq!(list.remove(1), Some(2));
+        assert!(list.free.is_empty());
+        // The first node drops below half full and takes in the next one.
+        assert_eq!(list.remove(1), Some(3));
+        check(&list);
+        assert_eq!(list.free.len(), 1);
+        assert_eq!(to_vec(&list), vec![0, 4, 5]);
+        assert_eq!(list.remove(3), None);
+    }
+
+    #[test]
+    fn remove_merges_with_previous_node() {
+        // Three nodes: [2, 3], [4, 5, 6, 7] and [8, 9, 10, 11].
+        let mut list: UnrolledLinkedList<usize, 4> = (0..12).collect();
+        list.pop_front();
+        list.pop_front();
+        for value in [4, 5, 6] {
+            assert_eq!(list.remove(2), Some(value));
+        }
+        // The next node is full, so the middle one went into the previous one.
+        check(&list);
+        assert_eq!(list.nodes.len() - list.free.len(), 2);
+        assert_eq!(list.node(list.head.unwrap()).items, vec![2, 3, 7]);
+        assert_eq!(to_vec(&list), vec![2, 3, 7, 8, 9, 10, 11]);
+    }
+
+    #[test]
+    fn remove_takes_elements_from_a_full_neighbour() {
+        // Three full nodes: [0, 1, 2, 3], [4, 5, 6, 7] and [8, 9, 10, 11].
+        let mut list: UnrolledLinkedList<usize, 4> = (0..12).collect();
+        for value in [4, 5, 6] {
+            assert_eq!(list.remove(4), Some(value));
+        }
+        // Neither neighbour has room, so the middle node shares the next one's elements.
+        check(&list);
+        let middle = list.node(list.head.unwrap()).next.unwrap();
+        assert_eq!(list.node(middle).items, vec![7, 8]);
+        assert_eq!(to_vec(&list), vec![0, 1, 2, 3, 7, 8, 9, 10, 11]);
+    }
+
+    #[test]
+    fn pops_and_removes_keep_nodes_dense() {
+        let mut list: UnrolledLinkedList<usize, 6> = (0..600).collect();
+        let mut expected: Vec<usize> = (0..600).collect();
+        for (step, value) in scrambled(500).into_iter().enumerate() {
+            match step % 3 {
+                0 => assert_eq!(list.pop_front(), Some(expected.remove(0))),
+                1 => assert_eq!(list.pop_back(), expected.pop()),
+                _ => {
+                    let index = value % expected.len();
+                    assert_eq!(list.remove(index), Some(expected.remove(index)));
+                }
+            }
+            check(&list);
+        }
+        assert_eq!(to_vec(&list), expected);
+    }
+
+    #[test]
+    fn remove_matches_vec() {
+        let mut list: UnrolledLinkedList<usize, 6> = (0..500).collect();
+        let mut expected: Vec<usize> = (0..500).collect();
+        for value in scrambled(400) {
+            let index = value % expected.len();
+            assert_eq!(list.remove(index), Some(expected.remove(index)));
+        }
+        check(&list);
+        assert_eq!(to_vec(&list), expected);
+        // Merging keeps the nodes dense: at most one node per CAP / 2 elements, plus one.
+        let used = list.nodes.len() - list.free.len();
+        assert!(used <= list.len() / 3 + 1);
+    }
+
+    #[test]
+    fn get_mut_and_clear() {
+        let mut list: UnrolledLinkedList<usize> = (0..40).collect();
+        *list.get_mut(35).unwrap() = 0;
+        *list.get_mut(2).unwrap() = 0;
+        assert_eq!(list.iter().sum::<usize>(), (0..40).sum::<usize>() - 37);
+        list.clear();
+        assert!(list.is_empty());
+        assert_eq!(list.iter().next(), None);
+        list.push_back(1);
+        check(&list);
+    }
+
+    #[test]
+    fn iterators() {
+        let list: UnrolledLinkedList<usize, 3> = (0..10).collect();
+        let iter = list.iter();
+        assert_eq!(iter.len(), 10);
+        assert_eq!(
+            (&list).into_iter().skip(8).collect::<Vec<_>>(),
+            vec![&8, &9]
+        );
+        let mut into_iter = list.into_iter();
+        assert_eq!(into_iter.next_back(), Some(9));
+        assert_eq!(into_iter.next(), Some(0));
+        assert_eq!(into_iter.len(), 8);
+        assert_eq!(into_iter.rev().take(2).collect::<Vec<_>>(), vec![8, 7]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn insert_out_of_bounds() {
+        let mut list: UnrolledLinkedList<usize> = UnrolledLinkedList::new();
+        list.insert(1, 0);
+    }
+}